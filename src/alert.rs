@@ -0,0 +1,211 @@
+use std::{collections::HashMap, fmt, time::{Duration, Instant}};
+
+use crate::collect::Snapshot;
+
+/// What an `--alert` watches, GPU metrics are checked on every GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Cpu,
+    CpuTemp,
+    Gpu,
+    GpuTemp,
+    Ram,
+    Vram,
+}
+
+const METRICS: [(&str, Metric); 6] = [
+    ("cpu", Metric::Cpu),
+    ("cpu-temp", Metric::CpuTemp),
+    ("gpu", Metric::Gpu),
+    ("gpu-temp", Metric::GpuTemp),
+    ("ram", Metric::Ram),
+    ("vram", Metric::Vram),
+];
+
+/// Level an alert fires at, usage is always a percent, temps are in °C and memory can be either a percent or a size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    Percent(f32),
+    Celsius(u32),
+    Bytes(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alert {
+    pub metric: Metric,
+    pub threshold: Threshold,
+}
+
+impl Metric {
+    pub fn name(self) -> &'static str {
+        METRICS.iter().find(|&&(_, metric)| metric == self).map_or("", |&(name, _)| name)
+    }
+
+    /// Current value of every device the metric covers (one for CPU and RAM, one per GPU) in the unit of `threshold`
+    fn values(self, threshold: Threshold, snapshot: &Snapshot) -> Vec<Option<f32>> {
+        let memory = |used: Option<u64>, total: Option<u64>| match threshold {
+            Threshold::Bytes(_) => used.map(|used| used as f32),
+            _ => used.zip(total.filter(|&total| total > 0)).map(|(used, total)| used as f32 / total as f32 * 100.0),
+        };
+        let gpus = &snapshot.gpus;
+        match self {
+            Metric::Cpu => vec![Some(snapshot.cpu.usage)],
+            Metric::CpuTemp => vec![snapshot.cpu.temp_c.map(|temp| temp as f32)],
+            Metric::Gpu => gpus.iter().map(|gpu| gpu.usage.map(|usage| usage as f32)).collect(),
+            Metric::GpuTemp => gpus.iter().map(|gpu| gpu.temp_c.map(|temp| temp as f32)).collect(),
+            Metric::Ram => vec![memory(Some(snapshot.memory.ram_used), Some(snapshot.memory.ram_total))],
+            Metric::Vram => gpus.iter().map(|gpu| memory(gpu.vram_used, gpu.vram_total)).collect(),
+        }
+    }
+}
+
+impl Threshold {
+    fn value(self) -> f32 {
+        match self {
+            Threshold::Percent(percent) => percent,
+            Threshold::Celsius(celsius) => celsius as f32,
+            Threshold::Bytes(bytes) => bytes as f32,
+        }
+    }
+
+    /// How far below the threshold a value has to drop to clear the alert, so values hovering around it don't flap
+    fn margin(self) -> f32 {
+        match self {
+            Threshold::Percent(_) | Threshold::Celsius(_) => 3.0,
+            Threshold::Bytes(bytes) => bytes as f32 * 0.03,
+        }
+    }
+}
+
+/// Same form `parse_alert` reads
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.threshold {
+            Threshold::Percent(percent) => write!(f, "{}={percent}%", self.metric.name()),
+            Threshold::Celsius(celsius) => write!(f, "{}={celsius}", self.metric.name()),
+            Threshold::Bytes(bytes) => {
+                // a size when it reads back exactly, bytes otherwise
+                let size = crate::fmt::format_size(bytes, crate::fmt::Units::Binary);
+                let size = if crate::args::parse_size(&size) == Ok(bytes) { size } else { bytes.to_string() };
+                write!(f, "{}={size}", self.metric.name())
+            }
+        }
+    }
+}
+
+/// `cpu-temp=90`, `gpu=95%` or `ram=28G`, the `%` is optional for usage
+pub fn parse_alert(value: &str) -> Result<Alert, String> {
+    let names = || METRICS.map(|(name, _)| name).join(", ");
+    let (name, level) = value.split_once('=')
+        .ok_or_else(|| format!("invalid alert '{value}', expected <metric>=<threshold> like cpu-temp=90"))?;
+    let metric = METRICS.iter().find(|&&(n, _)| n == name).map(|&(_, metric)| metric)
+        .ok_or_else(|| format!("unknown alert metric '{name}', expected one of {}", names()))?;
+    let expected = match metric {
+        Metric::Cpu | Metric::Gpu => "a percent like 90 or 90%",
+        Metric::CpuTemp | Metric::GpuTemp => "°C like 85",
+        Metric::Ram | Metric::Vram => "a percent like 95% or a size like 28G",
+    };
+    let err = || format!("invalid threshold '{level}' for {name}, expected {expected}");
+    let percent = |num: &str| num.parse::<f32>().ok().filter(|percent| (0.0..=100.0).contains(percent)).map(Threshold::Percent).ok_or_else(err);
+    let threshold = match (metric, level.strip_suffix('%')) {
+        (Metric::Cpu | Metric::Gpu, num) => percent(num.unwrap_or(level))?,
+        (Metric::Ram | Metric::Vram, Some(num)) => percent(num)?,
+        (Metric::Ram | Metric::Vram, None) => Threshold::Bytes(crate::args::parse_size(level).map_err(|_| err())?),
+        (Metric::CpuTemp | Metric::GpuTemp, None) => Threshold::Celsius(level.parse().map_err(|_| err())?),
+        (Metric::CpuTemp | Metric::GpuTemp, Some(_)) => return Err(err()),
+    };
+    Ok(Alert { metric, threshold })
+}
+
+/// Share of its threshold a value has to reach to count as close to firing
+const WARNING_RATIO: f32 = 0.9;
+
+/// Alert that is currently firing, `device` is the GPU index for GPU metrics and 0 otherwise
+pub struct Firing {
+    pub alert: Alert,
+    pub device: usize,
+    pub active: Duration,
+}
+
+/// Tracks which `--alert`s are firing and since when
+pub struct Alerts {
+    alerts: Vec<Alert>,
+    /// keyed by alert index and device
+    since: HashMap<(usize, usize), Instant>,
+}
+
+impl Alerts {
+    pub fn new(alerts: Vec<Alert>) -> Self {
+        Self { alerts, since: HashMap::new() }
+    }
+
+    /// Checks every alert against `snapshot`, returns whether any of them started firing
+    pub fn update(&mut self, snapshot: &Snapshot) -> bool {
+        let now = Instant::now();
+        let mut started = false;
+        for (i, alert) in self.alerts.iter().enumerate() {
+            let threshold = alert.threshold.value();
+            let values = alert.metric.values(alert.threshold, snapshot);
+            // devices that went away clear their alerts
+            self.since.retain(|&(j, device), _| j != i || device < values.len());
+            for (device, value) in values.into_iter().enumerate() {
+                let firing = self.since.contains_key(&(i, device));
+                match value {
+                    Some(value) if !firing && value >= threshold => {
+                        self.since.insert((i, device), now);
+                        started = true;
+                    }
+                    // unreadable values keep the alert as it was
+                    Some(value) if firing && value < threshold - alert.threshold.margin() => {
+                        self.since.remove(&(i, device));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        started
+    }
+
+    /// Whether any value is within 10% of its threshold, firing or not
+    pub fn near(&self, snapshot: &Snapshot) -> bool {
+        self.alerts.iter().any(|alert| {
+            let warning = alert.threshold.value() * WARNING_RATIO;
+            alert.metric.values(alert.threshold, snapshot).into_iter().flatten().any(|value| value >= warning)
+        })
+    }
+
+    pub fn is_firing(&self, metric: Metric, device: usize) -> bool {
+        self.since.keys().any(|&(i, d)| d == device && self.alerts[i].metric == metric)
+    }
+
+    /// Firing alerts, longest running first
+    pub fn firing(&self) -> Vec<Firing> {
+        let mut firing: Vec<Firing> = self.since.iter()
+            .map(|(&(i, device), since)| Firing { alert: self.alerts[i], device, active: since.elapsed() })
+            .collect();
+        firing.sort_by_key(|firing| std::cmp::Reverse(firing.active));
+        firing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_thresholds() {
+        assert_eq!(parse_alert("cpu-temp=90"), Ok(Alert { metric: Metric::CpuTemp, threshold: Threshold::Celsius(90) }));
+        assert_eq!(parse_alert("gpu=80"), Ok(Alert { metric: Metric::Gpu, threshold: Threshold::Percent(80.0) }));
+        assert_eq!(parse_alert("gpu=80%"), Ok(Alert { metric: Metric::Gpu, threshold: Threshold::Percent(80.0) }));
+        assert_eq!(parse_alert("ram=95%"), Ok(Alert { metric: Metric::Ram, threshold: Threshold::Percent(95.0) }));
+        assert_eq!(parse_alert("vram=6G"), Ok(Alert { metric: Metric::Vram, threshold: Threshold::Bytes(6 << 30) }));
+    }
+
+    #[test]
+    fn parse_rejects_nonsense() {
+        for value in ["cpu-temp", "fan=50", "cpu-temp=90%", "cpu-temp=hot", "cpu=120", "gpu=-5%", "ram=lots", "ram=200%", "=90"] {
+            assert!(parse_alert(value).is_err(), "{value} should not parse");
+        }
+        assert_eq!(parse_alert("cpu-temp=90%"), Err("invalid threshold '90%' for cpu-temp, expected °C like 85".to_string()));
+    }
+}
@@ -0,0 +1,1040 @@
+use std::{iter::Peekable, time::Duration};
+
+use crate::{alert::{Alert, parse_alert}, collect::sensors::TempKind, config::{self, Entry, Value}, template::Template, theme::{self, Color, Preset, Role}};
+
+pub const USAGE: &str = "\
+htop alternative for GPU/CPU and all other hardware info
+
+Usage: hwtop [OPTIONS]
+       hwtop check [CHECKS]
+       hwtop get <key>... [OPTIONS]
+       hwtop record <file> [OPTIONS]
+       hwtop replay <file> [OPTIONS]
+
+Options:
+  --info           print hardware info and exit
+  --extra          show extra components and their temperatures, package power,
+                   voltage/current/power sensors, pressure stalls (PSI), context switch,
+                   interrupt and fork rates and connection counts
+  --plain          no ANSI colors, same as --color never, frames still refresh
+                   unless --once, one after another with a blank line between
+  --color <when>   auto (default), always or never, auto disables colors
+                   when stdout isn't a terminal or NO_COLOR is set
+  --theme <name>   default, mono, solarized or high-contrast, single colors are set in the
+                   config's [colors] table
+  --gradient       color bars on a smooth blue-cyan-magenta-red gradient, on by default
+                   when COLORTERM is truecolor or 24bit
+  --no-gradient    the four bar_* theme colors even on truecolor terminals
+  --ascii          draw bars, arrows and trees with ASCII for terminals and fonts without
+                   good Unicode, on by default when LC_ALL/LC_CTYPE/LANG isn't UTF-8
+  --no-ascii       Unicode glyphs whatever the locale says
+  --once           print once and exit
+  --count <n>      exit after n frames or CSV rows, 0 (default) keeps going
+  --waybar         waybar tooltip compatible print (implies --once --plain)
+  --json           print one JSON snapshot and exit (fields below)
+  --json-stream    print one JSON snapshot per line every refresh, what --remote reads
+  --statusline     print one compact line per refresh for status bars
+  --statusline-format <format>
+                   text (default) or json for --statusline, json is waybar's {text, tooltip, class}
+                   with class warning/critical from the --alert thresholds
+  --format <template>
+                   print one line per refresh with {key}s filled in, like
+                   '{cpu.usage:>3}% {cpu.temp}° | {gpu0.power_w}W | {net.rx_rate}', `:>5.1`
+                   sets alignment, width and decimals, `{{` is a literal brace
+  --list-keys      list the {key}s of --format
+  --compact        two lines for a small tmux pane: CPU, clock bars and RAM, then GPU,
+                   VRAM and network rates, without the key hint and footer
+  --no-footer      hide the dim line under the live view with the time, refresh interval,
+                   collection time and a spinner that moves with every refresh
+  --title          keep the terminal title on a summary like `hwtop: 23% 61° | gpu 4% 42°`
+                   for tmux's window list and taskbars, live view only
+  --csv <file>     append one CSV row per refresh to <file>, `-` for stdout
+  --record <file>  append every snapshot to <file> (JSON lines) while showing them as usual
+  --replay <file>  show a recording instead of this machine, at the pace it was recorded,
+                   no GPU driver needed
+  --speed <x>      replay x times faster, e.g. 10 or 0.5 (default 1)
+  --remote <user@host>
+                   also show another machine, read over `ssh user@host hwtop --json-stream`
+                   (hwtop has to be installed there), under its hostname, repeatable,
+                   reconnects when the connection drops
+  --iface <name>   show this network interface instead of the busiest one
+  --all-ifaces     one NETW row per interface, busiest first
+  --exclude-iface <pattern>
+                   hide interfaces matching a `*` glob, repeatable, on top of
+                   the defaults: lo, br-*, *veth*, *docker* (Loopback*,
+                   vEthernet*, isatap*, Teredo* on Windows)
+  --min-disk-size <size>
+                   hide disks smaller than this, e.g. 500M or 0 (default 8G)
+  --disk <name>    only show this disk, repeatable, `*` globs work
+  --exclude-disk <pattern>
+                   hide disks matching a `*` glob, repeatable
+  --sort-disks <key>
+                   order disk rows by name (default), size, used or io (read and written this
+                   session), the largest first, rows keep their place as disks fill up
+  --partitions     one row per partition instead of one per drive
+  --mounts         list mounted filesystems with their usage
+  --all-mounts     include tmpfs, overlay and squashfs mounts in --mounts
+  --disk-detail    add reads/writes per second and %util to the disk rows
+  --cores          one line per core with usage, clock and temperature,
+                   wrapped into columns on wide terminals
+  --procs [n]      table of the top n processes (default 5), with their VRAM on
+                   NVIDIA GPUs
+  --procs-sort <key>
+                   order the process table by cpu (default), mem or disk
+  --procs-hide-kernel
+                   leave kernel threads out of the process table
+  --gpu-procs      list the processes running on each NVIDIA GPU (also with --extra)
+  --graphs         network and disk rate sparklines, each scaled to its own peak
+  --graph          scrolling braille plot of CPU, GPU and RAM usage
+  --graph-height <rows>
+                   rows of the --graph plot (default 4)
+  --history <s>    seconds of CPU/GPU usage drawn after their rows (default 30, 0 hides it)
+  --no-peaks       no peak markers, by default the RAM/VRAM bars mark the highest usage of
+                   the last seconds and CORE/FREQ bars take its color, like a level meter
+  --peak-hold <s>  seconds a peak is held before it falls (default 2)
+  --peak-decay <percent>
+                   how fast a peak falls after that, in percent of the bar per second (default 50)
+  --smooth <alpha> average per-core and GPU usage and network and disk rates across frames,
+                   0.3 smooths a lot, 1 (default) draws every frame as measured,
+                   JSON, CSV and recordings always keep the measured values
+  --alert <metric>=<threshold>
+                   highlight a row while cpu, gpu (usage %), cpu-temp, gpu-temp (°C),
+                   ram or vram (% or size like 28G) is at or above the threshold,
+                   repeatable, e.g. --alert cpu-temp=90 --alert ram=95%
+  --bell           ring the terminal bell when an alert fires, in the live view only
+  --summary        print min/avg/max of CPU, GPU and memory and the network and
+                   disk totals and CPU throttle events of the session on exit
+  --kwh-price <price>
+                   price of a kWh, adds what the CPU and GPU energy of the session
+                   cost to --summary, like 0.30
+  --hide <sections>
+                   comma separated sections to leave out: cpu, mem, vram, cores, freq,
+                   coretemp, clocks (GPU), fans, pcie, net, disks, extra
+  --only <sections>
+                   show just these sections, can't be combined with --hide,
+                   listing extra turns on --extra
+  --width <cols>   lay out for this many columns instead of the terminal width
+  --bar-width <cells>
+                   cells of the RAM and VRAM bars, by default 6 to 14 by the width
+  --si             1000-based sizes instead of 1024-based
+  --fahrenheit     show temperatures in °F
+  --no-gpu         skip all GPU queries, even when a GPU is present
+  --interval <s>   seconds between refreshes, e.g. 0.5 or 2 (default 1)
+  --write-config   write the current options to the config file and exit
+  --debug-timing   add a row with how long collecting and drawing the last frame took
+  -h, --help       print this help
+  -V, --version    print version
+
+Switches are turned back off by their --no- form, --no-extra undoes extra = true of
+the config
+
+Keys in the live view: q/Esc quit, p pause, e toggle --extra, +/- halve/double the
+interval, g cycle through GPUs, 1-6 toggle cpu (with cores, freq and coretemp), mem
+(with vram), clocks, fans (with pcie), net and disks, h hide the key hint,
+? help and detected hardware
+
+Get: sample once and print the raw value of each --format key on its own line, sizes
+in bytes, rates per second, temps as numbers, exit 1 when a reading is missing and 2
+when a key doesn't exist, e.g. hwtop get cpu.temp gpu0.vram_used
+
+Checks: sample once, print each result and exit 0 when all pass, 1 when one fails
+or 2 when a value can't be read, temps are in °C
+  --max-cpu-temp <temp>
+  --max-gpu-temp <temp>   checked on every GPU
+  --min-free-ram <size>
+  --min-free-disk <mount or device>=<size>
+                          e.g. /=10G or nvme0n1p2=50G, repeatable
+
+Config: ~/.config/hwtop/config.toml or $XDG_CONFIG_HOME/hwtop/config.toml sets the same options,
+keys are the flag names (`interval = 0.5`, `cores = true`, `alert = [\"cpu-temp=90\"]`) and flags
+given on the command line override them, the [colors] table sets theme colors by role
+(accent_cpu/gpu/ram/net, heading, info, rx, tx, alert, dim, bar_cold/cool/warm/hot) to #rrggbb,
+a 256 color index, a name like bright-cyan or none, hex colors need COLORTERM=truecolor
+or are matched to the 256 color palette
+
+Bare words (info, extra, plain, once, waybar) are accepted as aliases.
+
+JSON fields: sizes in bytes, rates per second (bps/pps), temps in °C, clocks in MHz,
+usage in percent, values that failed to read are null
+  host         hostname, os, os_version, kernel, boot_time (seconds since the Unix epoch)
+  load         [1, 5, 15] minute load averages, uptime_s seconds since boot
+  activity     context_switches, interrupts, forks per second, null on the first frame
+  cpu          usage, temp_c, cores[].{usage, freq_mhz, max_freq_mhz, temp_c}, core_temps_c[],
+               power_w, power_limit_w, packages[].{name, power_w, limit_w}, governor, turbo,
+               throttle.{active, events, heuristic}
+  memory       ram_used, ram_total, ram_cached, ram_dirty, swap_used, swap_total, swap_in_bps,
+               swap_out_bps, swapping, zram and zswap.{original, compressed} (null without them)
+               numa[].{node, used, total, cached} (empty with a single node)
+               hugepages[].{size, total, free} (page size in bytes and page counts)
+  psi          cpu, memory, io, each {some, full} percent of the last 10s stalled, null
+               without PSI
+  gpus[]       name, usage, mem_usage, temp_c, mem_temp_c, hotspot_c, power_w, power_limit_w,
+               power_default_limit_w, vram_used, vram_total, bar1_used, bar1_total,
+               clocks[].{name, mhz, max_mhz}, fans[].{percent, rpm},
+               pcie.{rx_bps, tx_bps, max_bps, link_bps, max_link.{generation, width}, link.{...}},
+               ecc.{enabled, corrected, uncorrected} (volatile counts, null without ECC),
+               nvlinks[].{link, active, rx_bps, tx_bps}, encoder, decoder,
+               processes[].{pid, name, kind (compute|graphics), vram_used, sm}
+  fans[]       label, rpm, max_rpm, percent (pwm duty or of max_rpm), hwmon fans with CPU fans
+               first, GPU fans are under gpus[]
+  networks[]   name, rx_bps, tx_bps, rx_pps, tx_pps, total_rx, total_tx, session_rx, session_tx,
+               errors (errors and drops in the last frame), wifi.{ssid, signal_dbm, bitrate_mbps}
+               link_mbps, duplex, link_downgraded
+  connections  established, listening (TCP), udp sockets, null when /proc/net isn't readable
+  disks[]      name, used, total, read_bps, write_bps, total_read, total_written, temp_c,
+               read_iops, write_iops, util
+  mounts[]     mount_point, fs, device, used, total
+  processes[]  pid, name, cpu, rss, virt, read_bps, write_bps, kernel, vram (summed over GPUs),
+               exited (only on a GPU anymore) (only with --procs)
+  components[] name, kind (cpu|gpu|nvme|ambient|other), temps_c[]
+  rails[]      chip, label, unit (volts|amps|watts), value (labeled hwmon sensors)
+  sensors_stale  fans, rails and disk temps are from an earlier frame, reading them timed out
+  resumed_after_s  seconds since the previous frame when the machine was suspended in between,
+               its rates are 0 or null then, null otherwise
+  timing       collect_ms, gpus_ms, connections_ms, hwmon_ms (the last three null when
+               late), only with --debug-timing";
+
+/// Interfaces hidden before any `--exclude-iface`, loopback, bridges and container links
+#[cfg(not(windows))]
+const DEFAULT_EXCLUDED_IFACES: [&str; 4] = ["lo", "br-*", "*veth*", "*docker*"];
+/// Windows names adapters after what they are, the loopback, Hyper-V/WSL switches and IPv6 tunnels
+#[cfg(windows)]
+const DEFAULT_EXCLUDED_IFACES: [&str; 4] = ["Loopback*", "vEthernet*", "isatap*", "Teredo*"];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Groups of rows `--hide` and `--only` pick from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    /// load and CPU usage
+    Cpu,
+    Mem,
+    Vram,
+    Cores,
+    Freq,
+    CoreTemp,
+    Clocks,
+    /// GPU and system fans
+    Fans,
+    Pcie,
+    Net,
+    /// disks and --mounts
+    Disks,
+    /// --extra components, package power, rails, PSI, kernel activity and connections
+    Extra,
+}
+
+impl Section {
+    pub const ALL: [Section; 12] = [
+        Section::Cpu, Section::Mem, Section::Vram, Section::Cores, Section::Freq, Section::CoreTemp,
+        Section::Clocks, Section::Fans, Section::Pcie, Section::Net, Section::Disks, Section::Extra,
+    ];
+
+    /// What the number keys 1-6 toggle, in key order
+    pub const KEYS: [&[Section]; 6] = [
+        &[Section::Cpu, Section::Cores, Section::Freq, Section::CoreTemp],
+        &[Section::Mem, Section::Vram],
+        &[Section::Clocks],
+        &[Section::Fans, Section::Pcie],
+        &[Section::Net],
+        &[Section::Disks],
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Cpu => "cpu",
+            Section::Mem => "mem",
+            Section::Vram => "vram",
+            Section::Cores => "cores",
+            Section::Freq => "freq",
+            Section::CoreTemp => "coretemp",
+            Section::Clocks => "clocks",
+            Section::Fans => "fans",
+            Section::Pcie => "pcie",
+            Section::Net => "net",
+            Section::Disks => "disks",
+            Section::Extra => "extra",
+        }
+    }
+}
+
+/// Output of `--statusline`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum StatuslineFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Process table order, ties break by PID so rows keep their place between frames
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ProcSort {
+    #[default]
+    Cpu,
+    Mem,
+    Disk,
+}
+
+impl ProcSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            ProcSort::Cpu => "cpu",
+            ProcSort::Mem => "mem",
+            ProcSort::Disk => "disk",
+        }
+    }
+}
+
+/// Disk row order, each disk is ranked by what it was when it first showed up so rows don't trade places
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DiskSort {
+    #[default]
+    Name,
+    Size,
+    Used,
+    /// bytes read and written this session
+    Io,
+}
+
+impl DiskSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            DiskSort::Name => "name",
+            DiskSort::Size => "size",
+            DiskSort::Used => "used",
+            DiskSort::Io => "io",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub info: bool,
+    pub extra: bool,
+    pub color: ColorMode,
+    pub theme: Preset,
+    /// `[colors]` of the config
+    pub colors: Vec<(Role, Color)>,
+    /// `[thresholds]` of the config, °C where a kind of temperature turns warm, hot and critical
+    pub thresholds: Vec<(TempKind, [u32; 3])>,
+    /// `None` picks gradient bars when the terminal has 24-bit color
+    pub gradient: Option<bool>,
+    /// `None` picks ASCII glyphs when the locale isn't UTF-8
+    pub ascii: Option<bool>,
+    pub once: bool,
+    /// frames before exiting, 0 for no limit
+    pub count: usize,
+    pub waybar: bool,
+    pub json: bool,
+    pub json_stream: bool,
+    pub statusline: bool,
+    pub statusline_format: StatuslineFormat,
+    pub format: Option<Template>,
+    pub list_keys: bool,
+    /// keys of `hwtop get`
+    pub get: Vec<String>,
+    pub compact: bool,
+    /// time, interval and a spinner under the live view
+    pub footer: bool,
+    pub title: bool,
+    pub csv: Option<String>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    /// replay pace, 2 is twice as fast as recorded
+    pub speed: f64,
+    /// `user@host` of every `--remote`
+    pub remotes: Vec<String>,
+    pub iface: Option<String>,
+    pub all_ifaces: bool,
+    pub exclude_ifaces: Vec<String>,
+    pub min_disk_size: u64,
+    pub disks: Vec<String>,
+    pub exclude_disks: Vec<String>,
+    pub disk_detail: bool,
+    pub sort_disks: DiskSort,
+    /// partitions get rows of their own instead of adding up to their drive's
+    pub partitions: bool,
+    pub cores: bool,
+    pub mounts: bool,
+    pub all_mounts: bool,
+    pub procs: Option<usize>,
+    pub procs_sort: ProcSort,
+    pub procs_hide_kernel: bool,
+    pub gpu_procs: bool,
+    pub graphs: bool,
+    pub graph: bool,
+    pub graph_height: usize,
+    pub history: usize,
+    /// peak hold markers on the memory bars and core rows
+    pub peaks: bool,
+    pub peak_hold: Duration,
+    /// percent of the bar a peak falls per second once `peak_hold` is over
+    pub peak_decay: f64,
+    /// weight of the newest frame in the averages of `--smooth`, 1 turns it off
+    pub smooth: f64,
+    pub width: Option<usize>,
+    /// `None` sizes the bars by the width
+    pub bar_width: Option<u32>,
+    pub hide: Vec<Section>,
+    pub alerts: Vec<Alert>,
+    pub bell: bool,
+    pub summary: bool,
+    /// in whatever currency, the summary's cost is in the same
+    pub kwh_price: Option<f64>,
+    pub check: bool,
+    pub max_cpu_temp: Option<u32>,
+    pub max_gpu_temp: Option<u32>,
+    pub min_free_ram: Option<u64>,
+    pub min_free_disks: Vec<(String, u64)>,
+    pub si: bool,
+    pub fahrenheit: bool,
+    pub no_gpu: bool,
+    pub interval: Duration,
+    pub write_config: bool,
+    pub debug_timing: bool,
+    pub help: bool,
+    pub version: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            info: false,
+            extra: false,
+            color: ColorMode::Auto,
+            theme: Preset::Default,
+            colors: vec![],
+            thresholds: vec![],
+            gradient: None,
+            ascii: None,
+            once: false,
+            count: 0,
+            waybar: false,
+            json: false,
+            json_stream: false,
+            statusline: false,
+            statusline_format: StatuslineFormat::Text,
+            format: None,
+            list_keys: false,
+            get: vec![],
+            compact: false,
+            footer: true,
+            title: false,
+            csv: None,
+            record: None,
+            replay: None,
+            speed: 1.0,
+            remotes: vec![],
+            iface: None,
+            all_ifaces: false,
+            exclude_ifaces: DEFAULT_EXCLUDED_IFACES.map(String::from).to_vec(),
+            min_disk_size: 8 << 30,
+            disks: vec![],
+            exclude_disks: vec![],
+            disk_detail: false,
+            sort_disks: DiskSort::Name,
+            partitions: false,
+            cores: false,
+            mounts: false,
+            all_mounts: false,
+            procs: None,
+            procs_sort: ProcSort::Cpu,
+            procs_hide_kernel: false,
+            gpu_procs: false,
+            graphs: false,
+            graph: false,
+            graph_height: 4,
+            history: 30,
+            peaks: true,
+            peak_hold: Duration::from_secs(2),
+            peak_decay: 50.0,
+            smooth: 1.0,
+            width: None,
+            bar_width: None,
+            hide: vec![],
+            alerts: vec![],
+            bell: false,
+            summary: false,
+            kwh_price: None,
+            check: false,
+            max_cpu_temp: None,
+            max_gpu_temp: None,
+            min_free_ram: None,
+            min_free_disks: vec![],
+            si: false,
+            fahrenheit: false,
+            no_gpu: false,
+            interval: Duration::from_secs(1),
+            write_config: false,
+            debug_timing: false,
+            help: false,
+            version: false,
+        }
+    }
+}
+
+impl Args {
+    /// Applies command line arguments on top of whatever is already set, so they override the config
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), String> {
+        let mut args = args.into_iter().peekable();
+        let (mut hide, mut only) = (false, false);
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            hide |= name == "--hide";
+            only |= name == "--only";
+            if !self.set(name, inline, &mut args)? {
+                return Err(format!("unknown argument '{name}'"));
+            }
+        }
+        if self.check && self.max_cpu_temp.is_none() && self.max_gpu_temp.is_none() && self.min_free_ram.is_none() && self.min_free_disks.is_empty() {
+            return Err("'check' needs at least one of --max-cpu-temp, --max-gpu-temp, --min-free-ram or --min-free-disk".to_string());
+        }
+        if hide && only {
+            return Err("'--hide' and '--only' can't be combined".to_string());
+        }
+        if self.replay.is_some() && (self.record.is_some() || self.info) {
+            return Err(format!("'--replay' can't be combined with '{}'", if self.info { "--info" } else { "--record" }));
+        }
+        if self.format.is_some() && (self.statusline || self.json || self.waybar) {
+            return Err("'--format' can't be combined with '--statusline', '--json' or '--waybar', it prints its own line".to_string());
+        }
+        // remote machines are only drawn in the frames, the other outputs are this machine's
+        let solo = [(self.once, "--once"), (self.json, "--json"), (self.json_stream, "--json-stream"), (self.statusline, "--statusline"), (self.format.is_some(), "--format"),
+            (self.waybar, "--waybar"), (self.csv.is_some(), "--csv"), (self.record.is_some(), "--record"), (self.replay.is_some(), "--replay")];
+        if !self.remotes.is_empty() && let Some((_, flag)) = solo.iter().find(|(on, _)| *on) {
+            return Err(format!("'--remote' can't be combined with '{flag}'"));
+        }
+        if self.once && self.count > 0 {
+            return Err("'--once' and '--count' can't be combined, --once already stops after one frame".to_string());
+        }
+        Ok(())
+    }
+
+    /// Applies config file entries, keys are flag names like `min_disk_size` or `min-disk-size`,
+    /// `true` turns a switch on and arrays repeat the flag, returns a warning for every unknown key
+    pub fn apply_config(&mut self, entries: &[Entry]) -> Result<Vec<String>, String> {
+        let mut warnings = vec![];
+        for entry in entries {
+            if let Some(role) = entry.key.strip_prefix("colors.") {
+                let Some(role) = theme::parse_role(role) else {
+                    warnings.push(format!("unknown color '{role}' on line {}", entry.line));
+                    continue;
+                };
+                let color = match &entry.value {
+                    Value::Text(text) => theme::parse_color(text),
+                    _ => Err(format!("'{}' expects a color string like \"#7aa2f7\" or \"196\"", entry.key)),
+                };
+                self.colors.push((role, color.map_err(|e| format!("line {}: {e}", entry.line))?));
+                continue;
+            }
+            if let Some(kind) = entry.key.strip_prefix("thresholds.") {
+                let Some(kind) = TempKind::ALL.into_iter().find(|known| known.name() == kind) else {
+                    warnings.push(format!("unknown temperature '{kind}' on line {}", entry.line));
+                    continue;
+                };
+                let thresholds = match &entry.value {
+                    Value::List(items) => items.iter().map(|item| item.parse().ok()).collect::<Option<Vec<u32>>>().and_then(|items| <[u32; 3]>::try_from(items).ok()),
+                    _ => None,
+                };
+                let thresholds = thresholds.filter(|[warm, hot, critical]| warm <= hot && hot <= critical)
+                    .ok_or_else(|| format!("line {}: '{}' expects three rising °C like [50, 70, 85]", entry.line, entry.key))?;
+                self.thresholds.retain(|&(known, _)| known != kind);
+                self.thresholds.push((kind, thresholds));
+                continue;
+            }
+            let mut name = format!("--{}", entry.key.replace('_', "-"));
+            let values = match &entry.value {
+                Value::Bool(true) => vec![None],
+                // `false` turns on the --no- flag of options that have one
+                Value::Bool(false) if matches!(self.clone().set(&format!("--no-{}", &name[2..]), None, &mut std::iter::empty().peekable()), Ok(true)) => {
+                    name = format!("--no-{}", &name[2..]);
+                    vec![None]
+                }
+                Value::Bool(false) => vec![],
+                Value::Text(text) => vec![Some(text.clone())],
+                Value::List(items) => items.iter().cloned().map(Some).collect(),
+            };
+            let mut known = true;
+            if values.is_empty() {
+                // `false` sets nothing, a throwaway copy still tells whether the key exists
+                known = !matches!(self.clone().set(&name, None, &mut std::iter::empty().peekable()), Ok(false));
+            }
+            for value in values {
+                known &= self.set(&name, value, &mut std::iter::empty().peekable()).map_err(|e| format!("line {}: {e}", entry.line))?;
+            }
+            if !known {
+                warnings.push(format!("unknown key '{}' on line {}", entry.key, entry.line));
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Sets one option, `false` when there's no option called `name`
+    fn set(&mut self, name: &str, inline: Option<String>, rest: &mut Peekable<impl Iterator<Item = String>>) -> Result<bool, String> {
+        let mut value = || inline.clone().or_else(|| rest.next()).ok_or(format!("'{name}' expects a value"));
+        match name {
+            "--interval" => self.interval = parse_interval(&value()?)?,
+            "--statusline-format" => self.statusline_format = parse_statusline_format(&value()?)?,
+            // every key up to the first option
+            "get" => {
+                self.get.extend(std::iter::from_fn(|| rest.next_if(|next| !next.starts_with('-'))));
+                if self.get.is_empty() {
+                    return Err("'get' expects keys like cpu.temp, hwtop --list-keys lists them".to_string());
+                }
+            }
+            "--format" => self.format = Some(Template::parse(&value()?)?),
+            "--csv" => self.csv = Some(value()?),
+            "--record" | "record" => self.record = Some(value()?),
+            "--replay" | "replay" => self.replay = Some(value()?),
+            "--speed" => self.speed = value()?.parse().ok().filter(|&speed: &f64| speed > 0.0 && speed.is_finite()).ok_or("'--speed' expects a positive factor like 10 or 0.5")?,
+            "--remote" => self.remotes.push(value()?),
+            "--iface" => self.iface = Some(value()?),
+            "--exclude-iface" => self.exclude_ifaces.push(value()?),
+            // the count is optional, so the next argument is only taken when it's a number
+            "--procs" => self.procs = Some(match inline.or_else(|| rest.next_if(|next| next.parse::<usize>().is_ok())) {
+                Some(count) => count.parse().map_err(|_| format!("invalid process count '{count}'"))?,
+                None => 5,
+            }),
+            "--min-disk-size" => self.min_disk_size = parse_size(&value()?)?,
+            "--disk" => self.disks.push(value()?),
+            "--exclude-disk" => self.exclude_disks.push(value()?),
+            "--graph-height" => self.graph_height = value()?.parse().ok().filter(|&rows| rows > 0).ok_or("'--graph-height' expects a positive number of rows")?,
+            "--count" => self.count = value()?.parse().map_err(|_| "'--count' expects a number of frames")?,
+            "--history" => self.history = value()?.parse().map_err(|_| "'--history' expects a number of seconds")?,
+            "--peak-hold" => self.peak_hold = parse_interval(&value()?)?,
+            "--peak-decay" => self.peak_decay = value()?.parse().ok().filter(|&decay: &f64| decay >= 0.0 && decay.is_finite()).ok_or("'--peak-decay' expects percent per second like 50")?,
+            "--kwh-price" => self.kwh_price = Some(value()?.parse().ok().filter(|&price: &f64| price >= 0.0 && price.is_finite()).ok_or("'--kwh-price' expects a price like 0.30")?),
+            "--smooth" => self.smooth = value()?.parse().ok().filter(|&alpha: &f64| alpha > 0.0 && alpha <= 1.0).ok_or("'--smooth' expects an alpha above 0 and up to 1, like 0.3")?,
+            "--bar-width" => self.bar_width = Some(value()?.parse().ok().filter(|&cells| cells > 0).ok_or("'--bar-width' expects a positive number of cells")?),
+            "--width" => self.width = Some(value()?.parse().ok().filter(|&cols| cols > 0).ok_or("'--width' expects a positive number of columns")?),
+            "--max-cpu-temp" => self.max_cpu_temp = Some(value()?.parse().map_err(|_| "'--max-cpu-temp' expects °C like 85")?),
+            "--max-gpu-temp" => self.max_gpu_temp = Some(value()?.parse().map_err(|_| "'--max-gpu-temp' expects °C like 80")?),
+            "--min-free-ram" => self.min_free_ram = Some(parse_size(&value()?)?),
+            "--min-free-disk" => self.min_free_disks.push(parse_free_disk(&value()?)?),
+            "--alert" => self.alerts.push(parse_alert(&value()?)?),
+            "--hide" => self.hide = parse_sections(&value()?)?,
+            // stored as the sections it leaves out, so it's written to the config as `hide`
+            "--only" => {
+                let only = parse_sections(&value()?)?;
+                self.extra |= only.contains(&Section::Extra);
+                self.hide = Section::ALL.into_iter().filter(|section| !only.contains(section)).collect();
+            }
+            "--procs-sort" => self.procs_sort = parse_proc_sort(&value()?)?,
+            "--sort-disks" => self.sort_disks = parse_disk_sort(&value()?)?,
+            "--color" => self.color = parse_color(&value()?)?,
+            "--theme" => self.theme = theme::parse_preset(&value()?)?,
+            "--gradient" | "--no-gradient" => match inline {
+                Some(_) => return Err(format!("'{name}' doesn't take a value")),
+                None => self.gradient = Some(name == "--gradient"),
+            },
+            "--footer" | "--no-footer" => match inline {
+                Some(_) => return Err(format!("'{name}' doesn't take a value")),
+                None => self.footer = name == "--footer",
+            },
+            "--peaks" | "--no-peaks" => match inline {
+                Some(_) => return Err(format!("'{name}' doesn't take a value")),
+                None => self.peaks = name == "--peaks",
+            },
+            "--ascii" | "--no-ascii" => match inline {
+                Some(_) => return Err(format!("'{name}' doesn't take a value")),
+                None => self.ascii = Some(name == "--ascii"),
+            },
+            "--plain" | "plain" => self.color = ColorMode::Never,
+            _ => {
+                // `--no-extra` turns off what a config turned on, `--no-gpu` is a switch of its own
+                let (switch, on) = match name.strip_prefix("--no-") {
+                    Some(switch) if self.switch(name).is_none() => (format!("--{switch}"), false),
+                    _ => (name.to_string(), true),
+                };
+                let Some(flag) = self.switch(&switch) else {
+                    return Ok(false);
+                };
+                if inline.is_some() {
+                    return Err(format!("'{name}' doesn't take a value"));
+                }
+                *flag = on;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Every option a config file can hold with its current value, for `--write-config`
+    pub fn to_config(&self) -> String {
+        let quote = |value: &str| config::quote(value);
+        let list = |items: &[String]| format!("[{}]", items.iter().map(|item| quote(item)).collect::<Vec<_>>().join(", "));
+        // sizes stay readable unless that would round them
+        let size = |bytes: u64| {
+            let short = crate::fmt::format_size(bytes, crate::fmt::Units::Binary);
+            if parse_size(&short) == Ok(bytes) { quote(&short) } else { bytes.to_string() }
+        };
+        let unset = |key: &str, value: Option<String>, example: &str| value.map_or(format!("# {key} = {example}"), |value| format!("{key} = {value}"));
+        let color = match self.color {
+            ColorMode::Auto => "auto",
+            ColorMode::Always => "always",
+            ColorMode::Never => "never",
+        };
+        let statusline_format = match self.statusline_format {
+            StatuslineFormat::Text => "text",
+            StatuslineFormat::Json => "json",
+        };
+        let default_ifaces = Self::default().exclude_ifaces.len();
+        let hide: Vec<&str> = self.hide.iter().map(|section| section.name()).collect();
+        let alerts: Vec<String> = self.alerts.iter().map(Alert::to_string).collect();
+        [
+            "# hwtop config, keys are the command line flags (hwtop --help) and the command line overrides them".to_string(),
+            format!("interval = {}", self.interval.as_secs_f64()),
+            format!("color = {}", quote(color)),
+            format!("theme = {}", quote(self.theme.name())),
+            unset("gradient", self.gradient.map(|gradient| gradient.to_string()), "true # picked by COLORTERM when unset"),
+            unset("ascii", self.ascii.map(|ascii| ascii.to_string()), "true # picked by the locale when unset"),
+            format!("si = {}", self.si),
+            format!("fahrenheit = {}", self.fahrenheit),
+            unset("width", self.width.map(|width| width.to_string()), "100"),
+            unset("bar_width", self.bar_width.map(|cells| cells.to_string()), "10"),
+            format!("hide = {}", quote(&hide.join(","))),
+            format!("extra = {}", self.extra),
+            format!("cores = {}", self.cores),
+            format!("history = {}", self.history),
+            format!("peaks = {}", self.peaks),
+            format!("peak_hold = {}", self.peak_hold.as_secs_f64()),
+            format!("peak_decay = {}", self.peak_decay),
+            format!("smooth = {}", self.smooth),
+            format!("graphs = {}", self.graphs),
+            format!("graph = {}", self.graph),
+            format!("graph_height = {}", self.graph_height),
+            unset("iface", self.iface.as_deref().map(quote), "\"eth0\""),
+            format!("remote = {}", list(&self.remotes)),
+            format!("all_ifaces = {}", self.all_ifaces),
+            "# on top of lo, br-*, *veth* and *docker*".to_string(),
+            format!("exclude_iface = {}", list(self.exclude_ifaces.get(default_ifaces..).unwrap_or_default())),
+            format!("min_disk_size = {}", size(self.min_disk_size)),
+            format!("disk = {}", list(&self.disks)),
+            format!("exclude_disk = {}", list(&self.exclude_disks)),
+            format!("disk_detail = {}", self.disk_detail),
+            format!("sort_disks = {}", quote(self.sort_disks.name())),
+            format!("partitions = {}", self.partitions),
+            format!("mounts = {}", self.mounts),
+            format!("all_mounts = {}", self.all_mounts),
+            unset("procs", self.procs.map(|count| count.to_string()), "5"),
+            format!("procs_sort = {}", quote(self.procs_sort.name())),
+            format!("procs_hide_kernel = {}", self.procs_hide_kernel),
+            format!("gpu_procs = {}", self.gpu_procs),
+            format!("no_gpu = {}", self.no_gpu),
+            format!("alert = {}", list(&alerts)),
+            format!("bell = {}", self.bell),
+            format!("compact = {}", self.compact),
+            format!("footer = {}", self.footer),
+            format!("title = {}", self.title),
+            format!("summary = {}", self.summary),
+            unset("kwh_price", self.kwh_price.map(|price| price.to_string()), "0.30"),
+            format!("statusline_format = {}", quote(statusline_format)),
+            unset("format", self.format.as_ref().map(|template| quote(template.text())), "\"{cpu.usage}% {cpu.temp}°\""),
+            String::new(),
+            format!("# theme colors of {}, as #rrggbb, a 256 color index, a name like \"bright-cyan\" or \"none\"", theme::ROLES.map(|(name, _)| name).join(", ")),
+            "[colors]".to_string(),
+        ].into_iter()
+            .chain(self.colors.iter().map(|(role, color)| format!("{} = {}", role.name(), quote(&color.to_string()))))
+            .chain(self.colors.is_empty().then(|| "# accent_cpu = \"#7aa2f7\"".to_string()))
+            .chain([
+                String::new(),
+                format!("# °C where {} temperatures turn warm, hot and critical", TempKind::ALL.map(TempKind::name).join(", ")),
+                "[thresholds]".to_string(),
+            ])
+            .chain(self.thresholds.iter().map(|(kind, [warm, hot, critical])| format!("{} = [{warm}, {hot}, {critical}]", kind.name())))
+            .chain(self.thresholds.is_empty().then(|| "# cpu = [50, 70, 85]".to_string()))
+            .collect::<Vec<_>>().join("\n") + "\n"
+    }
+
+    /// Options that change what a snapshot holds, for the `hwtop --json-stream` of each `--remote`
+    #[must_use]
+    pub fn remote_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = [
+            ("--interval", self.interval.as_secs_f64().to_string()),
+            ("--min-disk-size", self.min_disk_size.to_string()),
+            ("--sort-disks", self.sort_disks.name().to_string()),
+            ("--procs-sort", self.procs_sort.name().to_string()),
+        ].into_iter().flat_map(|(flag, value)| [flag.to_string(), value]).collect();
+        if let Some(count) = self.procs {
+            args.extend(["--procs".to_string(), count.to_string()]);
+        }
+        if let Some(iface) = &self.iface {
+            args.extend(["--iface".to_string(), iface.clone()]);
+        }
+        // the remote has the default exclusions of its own system
+        let default_ifaces = Self::default().exclude_ifaces.len();
+        let lists = [("--exclude-iface", self.exclude_ifaces.get(default_ifaces..).unwrap_or_default()), ("--disk", &self.disks), ("--exclude-disk", &self.exclude_disks)];
+        args.extend(lists.into_iter().flat_map(|(flag, values)| values.iter().flat_map(move |value| [flag.to_string(), value.clone()])));
+        let switches = [(self.extra, "--extra"), (self.gpu_procs, "--gpu-procs"), (self.all_mounts, "--all-mounts"), (self.partitions, "--partitions"), (self.procs_hide_kernel, "--procs-hide-kernel"),
+            (self.no_gpu, "--no-gpu"), (self.debug_timing, "--debug-timing")];
+        args.extend(switches.into_iter().filter(|&(on, _)| on).map(|(_, flag)| flag.to_string()));
+        args
+    }
+
+    fn switch(&mut self, name: &str) -> Option<&mut bool> {
+        Some(match name {
+            "--info" | "info" => &mut self.info,
+            "--extra" | "extra" => &mut self.extra,
+            "--once" | "once" => &mut self.once,
+            "--waybar" | "waybar" => &mut self.waybar,
+            "--json" => &mut self.json,
+            "--json-stream" => &mut self.json_stream,
+            "--statusline" => &mut self.statusline,
+            "--compact" => &mut self.compact,
+            "--all-ifaces" => &mut self.all_ifaces,
+            "--disk-detail" => &mut self.disk_detail,
+            "--partitions" => &mut self.partitions,
+            "--cores" => &mut self.cores,
+            "--graphs" => &mut self.graphs,
+            "--graph" => &mut self.graph,
+            "--mounts" => &mut self.mounts,
+            "--all-mounts" => &mut self.all_mounts,
+            "--procs-hide-kernel" => &mut self.procs_hide_kernel,
+            "--gpu-procs" => &mut self.gpu_procs,
+            "--bell" => &mut self.bell,
+            "--title" => &mut self.title,
+            "--list-keys" => &mut self.list_keys,
+            "--summary" => &mut self.summary,
+            "check" => &mut self.check,
+            "--si" => &mut self.si,
+            "--fahrenheit" => &mut self.fahrenheit,
+            "--no-gpu" => &mut self.no_gpu,
+            "--write-config" => &mut self.write_config,
+            "--debug-timing" => &mut self.debug_timing,
+            "-h" | "--help" | "help" => &mut self.help,
+            "-V" | "--version" => &mut self.version,
+            _ => return None,
+        })
+    }
+}
+
+/// `/=10G` or `sda1=50G`, the size is split off the last `=` so mount points may contain one
+fn parse_free_disk(value: &str) -> Result<(String, u64), String> {
+    match value.rsplit_once('=') {
+        Some((target, size)) if !target.is_empty() => Ok((target.to_string(), parse_size(size)?)),
+        _ => Err(format!("invalid disk check '{value}', expected <mount point or device>=<size> like /=10G")),
+    }
+}
+
+fn parse_color(value: &str) -> Result<ColorMode, String> {
+    match value {
+        "auto" => Ok(ColorMode::Auto),
+        "always" => Ok(ColorMode::Always),
+        "never" => Ok(ColorMode::Never),
+        _ => Err(format!("invalid color mode '{value}', expected auto, always or never")),
+    }
+}
+
+fn parse_sections(value: &str) -> Result<Vec<Section>, String> {
+    value.split(',').filter(|name| !name.is_empty()).map(|name| {
+        // memory and network are the names from before the rows were split up
+        let name = match name {
+            "memory" => "mem",
+            "network" => "net",
+            name => name,
+        };
+        Section::ALL.into_iter().find(|section| section.name() == name)
+            .ok_or_else(|| format!("invalid section '{name}', expected one of {}", Section::ALL.map(Section::name).join(", ")))
+    }).collect()
+}
+
+fn parse_statusline_format(value: &str) -> Result<StatuslineFormat, String> {
+    match value {
+        "text" => Ok(StatuslineFormat::Text),
+        "json" => Ok(StatuslineFormat::Json),
+        _ => Err(format!("invalid statusline format '{value}', expected text or json")),
+    }
+}
+
+fn parse_disk_sort(value: &str) -> Result<DiskSort, String> {
+    match value {
+        "name" => Ok(DiskSort::Name),
+        "size" => Ok(DiskSort::Size),
+        "used" => Ok(DiskSort::Used),
+        "io" => Ok(DiskSort::Io),
+        _ => Err(format!("invalid disk sort '{value}', expected name, size, used or io")),
+    }
+}
+
+fn parse_proc_sort(value: &str) -> Result<ProcSort, String> {
+    match value {
+        "cpu" => Ok(ProcSort::Cpu),
+        "mem" => Ok(ProcSort::Mem),
+        "disk" => Ok(ProcSort::Disk),
+        _ => Err(format!("invalid process sort '{value}', expected cpu, mem or disk")),
+    }
+}
+
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    value.parse::<f64>().ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or(format!("invalid interval '{value}', expected seconds like 0.5 or 2"))
+}
+
+/// Inverse of `format_size` for command line values like `500M`, `8G` or `0`, suffixes are 1024-based
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let err = || format!("invalid size '{value}', expected a number with an optional B, K, M, G or T suffix like 500M");
+    let (num, scale) = match value.char_indices().last().ok_or_else(err)? {
+        (i, suffix) if suffix.is_ascii_alphabetic() => {
+            let power = "BKMGT".find(suffix.to_ascii_uppercase()).ok_or_else(err)?;
+            (&value[..i], 1u64 << (10 * power))
+        }
+        _ => (value, 1),
+    };
+    let num = num.parse::<f64>().ok().filter(|num| num.is_finite() && *num >= 0.0).ok_or_else(err)?;
+    Ok((num * scale as f64).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured(text: &str, cli: &[&str]) -> Result<(Args, Vec<String>), String> {
+        let mut args = Args::default();
+        let warnings = args.apply_config(&config::parse(text)?)?;
+        args.apply_args(cli.iter().map(|arg| arg.to_string()))?;
+        Ok((args, warnings))
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let (args, warnings) = configured("interval = 2\ncolor = 'never'\nextra = true\nexclude_iface = ['tun*']", &["--interval", "0.5", "--color=always"]).unwrap();
+        assert_eq!(args.interval, Duration::from_millis(500));
+        assert_eq!(args.color, ColorMode::Always);
+        assert!(args.extra);
+        assert!(!configured("extra = true", &["--no-extra"]).unwrap().0.extra);
+        assert!(!configured("", &["--cores", "--no-cores"]).unwrap().0.cores);
+        assert!(configured("", &["--no-gpu"]).unwrap().0.no_gpu);
+        assert_eq!(configured("", &["--no-extra=1"]).err(), Some("'--no-extra' doesn't take a value".to_string()));
+        assert_eq!(args.exclude_ifaces.last().map(String::as_str), Some("tun*"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn false_turns_on_the_no_flag() {
+        assert_eq!(configured("gradient = false", &[]).unwrap().0.gradient, Some(false));
+        assert_eq!(configured("gradient = false", &["--gradient"]).unwrap().0.gradient, Some(true));
+        assert!(!configured("no_gpu = false", &[]).unwrap().0.no_gpu);
+        assert!(!configured("peaks = false", &[]).unwrap().0.peaks);
+    }
+
+    #[test]
+    fn unknown_keys_warn() {
+        let (_, warnings) = configured("interval = 2\nrefresh = 3\nturbo = false", &[]).unwrap();
+        assert_eq!(warnings, ["unknown key 'refresh' on line 2", "unknown key 'turbo' on line 3"]);
+    }
+
+    #[test]
+    fn bad_values_name_the_line() {
+        assert_eq!(configured("si = true\ninterval = 'soon'", &[]).err(), Some("line 2: invalid interval 'soon', expected seconds like 0.5 or 2".to_string()));
+        assert_eq!(configured("extra = 'yes'", &[]).err(), Some("line 1: '--extra' doesn't take a value".to_string()));
+    }
+
+    #[test]
+    fn written_config_reads_back() {
+        let (args, _) = configured("", &["--interval", "0.25", "--hide", "fans,disks", "--min-disk-size", "500M", "--alert", "vram=6G", "--alert", "cpu=90", "--exclude-iface", "tun*", "--procs", "8", "--cores", "--no-peaks", "--peak-decay", "25", "--smooth", "0.3", "--sort-disks", "io", "--kwh-price", "0.3"]).unwrap();
+        let (read_back, warnings) = configured(&args.to_config(), &[]).unwrap();
+        assert_eq!(read_back, args);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn thresholds_and_bar_width_read_back() {
+        let text = "bar_width = 20\n[thresholds]\nnvme = [40, 50, 60]\ngpu_hotspot = [70, 90, 105]\ncpu = [50, 70, 85]\ncpu = [60, 80, 95]";
+        let (args, warnings) = configured(text, &[]).unwrap();
+        assert_eq!(args.bar_width, Some(20));
+        assert_eq!(args.thresholds, [(TempKind::Nvme, [40, 50, 60]), (TempKind::GpuHotspot, [70, 90, 105]), (TempKind::Cpu, [60, 80, 95])]);
+        assert!(warnings.is_empty());
+        let (read_back, warnings) = configured(&args.to_config(), &[]).unwrap();
+        assert_eq!(read_back, args);
+        assert!(warnings.is_empty());
+        assert_eq!(configured("[thresholds]\nssd = [1, 2, 3]", &[]).unwrap().1, ["unknown temperature 'ssd' on line 2"]);
+        for bad in ["[50, 70]", "[70, 50, 85]", "'hot'"] {
+            assert_eq!(configured(&format!("[thresholds]\ncpu = {bad}"), &[]).err(), Some("line 2: 'thresholds.cpu' expects three rising °C like [50, 70, 85]".to_string()));
+        }
+    }
+
+    #[test]
+    fn parse_size_suffixes() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size("4K"), Ok(4 << 10));
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("1.5G"), Ok(3 << 29));
+        assert_eq!(parse_size("8g"), Ok(8 << 30));
+        assert_eq!(parse_size("2T"), Ok(2 << 40));
+        assert_eq!(parse_size(&crate::fmt::format_size(100 << 30, crate::fmt::Units::Binary)), Ok(100 << 30));
+    }
+
+    #[test]
+    fn parse_size_garbage() {
+        for value in ["", "G", "abc", "5X", "-1G", "1.2.3M", "NaN", "inf"] {
+            assert!(parse_size(value).is_err(), "{value} should not parse");
+        }
+    }
+
+    #[test]
+    fn count_limits_frames() {
+        assert_eq!(configured("", &["--count", "60", "--interval", "1"]).unwrap().0.count, 60);
+        assert_eq!(configured("", &["--count=0"]).unwrap().0.count, 0);
+        assert_eq!(configured("", &["--count", "-1"]).err(), Some("'--count' expects a number of frames".to_string()));
+        assert!(configured("", &["--once", "--count", "3"]).is_err());
+        assert!(configured("", &["once", "--count", "0"]).is_ok());
+    }
+
+    #[test]
+    fn record_and_replay_take_a_file() {
+        let (args, _) = configured("", &["record", "trip.ndjson", "--cores"]).unwrap();
+        assert_eq!(args.record.as_deref(), Some("trip.ndjson"));
+        let (args, _) = configured("", &["replay", "trip.ndjson", "--speed", "10"]).unwrap();
+        assert_eq!((args.replay.as_deref(), args.speed), (Some("trip.ndjson"), 10.0));
+        assert_eq!(configured("", &["replay"]).err(), Some("'replay' expects a value".to_string()));
+        assert!(configured("", &["--speed", "0"]).is_err());
+        assert_eq!(configured("", &["--replay", "a", "--record", "b"]).err(), Some("'--replay' can't be combined with '--record'".to_string()));
+    }
+
+    #[test]
+    fn get_takes_keys_up_to_the_first_option() {
+        let (args, _) = configured("", &["get", "cpu.temp", "mem.used", "--fahrenheit"]).unwrap();
+        assert_eq!((args.get.as_slice(), args.fahrenheit), (["cpu.temp".to_string(), "mem.used".to_string()].as_slice(), true));
+        assert!(configured("", &["get", "--fahrenheit"]).is_err());
+    }
+
+    #[test]
+    fn remotes_pass_on_what_snapshots_hold() {
+        let (args, _) = configured("remote = ['pi@nas']", &["--remote", "me@box", "--procs", "3", "--extra", "--interval", "2", "--cores"]).unwrap();
+        assert_eq!(args.remotes, ["pi@nas", "me@box"]);
+        assert_eq!(args.remote_args(), ["--interval", "2", "--min-disk-size", "8589934592", "--sort-disks", "name", "--procs-sort", "cpu", "--procs", "3", "--extra"]);
+        let cli = ["--iface", "eth1", "--exclude-iface", "tun*", "--disk", "sda", "--exclude-disk", "loop*", "--min-disk-size", "1G", "--sort-disks", "io", "--procs-sort", "mem", "--procs-hide-kernel", "--partitions"];
+        let (args, _) = configured("", &cli).unwrap();
+        assert_eq!(args.remote_args(), ["--interval", "1", "--min-disk-size", "1073741824", "--sort-disks", "io", "--procs-sort", "mem", "--iface", "eth1", "--exclude-iface", "tun*",
+            "--disk", "sda", "--exclude-disk", "loop*", "--partitions", "--procs-hide-kernel"]);
+        // the remote reads them back to the same collection options
+        let (remote, _) = configured("", &args.remote_args().iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+        assert_eq!((remote.iface, remote.exclude_ifaces, remote.disks, remote.min_disk_size, remote.sort_disks), (args.iface.clone(), args.exclude_ifaces.clone(), args.disks.clone(), args.min_disk_size, args.sort_disks));
+        assert_eq!(configured("", &["--remote", "pi@nas", "--json"]).err(), Some("'--remote' can't be combined with '--json'".to_string()));
+    }
+
+    #[test]
+    fn only_hides_the_other_sections() {
+        let (args, _) = configured("", &["--only", "cpu,mem,vram"]).unwrap();
+        assert_eq!(args.hide.len(), Section::ALL.len() - 3);
+        assert!(!args.hide.contains(&Section::Vram) && args.hide.contains(&Section::Pcie));
+        assert!(configured("", &["--only", "cpu,extra"]).unwrap().0.extra);
+        // the command line can narrow a config's hide down
+        assert_eq!(configured("hide = 'net'", &["--only", "net,disks"]).unwrap().0.hide.len(), Section::ALL.len() - 2);
+        assert_eq!(configured("", &["--hide", "memory,network"]).unwrap().0.hide, [Section::Mem, Section::Net]);
+        assert_eq!(configured("", &["--hide", "net", "--only", "cpu"]).err(), Some("'--hide' and '--only' can't be combined".to_string()));
+        assert!(configured("", &["--only", "cpu,gpu"]).unwrap_err().starts_with("invalid section 'gpu'"));
+    }
+}
@@ -0,0 +1,107 @@
+/// Dot grid drawn with braille characters, each cell holds 2×4 dots, `(0, 0)` is the top left dot
+pub struct Canvas<'a> {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+    colors: Vec<&'a str>,
+}
+
+/// Bit of each dot in a cell, indexed by `[y][x]`, braille numbers dots 1-2-3-7 down the left and 4-5-6-8 down the right
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl<'a> Canvas<'a> {
+    /// `width` and `height` are in cells, so the canvas is `2 * width` by `4 * height` dots
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, cells: vec![0; width * height], colors: vec![""; width * height] }
+    }
+
+    /// Dots outside the canvas are ignored, the cell takes the color of the last dot set in it
+    pub fn set(&mut self, x: usize, y: usize, color: &'a str) {
+        if let Some(i) = self.cell(x, y) {
+            self.cells[i] |= DOTS[y % 4][x % 2];
+            self.colors[i] = color;
+        }
+    }
+
+    #[allow(dead_code)] // only the tests erase dots so far
+    pub fn clear(&mut self, x: usize, y: usize) {
+        if let Some(i) = self.cell(x, y) {
+            self.cells[i] &= !DOTS[y % 4][x % 2];
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width * 2 && y < self.height * 4).then(|| y / 4 * self.width + x / 2)
+    }
+
+    /// One string per cell row, `reset` ends every colored cell
+    pub fn lines(&self, reset: &str) -> Vec<String> {
+        self.cells.chunks(self.width.max(1)).zip(self.colors.chunks(self.width.max(1))).map(|(cells, colors)| {
+            cells.iter().zip(colors).map(|(&bits, color)| {
+                let c = crate::glyphs::braille(bits);
+                if bits == 0 || color.is_empty() { c.to_string() } else { format!("{color}{c}{reset}") }
+            }).collect()
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(canvas: &Canvas) -> Vec<String> {
+        canvas.lines("")
+    }
+
+    #[test]
+    fn empty_canvas_is_blank_braille() {
+        assert_eq!(chars(&Canvas::new(2, 1)), ["\u{2800}\u{2800}"]);
+    }
+
+    #[test]
+    fn dot_bits() {
+        let expected = [((0, 0), '⠁'), ((0, 1), '⠂'), ((0, 2), '⠄'), ((0, 3), '⡀'), ((1, 0), '⠈'), ((1, 1), '⠐'), ((1, 2), '⠠'), ((1, 3), '⢀')];
+        for ((x, y), c) in expected {
+            let mut canvas = Canvas::new(1, 1);
+            canvas.set(x, y, "");
+            assert_eq!(chars(&canvas), [c.to_string()], "dot ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn dots_land_in_the_right_cell() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set(3, 7, "");
+        canvas.set(0, 4, "");
+        assert_eq!(chars(&canvas), ["\u{2800}\u{2800}", "⠁⢀"]);
+    }
+
+    #[test]
+    fn full_cell_and_clear() {
+        let mut canvas = Canvas::new(1, 1);
+        for y in 0..4 {
+            for x in 0..2 {
+                canvas.set(x, y, "");
+            }
+        }
+        assert_eq!(chars(&canvas), ["⣿"]);
+        canvas.clear(1, 3);
+        assert_eq!(chars(&canvas), ["⡿"]);
+    }
+
+    #[test]
+    fn out_of_bounds_is_ignored() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.set(2, 0, "");
+        canvas.set(0, 4, "");
+        canvas.clear(5, 5);
+        assert_eq!(chars(&canvas), ["\u{2800}"]);
+    }
+
+    #[test]
+    fn colors_wrap_set_cells() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set(0, 0, "<c>");
+        assert_eq!(canvas.lines("</c>"), ["<c>⠁</c>\u{2800}"]);
+    }
+}
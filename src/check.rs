@@ -0,0 +1,77 @@
+use crate::{args::Args, collect::Snapshot, fmt::{Units, format_size}};
+
+/// Result of one `hwtop check` condition, `Error` is a value that couldn't be read at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Error,
+}
+
+/// Every condition requested on the command line with a line describing it
+pub fn run(snapshot: &Snapshot, args: &Args) -> Vec<(Outcome, String)> {
+    let mut results = vec![];
+    let size = |bytes: u64| format_size(bytes, Units::Binary);
+    if let Some(max) = args.max_cpu_temp {
+        results.push(match snapshot.cpu.temp_c {
+            Some(temp) => (outcome(temp <= max), format!("cpu temp {temp}°C, max {max}°C")),
+            None => (Outcome::Error, "cpu temp unreadable".to_string()),
+        });
+    }
+    if let Some(max) = args.max_gpu_temp {
+        if snapshot.gpus.is_empty() {
+            results.push((Outcome::Error, "gpu temp: no GPU found".to_string()));
+        }
+        for gpu in &snapshot.gpus {
+            results.push(match gpu.temp_c {
+                Some(temp) => (outcome(temp <= max), format!("{} temp {temp}°C, max {max}°C", gpu.name)),
+                None => (Outcome::Error, format!("{} temp unreadable", gpu.name)),
+            });
+        }
+    }
+    if let Some(min) = args.min_free_ram {
+        // used is total minus available, so reclaimable caches count as free like `free`'s available column
+        let free = snapshot.memory.ram_total.saturating_sub(snapshot.memory.ram_used);
+        results.push((outcome(free >= min), format!("free ram {}, min {}", size(free), size(min))));
+    }
+    for (target, min) in &args.min_free_disks {
+        // a mount point or a device with or without /dev/
+        let device = format!("/dev/{}", target.trim_start_matches("/dev/"));
+        results.push(match snapshot.mounts.iter().find(|mount| &mount.mount_point == target || mount.device == device) {
+            Some(mount) => {
+                let free = mount.total.saturating_sub(mount.used);
+                (outcome(free >= *min), format!("free disk {target} {}, min {}", size(free), size(*min)))
+            }
+            None => (Outcome::Error, format!("disk {target}: no such mount point or device")),
+        });
+    }
+    results
+}
+
+fn outcome(pass: bool) -> Outcome {
+    if pass { Outcome::Pass } else { Outcome::Fail }
+}
+
+/// 0 when everything passed, 1 when a check failed and 2 when a value couldn't be collected
+#[must_use]
+pub fn exit_code(results: &[(Outcome, String)]) -> i32 {
+    match results.iter().map(|&(outcome, _)| outcome).max() {
+        Some(Outcome::Error) => 2,
+        Some(Outcome::Fail) => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_outrank_failures() {
+        let result = |outcome| (outcome, String::new());
+        assert_eq!(exit_code(&[]), 0);
+        assert_eq!(exit_code(&[result(Outcome::Pass), result(Outcome::Pass)]), 0);
+        assert_eq!(exit_code(&[result(Outcome::Pass), result(Outcome::Fail)]), 1);
+        assert_eq!(exit_code(&[result(Outcome::Error), result(Outcome::Fail)]), 2);
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant, SystemTime};
+
+/// Ticks shorter than this are never taken for a gap, whatever the interval, longer than three of the slowest live interval
+const MIN_GAP: Duration = Duration::from_secs(30);
+
+/// Time between refreshes. `Instant` stands still while the machine sleeps, a suspend shows as the wall clock running ahead of it
+pub struct Clock {
+    last: Instant,
+    last_wall: SystemTime,
+}
+
+impl Clock {
+    pub fn new(now: Instant, wall: SystemTime) -> Self {
+        Self { last: now, last_wall: wall }
+    }
+
+    #[must_use]
+    pub fn since_tick(&self) -> Duration {
+        self.last.elapsed()
+    }
+
+    /// Seconds since the last tick, the divisor of per-tick counters, and the wall clock time since it when that's far longer
+    /// than `interval`, a suspend or a stall the counters grew over without anyone looking
+    pub fn tick(&mut self, now: Instant, wall: SystemTime, interval: Duration) -> (f32, Option<Duration>) {
+        let elapsed = now.saturating_duration_since(self.last);
+        // a clock set back leaves only the monotonic time
+        let wall_elapsed = wall.duration_since(self.last_wall).unwrap_or_default().max(elapsed);
+        (self.last, self.last_wall) = (now, wall);
+        (elapsed.as_secs_f32(), (wall_elapsed > (interval * 3).max(MIN_GAP)).then_some(wall_elapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspends_show_on_the_wall_clock() {
+        let (start, wall, second) = (Instant::now(), SystemTime::now(), Duration::from_secs(1));
+        let mut clock = Clock::new(start, wall);
+        assert_eq!(clock.tick(start + second, wall + second, second), (1.0, None));
+        // the lid closed for an hour, the monotonic clock only saw the second after waking
+        assert_eq!(clock.tick(start + second * 2, wall + second * 3602, second), (1.0, Some(second * 3601)));
+        assert_eq!(clock.tick(start + second * 3, wall + second * 3603, second), (1.0, None));
+        // a stall counts too, a clock set back doesn't
+        assert_eq!(clock.tick(start + second * 63, wall + second * 3663, second), (60.0, Some(second * 60)));
+        assert_eq!(clock.tick(start + second * 64, wall, second), (1.0, None));
+        // slow intervals get three of them
+        assert_eq!(clock.tick(start + second * 124, wall + second * 60, second * 20), (60.0, None));
+    }
+}
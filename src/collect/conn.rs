@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+/// Sockets of the whole machine, enough to notice something opening thousands of them
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ConnSample {
+    pub established: u64,
+    pub listening: u64,
+    pub udp: u64,
+}
+
+// `st` column of /proc/net/tcp, from include/net/tcp_states.h
+const TCP_ESTABLISHED: &str = "01";
+const TCP_LISTEN: &str = "0A";
+
+/// IPv4 and IPv6 counted together, `None` when none of the files are readable
+pub fn sample() -> Option<ConnSample> {
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    let tcp: Vec<String> = ["/proc/net/tcp", "/proc/net/tcp6"].into_iter().filter_map(read).collect();
+    let udp: Vec<String> = ["/proc/net/udp", "/proc/net/udp6"].into_iter().filter_map(read).collect();
+    if tcp.is_empty() && udp.is_empty() {
+        return None;
+    }
+    let mut conns = ConnSample { established: 0, listening: 0, udp: 0 };
+    for table in &tcp {
+        count_tcp(table, &mut conns);
+    }
+    conns.udp = udp.iter().map(|table| table.lines().skip(1).count() as u64).sum();
+    Some(conns)
+}
+
+/// Adds up the states of one table, the first line is the header
+fn count_tcp(table: &str, conns: &mut ConnSample) {
+    for line in table.lines().skip(1) {
+        match line.split_whitespace().nth(3) {
+            Some(TCP_ESTABLISHED) => conns.established += 1,
+            Some(TCP_LISTEN) => conns.listening += 1,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_tcp_states() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1234 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 5678 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:C350 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 5679 1 0000000000000000 20 4 30 10 -1
+   3: 0100007F:C351 0100007F:1F90 06 00000000:00000000 03:00000F9E 00000000     0        0 0 3 0000000000000000
+";
+        let mut conns = ConnSample { established: 0, listening: 0, udp: 0 };
+        count_tcp(table, &mut conns);
+        assert_eq!(conns, ConnSample { established: 2, listening: 1, udp: 0 });
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::System;
+
+use super::{sensors::Temps, sysfs, rapl::{PackagePower, Rapl}, throttle::{Throttle, ThrottleSample}};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct CpuSample {
+    pub usage: f32,
+    pub temp_c: Option<u32>,
+    pub cores: Vec<CoreSample>,
+    pub core_temps_c: Vec<u32>,
+    /// RAPL package power summed over packages, `None` without readable counters
+    pub power_w: Option<f32>,
+    pub power_limit_w: Option<f32>,
+    /// watt-hours of the packages since hwtop started, `None` without RAPL
+    #[serde(default)]
+    pub energy_wh: Option<f64>,
+    pub packages: Vec<PackagePower>,
+    /// cpufreq governor, several joined by `/` when cores disagree, `None` without cpufreq
+    pub governor: Option<String>,
+    /// whether the CPU may boost above its base clock, `None` when the driver doesn't say
+    pub turbo: Option<bool>,
+    /// `None` without throttle counters or a base clock to guess against
+    pub throttle: Option<ThrottleSample>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CoreSample {
+    pub usage: f32,
+    pub freq_mhz: u64,
+    pub max_freq_mhz: Option<u32>,
+    /// from the `Core N` sensor of this logical CPU's physical core
+    pub temp_c: Option<u32>,
+}
+
+/// Usage, clocks and temps of every core and the package power, `delta` is the seconds since the previous sample
+pub fn sample(sys: &System, temps: &Temps, max_freqs: &mut MaxFreqs, rapl: &mut Rapl, throttle: &mut Throttle, delta: f32) -> CpuSample {
+    let max = max_freqs.update(sys);
+    let cores = sys.cpus().iter().zip(max).zip(&max_freqs.core_ids).map(|((cpu, max_freq_mhz), core_id)| CoreSample {
+        usage: cpu.cpu_usage(),
+        freq_mhz: cpu.frequency(),
+        max_freq_mhz,
+        temp_c: core_id.and_then(|id| temps.by_core_id.get(&id).copied()),
+    }).collect::<Vec<_>>();
+    let usage = sys.global_cpu_usage();
+    let throttle = throttle.sample(usage, &cores);
+
+    // POWER
+    let packages = rapl.sample(delta);
+    let power_w = packages.iter().map(|package| package.power_w).sum::<Option<f32>>().filter(|_| !packages.is_empty());
+    let power_limit_w = packages.iter().map(|package| package.limit_w).sum::<Option<f32>>().filter(|_| !packages.is_empty());
+
+    CpuSample {
+        usage,
+        temp_c: temps.package_c,
+        cores,
+        core_temps_c: temps.cores_c.clone(),
+        power_w,
+        power_limit_w,
+        energy_wh: None,
+        packages,
+        governor: governor(sys.cpus().len()),
+        turbo: turbo(),
+        throttle,
+    }
+}
+
+fn governor(cpus: usize) -> Option<String> {
+    let mut governors: Vec<String> = (0..cpus).filter_map(|i| sysfs::read(format!("/sys/devices/system/cpu/cpu{i}/cpufreq/scaling_governor"))).collect();
+    governors.sort();
+    governors.dedup();
+    (!governors.is_empty()).then(|| governors.join("/"))
+}
+
+/// intel_pstate has its own switch, other drivers have the generic cpufreq one
+fn turbo() -> Option<bool> {
+    sysfs::read_num::<u32>("/sys/devices/system/cpu/intel_pstate/no_turbo").map(|no_turbo| no_turbo == 0)
+        .or_else(|| sysfs::read_num::<u32>("/sys/devices/system/cpu/cpufreq/boost").map(|boost| boost == 1))
+}
+
+/// Max clock of every core in MHz and the physical core it belongs to, read once since they never change (and again only if CPUs are hotplugged)
+#[derive(Default)]
+pub struct MaxFreqs {
+    rated: Vec<Option<u32>>,
+    core_ids: Vec<Option<u32>>,
+    /// highest clock seen per core, the fallback when cpufreq and the model name both lack it (common in VMs)
+    observed: Vec<u32>,
+}
+
+impl MaxFreqs {
+    fn update(&mut self, sys: &System) -> Vec<Option<u32>> {
+        let cpus = sys.cpus();
+        if self.rated.len() != cpus.len() {
+            // `@ 3.40GHz` at the end of the model name
+            let model_max = cpus.first()
+                .and_then(|cpu| cpu.brand().rsplit_once('@'))
+                .and_then(|(_, ghz)| ghz.trim().strip_suffix("GHz")?.parse::<f32>().ok())
+                .map(|ghz| (ghz * 1000.0).round() as u32);
+            #[cfg(target_os = "macos")]
+            let model_max = model_max.or_else(sysctl_max_mhz);
+            #[cfg(windows)]
+            let model_max = model_max.or_else(|| power_info_max_mhz(cpus.len()));
+            self.rated = (0..cpus.len()).map(|i| {
+                sysfs::read_num::<u32>(format!("/sys/devices/system/cpu/cpu{i}/cpufreq/cpuinfo_max_freq")).map(|khz| khz / 1000).filter(|&mhz| mhz > 0).or(model_max)
+            }).collect();
+            self.core_ids = (0..cpus.len()).map(|i| sysfs::read_num(format!("/sys/devices/system/cpu/cpu{i}/topology/core_id"))).collect();
+            self.observed = vec![0; cpus.len()];
+        }
+        cpus.iter().zip(&self.rated).zip(&mut self.observed).map(|((cpu, rated), observed)| {
+            *observed = (*observed).max(cpu.frequency() as u32);
+            rated.or(Some(*observed).filter(|&mhz| mhz > 0))
+        }).collect()
+    }
+}
+
+/// `PROCESSOR_POWER_INFORMATION` of every logical CPU, only the fields up to `MaxMhz` are read
+#[cfg(windows)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ProcessorPowerInformation {
+    _number: u32,
+    max_mhz: u32,
+    /// current and limit clocks, max and current idle state
+    _rest: [u32; 4],
+}
+
+#[cfg(windows)]
+#[link(name = "powrprof")]
+unsafe extern "system" {
+    fn CallNtPowerInformation(level: i32, input: *const std::ffi::c_void, input_len: u32, output: *mut std::ffi::c_void, output_len: u32) -> i32;
+}
+
+/// Highest `MaxMhz` from `CallNtPowerInformation(ProcessorInformation)`, Windows has no sysfs to read rated clocks from
+#[cfg(windows)]
+fn power_info_max_mhz(cpus: usize) -> Option<u32> {
+    const PROCESSOR_INFORMATION: i32 = 11;
+    let mut info = vec![ProcessorPowerInformation::default(); cpus.max(1)];
+    let len = (info.len() * std::mem::size_of::<ProcessorPowerInformation>()) as u32;
+    let status = unsafe { CallNtPowerInformation(PROCESSOR_INFORMATION, std::ptr::null(), 0, info.as_mut_ptr().cast(), len) };
+    if status != 0 {
+        return None;
+    }
+    info.iter().map(|cpu| cpu.max_mhz).max().filter(|&mhz| mhz > 0)
+}
+
+/// `hw.cpufrequency_max` in Hz, only Intel Macs have it, Apple Silicon reports no clocks at all
+#[cfg(target_os = "macos")]
+fn sysctl_max_mhz() -> Option<u32> {
+    let (mut hz, mut len) = (0u64, std::mem::size_of::<u64>());
+    let ok = unsafe { libc::sysctlbyname(c"hw.cpufrequency_max".as_ptr(), (&raw mut hz).cast(), &mut len, std::ptr::null_mut(), 0) } == 0;
+    (ok && hz > 0).then(|| (hz / 1_000_000) as u32)
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sysinfo::Disks;
+
+use crate::args::{Args, DiskSort};
+use super::{glob_match, per_sec, diskstats::{self, DiskStats}, nvme::{self, NvmeHealth}, sysfs};
+
+#[derive(Serialize, Deserialize)]
+pub struct DiskSample {
+    pub name: String,
+    pub used: u64,
+    pub total: u64,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    pub total_read: u64,
+    pub total_written: u64,
+    pub temp_c: Option<u32>,
+    /// from `/proc/diskstats`, `None` on the first tick or when the device isn't listed there
+    pub read_iops: Option<u64>,
+    pub write_iops: Option<u64>,
+    pub util: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MountSample {
+    pub mount_point: String,
+    pub fs: String,
+    pub device: String,
+    pub used: u64,
+    pub total: u64,
+}
+
+/// Block device backed by hardware, for `--info`
+pub struct PhysicalDisk {
+    pub name: String,
+    pub model: Option<String>,
+    pub size: u64,
+    /// `None` for SATA drives and when the controller can't be queried
+    pub health: Option<NvmeHealth>,
+}
+
+/// Every `/sys/block` device of at least `min_size`, loop, zram and device mapper devices have no `device` link and are skipped
+pub fn physical(min_size: u64) -> Vec<PhysicalDisk> {
+    let Ok(entries) = std::fs::read_dir("/sys/block") else {
+        return vec![];
+    };
+    let mut disks: Vec<PhysicalDisk> = entries.flatten().filter(|e| e.path().join("device").exists()).filter_map(|e| {
+        let name = e.file_name().to_string_lossy().into_owned();
+        // sizes are in 512 byte sectors whatever the logical block size
+        let size = sysfs::read_num::<u64>(e.path().join("size"))? * 512;
+        // namespace `nvme0n1` belongs to controller `nvme0`
+        let controller = name.strip_prefix("nvme").and_then(|rest| rest.split_once('n')).map(|(index, _)| format!("nvme{index}"));
+        (size >= min_size).then(|| PhysicalDisk {
+            model: sysfs::read(e.path().join("device/model")).filter(|model| !model.is_empty()),
+            size,
+            health: controller.as_deref().and_then(nvme::health),
+            name,
+        })
+    }).collect();
+    disks.sort_by(|a, b| a.name.cmp(&b.name));
+    disks
+}
+
+/// Where each disk ranks for `--sort-disks`, kept for the session so the order is settled by what disks were when they showed up
+#[derive(Default)]
+pub struct DiskOrder {
+    /// size or used bytes when first seen, or what had been read and written by then for `io`
+    first: HashMap<String, u64>,
+}
+
+impl DiskOrder {
+    /// Largest first, ties and `name` go by name
+    fn sort(&mut self, disks: &mut [DiskSample], key: DiskSort) {
+        for disk in disks.iter() {
+            self.first.entry(disk.name.clone()).or_insert(match key {
+                DiskSort::Size => disk.total,
+                DiskSort::Used => disk.used,
+                DiskSort::Name | DiskSort::Io => disk.total_read + disk.total_written,
+            });
+        }
+        let rank = |disk: &DiskSample| match key {
+            DiskSort::Name => 0,
+            DiskSort::Size | DiskSort::Used => self.first[&disk.name],
+            DiskSort::Io => (disk.total_read + disk.total_written).saturating_sub(self.first[&disk.name]),
+        };
+        disks.sort_by(|a, b| rank(b).cmp(&rank(a)).then_with(|| a.name.cmp(&b.name)));
+    }
+}
+
+/// Drive a partition is on, `nvme0n1p2` and `mmcblk0p1` have a `p` before the partition number, `sda1` and `vdb2` don't.
+/// Whole drives that end in a number like `nvme0n1`, and anything else, are their own
+#[must_use]
+fn drive_of(name: &str) -> &str {
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if base.len() == name.len() {
+        return name;
+    }
+    match base.strip_suffix('p') {
+        Some(drive) if drive.ends_with(|c: char| c.is_ascii_digit()) => drive,
+        _ if ["sd", "vd", "xvd", "hd"].iter().any(|prefix| base.starts_with(prefix)) => base,
+        _ => name,
+    }
+}
+
+/// sysinfo lists a disk per mount, a partition mounted twice (btrfs subvolumes, bind mounts) only counts once
+fn one_per_device(mut disks: Vec<DiskSample>) -> Vec<DiskSample> {
+    let mut seen = std::collections::HashSet::new();
+    disks.retain(|disk| seen.insert(disk.name.clone()));
+    disks
+}
+
+/// One row per drive, its partitions' space and rates add up
+fn merge_partitions(disks: Vec<DiskSample>) -> Vec<DiskSample> {
+    let add = |a: Option<u64>, b: Option<u64>| a.zip(b).map(|(a, b)| a + b).or(a).or(b);
+    let mut merged: Vec<DiskSample> = vec![];
+    for disk in disks {
+        let drive = drive_of(&disk.name).to_string();
+        let Some(row) = merged.iter_mut().find(|row| row.name == drive) else {
+            merged.push(DiskSample { name: drive, ..disk });
+            continue;
+        };
+        row.used += disk.used;
+        row.total += disk.total;
+        row.read_bps += disk.read_bps;
+        row.write_bps += disk.write_bps;
+        row.total_read += disk.total_read;
+        row.total_written += disk.total_written;
+        row.temp_c = row.temp_c.or(disk.temp_c);
+        row.read_iops = add(row.read_iops, disk.read_iops);
+        row.write_iops = add(row.write_iops, disk.write_iops);
+        // partitions share the drive's queue, the busiest one is closest to the drive's own utilization
+        row.util = [row.util, disk.util].into_iter().flatten().reduce(f32::max);
+    }
+    merged
+}
+
+/// Disks picked by `--disk`, or those of at least `--min-disk-size`, minus `--exclude-disk`,
+/// one row per drive unless `--partitions`, in `--sort-disks` order
+pub fn sample(disks: &Disks, disk_stats: &mut DiskStats, order: &mut DiskOrder, temps: &HashMap<String, u32>, delta: f32, args: &Args) -> Vec<DiskSample> {
+    let io_rates = disk_stats.sample(delta);
+    let samples = disks.iter().filter_map(|disk| {
+        // macOS names volumes instead of devices
+        let name = disk.name().to_str().map(|d| d.strip_prefix("/dev/").unwrap_or(d)).unwrap_or_default().to_string();
+        // and Windows volumes often have no label, `C:\` is what users know them by anyway
+        let name = if name.is_empty() { disk.mount_point().to_string_lossy().into_owned() } else { name };
+        // picking a disk by name shows it whatever its size
+        let shown = if args.disks.is_empty() {
+            disk.total_space() >= args.min_disk_size
+        } else {
+            args.disks.iter().any(|pattern| glob_match(pattern, &name))
+        };
+        (shown && !args.exclude_disks.iter().any(|pattern| glob_match(pattern, &name))).then_some((disk, name))
+    }).map(|(disk, name)| {
+        let usage = disk.usage();
+        let io = io_rates.get(&diskstats::kernel_name(&name));
+        DiskSample {
+            temp_c: temps.get(&name).copied(),
+            read_iops: io.map(|io| io.read_iops),
+            write_iops: io.map(|io| io.write_iops),
+            util: io.map(|io| io.util),
+            name,
+            used: disk.total_space() - disk.available_space(),
+            total: disk.total_space(),
+            read_bps: per_sec(usage.read_bytes, delta),
+            write_bps: per_sec(usage.written_bytes, delta),
+            total_read: usage.total_read_bytes,
+            total_written: usage.total_written_bytes,
+        }
+    }).collect();
+    let samples = one_per_device(samples);
+    let mut samples = if args.partitions { samples } else { merge_partitions(samples) };
+    order.sort(&mut samples, args.sort_disks);
+    samples
+}
+
+/// Mounted filesystems sorted by mount point, pseudo filesystems are left out unless `all`
+pub fn mounts(disks: &Disks, all: bool) -> Vec<MountSample> {
+    const PSEUDO_FS: [&str; 6] = ["tmpfs", "devtmpfs", "ramfs", "overlay", "squashfs", "efivarfs"];
+    let mut mounts: Vec<MountSample> = disks.iter()
+        .filter(|disk| all || !PSEUDO_FS.contains(&disk.file_system().to_string_lossy().as_ref()))
+        .map(|disk| MountSample {
+        mount_point: disk.mount_point().to_string_lossy().into_owned(),
+        fs: disk.file_system().to_string_lossy().into_owned(),
+        device: disk.name().to_string_lossy().into_owned(),
+        used: disk.total_space() - disk.available_space(),
+        total: disk.total_space(),
+    }).collect();
+    unique_mounts(&mut mounts);
+    mounts
+}
+
+/// Bind mounts show up once per mount point, the shortest path of each device is kept. btrfs subvolumes share the device
+/// and size too but hold different files, each of them keeps its row
+fn unique_mounts(mounts: &mut Vec<MountSample>) {
+    mounts.sort_by(|a, b| a.mount_point.len().cmp(&b.mount_point.len()).then_with(|| a.mount_point.cmp(&b.mount_point)));
+    let mut seen = std::collections::HashSet::new();
+    mounts.retain(|mount| {
+        let subvolume = (mount.fs == "btrfs").then(|| mount.mount_point.clone());
+        seen.insert((mount.device.clone(), mount.total, subvolume))
+    });
+    mounts.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk(name: &str, used: u64, total: u64, read: u64) -> DiskSample {
+        DiskSample { name: name.to_string(), used, total, read_bps: read, write_bps: 0, total_read: read, total_written: 0, temp_c: None, read_iops: None, write_iops: None, util: None }
+    }
+
+    #[test]
+    fn partitions_add_up_to_their_drive() {
+        for (name, drive) in [("nvme0n1p2", "nvme0n1"), ("nvme0n1", "nvme0n1"), ("mmcblk0p1", "mmcblk0"), ("sda1", "sda"), ("xvda1", "xvda"), ("loop3", "loop3"), ("dm-0", "dm-0"), ("C:\\", "C:\\")] {
+            assert_eq!(drive_of(name), drive);
+        }
+        let merged = merge_partitions(vec![disk("nvme0n1p2", 10, 100, 1), disk("sda1", 5, 50, 0), disk("nvme0n1p3", 20, 200, 2)]);
+        let rows: Vec<(&str, u64, u64, u64)> = merged.iter().map(|disk| (disk.name.as_str(), disk.used, disk.total, disk.read_bps)).collect();
+        assert_eq!(rows, [("nvme0n1", 30, 300, 3), ("sda", 5, 50, 0)]);
+        // nvme0n1p2 is both `/` and `/home`, its device's space and rates are there once
+        let merged = merge_partitions(one_per_device(vec![disk("nvme0n1p2", 10, 100, 1), disk("nvme0n1p2", 10, 100, 1), disk("nvme0n1p1", 1, 2, 0)]));
+        let rows: Vec<(&str, u64, u64, u64)> = merged.iter().map(|disk| (disk.name.as_str(), disk.used, disk.total, disk.read_bps)).collect();
+        assert_eq!(rows, [("nvme0n1", 11, 102, 1)]);
+    }
+
+    #[test]
+    fn bind_mounts_go_but_subvolumes_stay() {
+        let mount = |mount_point: &str, fs: &str, device: &str| MountSample { mount_point: mount_point.to_string(), fs: fs.to_string(), device: device.to_string(), used: 1, total: 100 };
+        let mut mounts = vec![
+            mount("/var/lib/docker", "ext4", "/dev/sda1"), mount("/data", "ext4", "/dev/sda1"),
+            mount("/home", "btrfs", "/dev/nvme0n1p2"), mount("/", "btrfs", "/dev/nvme0n1p2"), mount("/var", "btrfs", "/dev/nvme0n1p2"),
+        ];
+        unique_mounts(&mut mounts);
+        assert_eq!(mounts.iter().map(|mount| mount.mount_point.as_str()).collect::<Vec<_>>(), ["/", "/data", "/home", "/var"]);
+    }
+
+    #[test]
+    fn rows_keep_the_order_they_started_in() {
+        let names = |disks: &[DiskSample]| disks.iter().map(|disk| disk.name.clone()).collect::<Vec<_>>();
+        let mut order = DiskOrder::default();
+        let mut disks = vec![disk("sdb", 10, 100, 0), disk("sda", 20, 50, 0)];
+        order.sort(&mut disks, DiskSort::Used);
+        assert_eq!(names(&disks), ["sda", "sdb"]);
+        // sdb filling up past sda later doesn't move it
+        disks[1].used = 90;
+        order.sort(&mut disks, DiskSort::Used);
+        assert_eq!(names(&disks), ["sda", "sdb"]);
+        let mut order = DiskOrder::default();
+        order.sort(&mut disks, DiskSort::Io);
+        disks[1].total_read += 5;
+        order.sort(&mut disks, DiskSort::Io);
+        assert_eq!(names(&disks), ["sdb", "sda"]);
+        order.sort(&mut disks, DiskSort::Name);
+        assert_eq!(names(&disks), ["sda", "sdb"]);
+    }
+}
@@ -0,0 +1,64 @@
+use std::{collections::HashMap, path::Path};
+
+use super::sysfs;
+
+/// Requests per second and busy time of one block device over the last tick
+pub struct IoRates {
+    pub read_iops: u64,
+    pub write_iops: u64,
+    /// percent of the tick the device was doing I/O, like iostat's %util
+    pub util: f32,
+}
+
+/// Request counters from `/proc/diskstats`, sysinfo only tracks bytes
+pub struct DiskStats {
+    last: HashMap<String, [u64; 3]>, // reads, writes, ms doing I/O
+}
+
+impl Default for DiskStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiskStats {
+    pub fn new() -> Self {
+        let mut stats = Self { last: HashMap::new() };
+        stats.sample(1.0);
+        stats
+    }
+
+    /// Rates of every device since the previous call keyed by kernel name (`nvme0n1p2`, `dm-0`), `delta` is the elapsed seconds
+    pub fn sample(&mut self, delta: f32) -> HashMap<String, IoRates> {
+        let stats = sysfs::read("/proc/diskstats").unwrap_or_default();
+        let mut rates = HashMap::new();
+        for line in stats.lines() {
+            // major minor name, then reads completed at 1, writes completed at 5 and ms doing I/O at 10
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some((name, counters)) = fields.get(2).zip(fields.get(3..)) else {
+                continue;
+            };
+            let counter = |i: usize| counters.get(i - 1).and_then(|v| v.parse::<u64>().ok());
+            let Some(now) = counter(1).zip(counter(5)).zip(counter(10)).map(|((reads, writes), io_ms)| [reads, writes, io_ms]) else {
+                continue;
+            };
+            if let Some(last) = self.last.insert(name.to_string(), now) {
+                let [reads, writes, io_ms] = [0, 1, 2].map(|i| now[i].saturating_sub(last[i]));
+                rates.insert(name.to_string(), IoRates {
+                    read_iops: (reads as f32 / delta) as u64,
+                    write_iops: (writes as f32 / delta) as u64,
+                    util: (io_ms as f32 / (delta * 1000.0) * 100.0).min(100.0),
+                });
+            }
+        }
+        rates
+    }
+}
+
+/// Kernel name of a device as listed in `/dev`, `mapper/root` resolves to `dm-0`
+#[must_use]
+pub fn kernel_name(name: &str) -> String {
+    std::fs::canonicalize(Path::new("/dev").join(name)).ok()
+        .and_then(|path| Some(path.file_name()?.to_str()?.to_string()))
+        .unwrap_or_else(|| name.to_string())
+}
@@ -0,0 +1,30 @@
+use super::{cpu::CpuSample, gpu::GpuSample};
+
+/// Watt-hours since hwtop started, each tick's power reading times the seconds the tick really took
+#[derive(Default)]
+pub struct Energy {
+    cpu_wh: Option<f64>,
+    gpu_wh: Vec<Option<f64>>,
+}
+
+impl Energy {
+    /// Adds a tick of `delta` seconds and fills in `energy_wh`. A tick spanning a `gap`, a suspend or a stall the reading says
+    /// nothing about, is left out rather than counted at whatever power was read after it
+    pub fn add(&mut self, cpu: &mut CpuSample, gpus: &mut [GpuSample], delta: f32, gap: bool) {
+        let hours = if gap { 0.0 } else { delta as f64 / 3600.0 };
+        let add = |total: &mut Option<f64>, power_w: Option<f64>| {
+            if let Some(power_w) = power_w {
+                *total = Some(total.unwrap_or(0.0) + power_w * hours);
+            }
+            *total
+        };
+        cpu.energy_wh = add(&mut self.cpu_wh, cpu.power_w.map(f64::from));
+        // GPUs are told apart by their index, a GPU falling off the bus starts the later ones over
+        if self.gpu_wh.len() != gpus.len() {
+            self.gpu_wh.resize(gpus.len(), None);
+        }
+        for (gpu, total) in gpus.iter_mut().zip(&mut self.gpu_wh) {
+            gpu.energy_wh = add(total, gpu.power_w.map(f64::from));
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use crate::collect::sysfs;
+use super::{ClockSample, FanSample, GpuSample, PcieLink, PcieSample};
+
+/// amdgpu card read through `/sys/class/drm/card*/device`
+pub struct AmdGpu {
+    pub name: String,
+    bus_id: Option<String>,
+    device: PathBuf,
+    hwmon: Option<PathBuf>,
+}
+
+impl AmdGpu {
+    pub fn detect() -> Vec<Self> {
+        let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+            return vec![];
+        };
+        let mut cards: Vec<PathBuf> = entries.flatten()
+            .filter(|e| e.file_name().to_str().and_then(|n| n.strip_prefix("card")).is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())))
+            .map(|e| e.path().join("device"))
+            .filter(|device| sysfs::read(device.join("vendor")).as_deref() == Some("0x1002") && device.join("gpu_busy_percent").exists())
+            .collect();
+        cards.sort();
+        cards.into_iter().map(|device| {
+            let hwmon = std::fs::read_dir(device.join("hwmon")).ok()
+                .and_then(|mut dirs| dirs.next())
+                .and_then(Result::ok)
+                .map(|e| e.path());
+            let name = sysfs::read(device.join("product_name"))
+                .filter(|name| !name.is_empty())
+                .or_else(|| pci_name(&device))
+                .unwrap_or_else(|| "AMD GPU".to_string());
+            let name = name.replace("AMD ", "").replace("Advanced Micro Devices, Inc. ", "");
+            // `device` links to the PCI function, named after its bus id
+            let bus_id = std::fs::canonicalize(&device).ok().and_then(|path| path.file_name()?.to_str().map(str::to_string));
+            Self { name, bus_id, device, hwmon }
+        }).collect()
+    }
+
+    pub fn vram_total(&self) -> Option<u64> {
+        sysfs::read_num(self.device.join("mem_info_vram_total"))
+    }
+
+    pub fn sample(&self) -> GpuSample {
+        let device = |file: &str| self.device.join(file);
+        let hwmon = |file: &str| self.hwmon.as_ref().map(|hwmon| hwmon.join(file));
+        let hwmon_num = |file: &str| hwmon(file).and_then(sysfs::read_num::<u64>);
+        let fans = hwmon_num("fan1_input").map(|rpm| {
+            let percent = hwmon_num("pwm1").map(|pwm| pwm * 100 / 255)
+                .or_else(|| hwmon_num("fan1_max").filter(|&max| max > 0).map(|max| rpm * 100 / max));
+            vec![FanSample { percent: percent.map(|p| p as u32), rpm: Some(rpm as u32) }]
+        });
+        let link = |speed: &str, width: &str| PcieLink::from(sysfs::read(device(speed)).and_then(|speed| link_gen(&speed)), sysfs::read_num(device(width)));
+        let (max_link, link) = (link("max_link_speed", "max_link_width"), link("current_link_speed", "current_link_width"));
+        GpuSample {
+            name: self.name.clone(),
+            bus_id: self.bus_id.clone(),
+            usage: sysfs::read_num(device("gpu_busy_percent")),
+            mem_usage: sysfs::read_num(device("mem_busy_percent")),
+            temp_c: self.temp("edge").or_else(|| hwmon_num("temp1_input").map(|t| (t as f32 / 1000.0).round() as u32)),
+            mem_temp_c: self.temp("mem"),
+            hotspot_c: self.temp("junction"),
+            power_w: hwmon_num("power1_average").or_else(|| hwmon_num("power1_input")).map(|uw| (uw / 1_000_000) as u32),
+            power_limit_w: hwmon_num("power1_cap").map(|uw| (uw / 1_000_000) as u32),
+            power_default_limit_w: hwmon_num("power1_cap_default").map(|uw| (uw / 1_000_000) as u32),
+            energy_wh: None,
+            vram_used: sysfs::read_num(device("mem_info_vram_used")),
+            vram_total: self.vram_total(),
+            bar1_used: None,
+            bar1_total: None,
+            clocks: vec![
+                dpm_clock("GFX", &device("pp_dpm_sclk")),
+                dpm_clock("MEM", &device("pp_dpm_mclk")),
+            ],
+            fans,
+            // pcie_bw blocks for a second inside the driver, so only the link maximum is shown
+            pcie: max_link.map(|max_link| PcieSample {
+                rx_bps: None,
+                tx_bps: None,
+                max_bps: max_link.bps(),
+                link_bps: link.map_or(max_link.bps(), PcieLink::bps),
+                max_link: Some(max_link),
+                link,
+            }),
+            ecc: None,
+            nvlinks: vec![],
+            encoder: None,
+            decoder: None,
+            processes: None,
+        }
+    }
+}
+
+impl AmdGpu {
+    /// °C of the hwmon sensor labeled `label`, amdgpu has edge, junction (hotspot) and mem
+    fn temp(&self, label: &str) -> Option<u32> {
+        let hwmon = self.hwmon.as_ref()?;
+        (1..=3).find(|i| sysfs::read(hwmon.join(format!("temp{i}_label"))).as_deref() == Some(label))
+            .and_then(|i| sysfs::read_num::<u64>(hwmon.join(format!("temp{i}_input"))))
+            .map(|t| (t as f32 / 1000.0).round() as u32)
+    }
+}
+
+/// Current and max clock from a `pp_dpm_*` table, lines look like `1: 2100Mhz *` with `*` on the active level
+fn dpm_clock(name: &'static str, path: &Path) -> ClockSample {
+    let levels = sysfs::read(path).unwrap_or_default();
+    let parse = |line: &str| line.split_whitespace().nth(1)?.to_lowercase().strip_suffix("mhz")?.parse::<u32>().ok();
+    ClockSample {
+        name: name.to_string(),
+        mhz: levels.lines().find(|line| line.trim_end().ends_with('*')).and_then(parse),
+        max_mhz: levels.lines().filter_map(parse).max(),
+    }
+}
+
+/// PCIe generation from a `max_link_speed` value like `16.0 GT/s PCIe`
+fn link_gen(speed: &str) -> Option<u32> {
+    let gts = speed.split_whitespace().next()?.parse::<f32>().ok()?;
+    Some(match gts {
+        ..=2.5 => 1,
+        ..=5.0 => 2,
+        ..=8.0 => 3,
+        ..=16.0 => 4,
+        _ => 5,
+    })
+}
+
+/// Device name from the pci.ids database
+fn pci_name(device: &Path) -> Option<String> {
+    let id = sysfs::read(device.join("device"))?;
+    let id = id.trim_start_matches("0x");
+    ["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids"].iter().find_map(|path| {
+        let ids = std::fs::read_to_string(path).ok()?;
+        ids.lines()
+            .skip_while(|line| !line.starts_with("1002 "))
+            .skip(1)
+            .take_while(|line| line.starts_with('\t') || line.starts_with('#'))
+            .find_map(|line| line.strip_prefix('\t')?.strip_prefix(id)?.strip_prefix("  ").map(str::to_string))
+    })
+}
@@ -0,0 +1,233 @@
+#[cfg(feature = "nvidia")]
+use nvml_wrapper::{Nvml, error::NvmlError};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "nvidia")]
+use std::collections::HashMap;
+
+use crate::error::HwtopError;
+
+#[cfg(feature = "amd")]
+pub mod amd;
+#[cfg(feature = "nvidia")]
+pub mod nvidia;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClockSample {
+    pub name: String,
+    pub mhz: Option<u32>,
+    pub max_mhz: Option<u32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FanSample {
+    pub percent: Option<u32>,
+    pub rpm: Option<u32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PcieSample {
+    pub rx_bps: Option<u64>,
+    pub tx_bps: Option<u64>,
+    /// throughput of the max link
+    pub max_bps: u64,
+    /// throughput of the link as negotiated now, idle cards drop to a lower gen to save power
+    pub link_bps: u64,
+    pub max_link: Option<PcieLink>,
+    pub link: Option<PcieLink>,
+}
+
+/// PCIe generation and lane count, shown as `Gen4 x16`
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PcieLink {
+    pub generation: u32,
+    pub width: u32,
+}
+
+impl PcieLink {
+    #[cfg(any(feature = "nvidia", feature = "amd"))]
+    fn from(generation: Option<u32>, width: Option<u32>) -> Option<Self> {
+        generation.zip(width).map(|(generation, width)| Self { generation, width })
+    }
+
+    /// Throughput of the link in B/s
+    #[must_use]
+    pub fn bps(self) -> u64 {
+        pcie_max_throughput(self.generation, self.width)
+    }
+}
+
+impl std::fmt::Display for PcieLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Gen{} x{}", self.generation, self.width)
+    }
+}
+
+/// One NVLink of a GPU, rates are `None` when the link counts something other than bytes
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NvLinkSample {
+    pub link: u32,
+    pub active: bool,
+    pub rx_bps: Option<u64>,
+    pub tx_bps: Option<u64>,
+}
+
+/// Volatile ECC error counts, they reset when the driver loads
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EccSample {
+    pub enabled: bool,
+    pub corrected: Option<u64>,
+    pub uncorrected: Option<u64>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuProcessKind {
+    Compute,
+    Graphics,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GpuProcessSample {
+    pub pid: u32,
+    pub name: String,
+    pub kind: GpuProcessKind,
+    pub vram_used: Option<u64>,
+    /// SM utilization in percent, when the driver reports per-process stats
+    pub sm: Option<u32>,
+}
+
+/// One tick of GPU readings, `None` fields failed to read this tick and are shown as `--`
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GpuSample {
+    pub name: String,
+    /// PCI bus id like `0000:01:00.0`, stays with the card whatever order the drivers list GPUs in
+    #[serde(default)]
+    pub bus_id: Option<String>,
+    pub usage: Option<u32>,
+    pub mem_usage: Option<u32>,
+    /// edge temperature
+    pub temp_c: Option<u32>,
+    /// memory junction and hotspot, `None` on cards that don't expose them
+    pub mem_temp_c: Option<u32>,
+    pub hotspot_c: Option<u32>,
+    pub power_w: Option<u32>,
+    /// the limit the driver enforces, lowered by `nvidia-smi -pl` or in the AMD driver
+    pub power_limit_w: Option<u32>,
+    /// the limit the card ships with
+    pub power_default_limit_w: Option<u32>,
+    /// watt-hours since hwtop started, `None` without power readings
+    #[serde(default)]
+    pub energy_wh: Option<f64>,
+    pub vram_used: Option<u64>,
+    pub vram_total: Option<u64>,
+    /// the window of VRAM the CPU can map, pinned host-visible allocations can fill it before VRAM
+    pub bar1_used: Option<u64>,
+    pub bar1_total: Option<u64>,
+    pub clocks: Vec<ClockSample>,
+    pub fans: Option<Vec<FanSample>>,
+    pub pcie: Option<PcieSample>,
+    /// `None` on GPUs without ECC memory
+    pub ecc: Option<EccSample>,
+    /// empty without NVLink
+    pub nvlinks: Vec<NvLinkSample>,
+    /// NVENC and NVDEC utilization in percent, `None` on cards without them and on AMD
+    pub encoder: Option<u32>,
+    pub decoder: Option<u32>,
+    /// `None` when not requested or the backend can't list processes
+    pub processes: Option<Vec<GpuProcessSample>>,
+}
+
+/// Every detected GPU across the NVIDIA and AMD backends, each compiled in by its cargo feature
+/// (`nvidia` and `amd`, both default), without either there are never any GPUs like with `--no-gpu`
+#[derive(Default)]
+pub struct Gpus {
+    #[cfg(feature = "nvidia")]
+    pub nvml: Option<Nvml>,
+    #[cfg(feature = "nvidia")]
+    nvidia: Vec<nvidia::NvidiaGpu>,
+    #[cfg(feature = "amd")]
+    amd: Vec<amd::AmdGpu>,
+    /// NVLink byte counters of the last tick by GPU and link, rates are the difference
+    #[cfg(feature = "nvidia")]
+    nvlink_counters: HashMap<(u32, u32), nvidia::LinkCounters>,
+    /// lost GPUs and a driver that failed to load, until `take_errors`
+    errors: Vec<HwtopError>,
+}
+
+impl Gpus {
+    pub fn new(no_gpu: bool) -> Self {
+        if no_gpu {
+            return Self::default();
+        }
+        // no NVIDIA card or driver is the usual case, only a driver that's there and fails is worth reporting
+        #[cfg(feature = "nvidia")]
+        let (nvml, errors) = match Nvml::init() {
+            Ok(nvml) => (Some(nvml), vec![]),
+            Err(NvmlError::LibloadingError(_) | NvmlError::LibraryNotFound | NvmlError::DriverNotLoaded | NvmlError::NoPermission) => (None, vec![]),
+            Err(e) => (None, vec![HwtopError::nvml("loading the driver")(e)]),
+        };
+        Self {
+            #[cfg(feature = "nvidia")]
+            nvidia: nvml.as_ref().map(nvidia::devices).unwrap_or_default(),
+            #[cfg(feature = "nvidia")]
+            nvml,
+            #[cfg(feature = "amd")]
+            amd: amd::AmdGpu::detect(),
+            #[cfg(feature = "nvidia")]
+            nvlink_counters: HashMap::new(),
+            #[cfg(feature = "nvidia")]
+            errors,
+            #[cfg(not(feature = "nvidia"))]
+            errors: vec![],
+        }
+    }
+
+    /// Errors since the last call, each GPU's rows are simply gone after it
+    pub fn take_errors(&mut self) -> Vec<HwtopError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    #[cfg(feature = "amd")]
+    pub fn amd(&self) -> &[amd::AmdGpu] {
+        &self.amd
+    }
+
+    /// `procs` also lists the processes running on each GPU, which only NVIDIA supports
+    #[cfg_attr(not(feature = "nvidia"), allow(unused_variables, unused_mut))]
+    pub fn sample(&mut self, procs: bool) -> Vec<GpuSample> {
+        let mut samples = vec![];
+        #[cfg(feature = "nvidia")]
+        if let Some(nvml) = &self.nvml {
+            // a lost GPU is dropped and its rows disappear, other errors only blank the readings for a tick
+            let (counters, errors) = (&mut self.nvlink_counters, &mut self.errors);
+            self.nvidia.retain(|gpu| match nvidia::sample(nvml, gpu, procs, counters) {
+                Ok(sample) => {
+                    samples.push(sample);
+                    true
+                }
+                Err(e) => {
+                    errors.push(HwtopError::nvml(format!("GPU {} ({})", gpu.index, gpu.name))(e));
+                    false
+                }
+            });
+        }
+        #[cfg(feature = "amd")]
+        samples.extend(self.amd.iter().map(amd::AmdGpu::sample));
+        samples
+    }
+}
+
+/// Max PCIe link throughput in B/s
+#[must_use]
+pub fn pcie_max_throughput(pcie_gen: u32, pcie_width: u32) -> u64 {
+    // PCIe throughput per lane in KB/s (accounting for encoding overhead)
+    let pcie_throughput_per_lane = match pcie_gen {
+        1 => 250,   // PCIe 1.0: 2.5 GT/s * 0.8 (8b/10b encoding) / 8 bits = 250 MB/s
+        2 => 500,   // PCIe 2.0: 5.0 GT/s * 0.8 / 8 = 500 MB/s
+        3 => 985,   // PCIe 3.0: 8.0 GT/s * 0.9846 (128b/130b encoding) / 8 = 985 MB/s
+        4 => 1969,  // PCIe 4.0: 16.0 GT/s * 0.9846 / 8 = 1969 MB/s
+        5 => 3938,  // PCIe 5.0: 32.0 GT/s * 0.9846 / 8 = 3938 MB/s
+        _ => 1969,  // Default to PCIe 4.0 if unknown
+    } * 1000;
+    pcie_throughput_per_lane as u64 * pcie_width as u64 * 1000
+}
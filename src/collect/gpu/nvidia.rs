@@ -0,0 +1,171 @@
+use nvml_wrapper::{enum_wrappers::{device::{Clock, EccCounter, MemoryError, PcieUtilCounter, TemperatureSensor}, nv_link::UtilizationCountUnit}, enums::{device::{SampleValue, UsedGpuMemory}, nv_link::Counter}, error::NvmlError, structs::device::FieldId, sys_exports::field_id::NVML_FI_DEV_MEMORY_TEMP, Device, Nvml};
+use std::{collections::HashMap, time::Instant};
+
+use crate::collect::sysfs;
+use super::{pcie_max_throughput, ClockSample, EccSample, FanSample, GpuProcessKind, GpuProcessSample, GpuSample, NvLinkSample, PcieLink, PcieSample};
+
+/// NVML_NVLINK_MAX_LINKS, NVML answers with an error past a GPU's last link
+pub const MAX_NVLINKS: u32 = 18;
+
+/// Received and sent bytes of one link and when they were read
+pub struct LinkCounters {
+    at: Instant,
+    rx: u64,
+    tx: u64,
+}
+
+/// Clocks sampled for every GPU with their labels
+const CLOCKS: [(&str, Clock); 4] = [("GFX", Clock::Graphics), ("MEM", Clock::Memory), ("SM", Clock::SM), ("VID", Clock::Video)];
+
+/// What NVML reports about a GPU that doesn't change while it runs, queried once instead of every tick
+pub struct NvidiaGpu {
+    pub index: u32,
+    pub name: String,
+    bus_id: Option<String>,
+    max_clocks: [Option<u32>; CLOCKS.len()],
+    num_fans: u32,
+    max_pcie_gen: Option<u32>,
+    max_pcie_width: Option<u32>,
+    power_default_limit_w: Option<u32>,
+}
+
+/// Every NVIDIA GPU, short names drop the `NVIDIA GeForce` prefix
+pub fn devices(nvml: &Nvml) -> Vec<NvidiaGpu> {
+    let count = nvml.device_count().unwrap_or(0);
+    (0..count).map(|index| {
+        let gpu = nvml.device_by_index(index).ok();
+        let name = gpu.as_ref().and_then(|gpu| gpu.name().ok()).unwrap_or_default();
+        NvidiaGpu {
+            index,
+            name: name.replace("NVIDIA ", "").replace("GeForce ", ""),
+            bus_id: gpu.as_ref().and_then(|gpu| gpu.pci_info().ok()).map(|pci| pci.bus_id),
+            max_clocks: CLOCKS.map(|(_, clock)| gpu.as_ref().and_then(|gpu| gpu.max_clock_info(clock).ok())),
+            num_fans: gpu.as_ref().and_then(|gpu| gpu.num_fans().ok()).unwrap_or(1),
+            max_pcie_gen: gpu.as_ref().and_then(|gpu| gpu.max_pcie_link_gen().ok()),
+            max_pcie_width: gpu.as_ref().and_then(|gpu| gpu.max_pcie_link_width().ok()),
+            power_default_limit_w: gpu.as_ref().and_then(|gpu| gpu.power_management_limit_default().ok()).map(|p| p / 1000),
+        }
+    }).collect()
+}
+
+/// Samples one GPU, the error when NVML reports it's gone for good
+pub fn sample(nvml: &Nvml, device: &NvidiaGpu, procs: bool, link_counters: &mut HashMap<(u32, u32), LinkCounters>) -> Result<GpuSample, NvmlError> {
+    let NvidiaGpu { index, ref name, ref bus_id, max_clocks, num_fans, max_pcie_gen: pcie_gen, max_pcie_width: pcie_width, power_default_limit_w } = *device;
+    let gpu = match nvml.device_by_index(index) {
+        Ok(gpu) => gpu,
+        Err(e @ (NvmlError::GpuLost | NvmlError::NotFound)) => return Err(e),
+        Err(_) => return Ok(GpuSample { name: name.clone(), bus_id: bus_id.clone(), ..Default::default() }),
+    };
+    let utilization = gpu.utilization_rates().ok();
+    let memory = gpu.memory_info().ok();
+    let bar1 = gpu.bar1_memory_info().ok();
+    let clocks = CLOCKS.iter().zip(max_clocks).map(|(&(name, clock), max_mhz)| ClockSample {
+        name: name.to_string(),
+        mhz: gpu.clock_info(clock).ok(),
+        max_mhz,
+    }).collect();
+    let fans = (0..num_fans).map(|i| FanSample {
+        percent: Some(gpu.fan_speed(i).unwrap_or(0)),
+        rpm: Some(gpu.fan_speed_rpm(i).unwrap_or(0)),
+    }).collect();
+    let max_bps = pcie_max_throughput(pcie_gen.unwrap_or(0), pcie_width.unwrap_or(16));
+    let link = PcieLink::from(gpu.current_pcie_link_gen().ok(), gpu.current_pcie_link_width().ok());
+    let throughput = |counter| gpu.pcie_throughput(counter).ok().map(|kbs| kbs as u64 * 50 * 1000);
+    Ok(GpuSample {
+        name: name.clone(),
+        bus_id: bus_id.clone(),
+        usage: utilization.as_ref().map(|u| u.gpu),
+        mem_usage: utilization.as_ref().map(|u| u.memory),
+        temp_c: gpu.temperature(TemperatureSensor::Gpu).ok(),
+        mem_temp_c: mem_temp(&gpu),
+        // NVML has no hotspot sensor
+        hotspot_c: None,
+        power_w: gpu.power_usage().ok().map(|p| p / 1000),
+        // the enforced limit also counts other caps than the management one, like a thermal limit
+        power_limit_w: gpu.enforced_power_limit().or_else(|_| gpu.power_management_limit()).ok().map(|p| p / 1000),
+        power_default_limit_w,
+        energy_wh: None,
+        vram_used: memory.as_ref().map(|m| m.used),
+        vram_total: memory.as_ref().map(|m| m.total),
+        bar1_used: bar1.as_ref().map(|b| b.used),
+        bar1_total: bar1.as_ref().map(|b| b.total),
+        clocks,
+        fans: Some(fans),
+        pcie: Some(PcieSample {
+            rx_bps: throughput(PcieUtilCounter::Receive),
+            tx_bps: throughput(PcieUtilCounter::Send),
+            max_bps,
+            link_bps: link.map_or(max_bps, PcieLink::bps),
+            max_link: PcieLink::from(pcie_gen, pcie_width),
+            link,
+        }),
+        nvlinks: nvlinks(&gpu, index, link_counters),
+        // consumer cards answer NotSupported
+        ecc: gpu.is_ecc_enabled().ok().map(|mode| EccSample {
+            enabled: mode.currently_enabled,
+            corrected: gpu.total_ecc_errors(MemoryError::Corrected, EccCounter::Volatile).ok(),
+            uncorrected: gpu.total_ecc_errors(MemoryError::Uncorrected, EccCounter::Volatile).ok(),
+        }),
+        // the sampling period that comes with the percentage isn't needed
+        encoder: gpu.encoder_utilization().ok().map(|u| u.utilization),
+        decoder: gpu.decoder_utilization().ok().map(|u| u.utilization),
+        processes: procs.then(|| processes(&gpu)).flatten(),
+    })
+}
+
+/// Memory junction temperature from the field values API, only GDDR6X and HBM cards report it
+fn mem_temp(gpu: &Device) -> Option<u32> {
+    let sample = gpu.field_values_for(&[FieldId(NVML_FI_DEV_MEMORY_TEMP)]).ok()?.into_iter().next()?.ok()?;
+    match sample.value.ok()? {
+        SampleValue::U32(temp) => Some(temp),
+        SampleValue::U64(temp) => Some(temp as u32),
+        SampleValue::I64(temp) => u32::try_from(temp).ok(),
+        SampleValue::F64(temp) => Some(temp.round() as u32),
+    }.filter(|&temp| temp > 0)
+}
+
+/// Every NVLink with its rates since the last tick, the first link's error ends the list
+fn nvlinks(gpu: &Device, index: u32, link_counters: &mut HashMap<(u32, u32), LinkCounters>) -> Vec<NvLinkSample> {
+    let mut links = vec![];
+    for link in 0..MAX_NVLINKS {
+        let nvlink = gpu.link_wrapper_for(link);
+        let Ok(active) = nvlink.is_active() else {
+            break;
+        };
+        // counters have no default unit, only byte counts make rates
+        let counts_bytes = nvlink.utilization_control(Counter::Zero).is_ok_and(|control| control.units == UtilizationCountUnit::Bytes);
+        let counter = nvlink.utilization_counter(Counter::Zero).ok().filter(|_| active && counts_bytes);
+        let now = counter.map(|counter| LinkCounters { at: Instant::now(), rx: counter.receive, tx: counter.send });
+        let last = match now {
+            Some(now) => link_counters.insert((index, link), now),
+            None => link_counters.remove(&(index, link)),
+        };
+        let rates = last.zip(link_counters.get(&(index, link))).map(|(last, now)| {
+            let secs = now.at.duration_since(last.at).as_secs_f64().max(0.001);
+            ((now.rx.saturating_sub(last.rx) as f64 / secs) as u64, (now.tx.saturating_sub(last.tx) as f64 / secs) as u64)
+        });
+        links.push(NvLinkSample { link, active, rx_bps: rates.map(|(rx, _)| rx), tx_bps: rates.map(|(_, tx)| tx) });
+    }
+    links
+}
+
+/// Compute and graphics processes with their VRAM and, when supported, latest SM utilization
+fn processes(gpu: &Device) -> Option<Vec<GpuProcessSample>> {
+    let compute = gpu.running_compute_processes().ok()?;
+    let graphics = gpu.running_graphics_processes().ok()?;
+    // the driver buffers several samples per process, keep the newest
+    let mut utilization = gpu.process_utilization_stats(None).unwrap_or_default();
+    utilization.sort_by_key(|sample| std::cmp::Reverse(sample.timestamp));
+    let compute = compute.into_iter().map(|info| (GpuProcessKind::Compute, info));
+    let graphics = graphics.into_iter().map(|info| (GpuProcessKind::Graphics, info));
+    Some(compute.chain(graphics).map(|(kind, info)| GpuProcessSample {
+        pid: info.pid,
+        name: sysfs::read(format!("/proc/{}/comm", info.pid)).unwrap_or_else(|| "?".to_string()),
+        kind,
+        vram_used: match info.used_gpu_memory {
+            UsedGpuMemory::Used(bytes) => Some(bytes),
+            UsedGpuMemory::Unavailable => None,
+        },
+        sm: utilization.iter().find(|sample| sample.pid == info.pid).map(|sample| sample.sm_util),
+    }).collect())
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::System;
+
+/// Which machine this is, read once since none of it changes while hwtop runs
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct HostSample {
+    pub hostname: Option<String>,
+    /// distro name and version, e.g. `Ubuntu` `24.04`
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub kernel: Option<String>,
+    /// seconds since the Unix epoch
+    pub boot_time: u64,
+}
+
+pub fn sample() -> HostSample {
+    HostSample {
+        hostname: System::host_name(),
+        os: System::name(),
+        os_version: System::os_version(),
+        kernel: System::kernel_version(),
+        boot_time: System::boot_time(),
+    }
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use super::sysfs;
+
+/// Fan read straight from hwmon, sysinfo's Components only covers temperatures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FanReading {
+    pub label: String,
+    pub rpm: u32,
+    pub max_rpm: Option<u32>,
+    /// duty cycle of the matching `pwm*` output, else rpm as percent of `max_rpm`
+    pub percent: Option<u32>,
+}
+
+/// One tick of hwmon readings
+#[derive(Clone, Default)]
+pub struct HwmonSample {
+    pub fans: Vec<FanReading>,
+    pub rails: Vec<RailReading>,
+    /// by block device name, disks without a sensor are left out
+    pub disk_temps: HashMap<String, u32>,
+}
+
+/// Fan, voltage, current and power inputs of `/sys/class/hwmon` found once, only their values are read every tick.
+/// GPU drivers are skipped since their fans are read with the GPU and they have their own power rows
+#[derive(Default)]
+pub struct Hwmon {
+    fans: Vec<FanInput>,
+    rails: Vec<RailInput>,
+    /// hwmon dir of each disk's temperature sensor, looked up the first time a disk is sampled
+    disk_temps: HashMap<String, Option<PathBuf>>,
+}
+
+struct FanInput {
+    label: String,
+    input: PathBuf,
+    pwm: PathBuf,
+    max_rpm: Option<u32>,
+}
+
+struct RailInput {
+    chip: String,
+    label: String,
+    unit: RailUnit,
+    input: PathBuf,
+}
+
+impl Hwmon {
+    /// CPU fans first then by chip and index so they keep their place between frames
+    pub fn detect() -> Self {
+        let Ok(entries) = std::fs::read_dir("/sys/class/hwmon") else {
+            return Self::default();
+        };
+        let mut hwmons: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+        hwmons.sort();
+        let (mut fans, mut rails) = (vec![], vec![]);
+        for hwmon in hwmons {
+            let chip = sysfs::read(hwmon.join("name")).unwrap_or_default();
+            if ["amdgpu", "nouveau", "radeon"].contains(&chip.as_str()) {
+                continue;
+            }
+            let Ok(files) = std::fs::read_dir(&hwmon) else {
+                continue;
+            };
+            // rails go volts, amps then watts like the kinds are listed
+            let mut inputs: Vec<(usize, &str, u32)> = files.flatten().filter_map(|e| {
+                let name = e.file_name().into_string().ok()?;
+                let input = name.strip_suffix("_input")?;
+                ["fan", "in", "curr", "power"].into_iter().enumerate().find_map(|(rank, prefix)| Some((rank, prefix, input.strip_prefix(prefix)?.parse().ok()?)))
+            }).collect();
+            inputs.sort();
+            for (_, kind, i) in inputs {
+                let file = |suffix: &str| hwmon.join(format!("{kind}{i}_{suffix}"));
+                let unit = match kind {
+                    "fan" => {
+                        fans.push(FanInput {
+                            label: sysfs::read(file("label")).unwrap_or_else(|| format!("{chip} fan{i}")),
+                            input: file("input"),
+                            pwm: hwmon.join(format!("pwm{i}")),
+                            max_rpm: sysfs::read_num(file("max")).filter(|&max| max > 0),
+                        });
+                        continue;
+                    }
+                    "in" => RailUnit::Volts,
+                    "curr" => RailUnit::Amps,
+                    _ => RailUnit::Watts,
+                };
+                // unlabeled inputs are mostly unconnected pins of Super I/O chips
+                if let Some(label) = sysfs::read(file("label")) {
+                    rails.push(RailInput { chip: chip.clone(), label, unit, input: file("input") });
+                }
+            }
+        }
+        fans.sort_by_key(|fan| !fan.label.to_lowercase().contains("cpu"));
+        Self { fans, rails, disk_temps: HashMap::new() }
+    }
+
+    fn fans(&self) -> Vec<FanReading> {
+        self.fans.iter().filter_map(|fan| {
+            // unreadable inputs are sensors the chip advertises but has nothing wired to
+            let rpm = sysfs::read_num(&fan.input)?;
+            // pwm outputs go from 0 to 255
+            let pwm = sysfs::read_num::<u32>(&fan.pwm).map(|pwm| (pwm.min(255) * 100 + 127) / 255);
+            Some(FanReading {
+                label: fan.label.clone(),
+                rpm,
+                max_rpm: fan.max_rpm,
+                percent: pwm.or_else(|| fan.max_rpm.map(|max| (rpm as f32 / max as f32 * 100.0).round() as u32)),
+            })
+        }).collect()
+    }
+
+    /// Labeled `in*_input` (mV), `curr*_input` (mA) and `power*_input` (µW)
+    fn rails(&self) -> Vec<RailReading> {
+        self.rails.iter().filter_map(|rail| {
+            let raw = sysfs::read_num::<f64>(&rail.input)?;
+            let scale = if rail.unit == RailUnit::Watts { 1e6 } else { 1e3 };
+            Some(RailReading { chip: rail.chip.clone(), label: rail.label.clone(), unit: rail.unit, value: (raw / scale) as f32 })
+        }).collect()
+    }
+
+    /// Fans, rails and the temperature of each of `disks`
+    pub fn sample(&mut self, disks: &[String]) -> HwmonSample {
+        let disk_temps = disks.iter().filter_map(|name| Some((name.clone(), self.disk_temp(name)?))).collect();
+        HwmonSample { fans: self.fans(), rails: self.rails(), disk_temps }
+    }
+
+    /// Drive temperature in °C for a block device or partition name like `nvme0n1p2` or `sda1`,
+    /// from the hwmon of the nvme controller or the drivetemp driver for SATA drives
+    fn disk_temp(&mut self, name: &str) -> Option<u32> {
+        let hwmon = self.disk_temps.entry(name.to_string()).or_insert_with(|| disk_hwmon(name)).as_ref()?;
+        // temp1 is the composite sensor on nvme
+        let millis: i64 = sysfs::read_num(hwmon.join("temp1_input"))?;
+        Some((millis as f32 / 1000.0).round().max(0.0) as u32)
+    }
+}
+
+/// Quantity of a `RailReading`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RailUnit {
+    Volts,
+    Amps,
+    Watts,
+}
+
+impl RailUnit {
+    #[must_use]
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Volts => "V",
+            Self::Amps => "A",
+            Self::Watts => "W",
+        }
+    }
+}
+
+/// Labeled voltage, current or power of a board or PSU chip, sysinfo only reads temperatures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RailReading {
+    pub chip: String,
+    pub label: String,
+    pub unit: RailUnit,
+    pub value: f32,
+}
+
+impl RailReading {
+    /// ATX allows ±5% on the main rails, other rails don't say what they should be
+    #[must_use]
+    pub fn out_of_range(&self) -> bool {
+        self.unit == RailUnit::Volts && nominal_volts(&self.label).is_some_and(|nominal| (self.value - nominal).abs() > nominal * 0.05)
+    }
+}
+
+/// Nominal voltage of a named rail, `+12V`, `5VSB`, `3V3`
+fn nominal_volts(label: &str) -> Option<f32> {
+    let label = label.trim_start_matches('+').replace(' ', "").to_uppercase();
+    [("12V", 12.0), ("5V", 5.0), ("3.3V", 3.3), ("3V3", 3.3), ("3VSB", 3.3), ("3VCC", 3.3)].into_iter()
+        .find(|(prefix, _)| label.starts_with(prefix)).map(|(_, nominal)| nominal)
+}
+
+fn disk_hwmon(name: &str) -> Option<PathBuf> {
+    let block = std::fs::canonicalize(Path::new("/sys/class/block").join(name)).ok()?;
+    let disk = if block.join("partition").exists() { block.parent()? } else { &block };
+    let device = disk.join("device");
+    // nvme controllers have hwmonN directly in the device dir, drivetemp nests it under hwmon/
+    [device.clone(), device.join("hwmon")].into_iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .find(|e| e.file_name().to_str().is_some_and(|n| n.starts_with("hwmon") && n != "hwmon"))
+        .map(|e| e.path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn main_rails_out_of_atx_range() {
+        let rail = |label: &str, unit, value| RailReading { chip: "corsairpsu".to_string(), label: label.to_string(), unit, value };
+        assert!(rail("+12V", RailUnit::Volts, 11.3).out_of_range());
+        assert!(!rail("+12V", RailUnit::Volts, 11.5).out_of_range());
+        assert!(rail("5VSB", RailUnit::Volts, 5.4).out_of_range());
+        assert!(!rail("3V3", RailUnit::Volts, 3.35).out_of_range());
+        assert!(!rail("Vcore", RailUnit::Volts, 1.4).out_of_range());
+        assert!(!rail("+12V", RailUnit::Amps, 30.0).out_of_range());
+    }
+}
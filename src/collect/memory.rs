@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::System;
+
+use super::sysfs;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct MemorySample {
+    /// total minus available, so page cache the kernel can drop doesn't count
+    pub ram_used: u64,
+    pub ram_total: u64,
+    /// page cache and buffers, `None` without `/proc/meminfo`
+    pub ram_cached: Option<u64>,
+    /// written to but not yet flushed to disk
+    pub ram_dirty: Option<u64>,
+    pub swap_used: u64,
+    pub swap_total: u64,
+    /// pages swapped in and out as bytes per second, `None` without `/proc/vmstat`
+    pub swap_in_bps: Option<u64>,
+    pub swap_out_bps: Option<u64>,
+    /// pages were swapped for several ticks in a row
+    pub swapping: bool,
+    /// summed over every zram device, `None` without any
+    pub zram: Option<Compressed>,
+    /// `None` unless zswap is enabled
+    pub zswap: Option<Compressed>,
+    /// one per NUMA node, empty on single node machines
+    pub numa: Vec<NumaNode>,
+    /// pools with any pages set aside, part of `ram_used` whether mapped or not
+    pub hugepages: Vec<HugePages>,
+}
+
+/// Hugepage pool of one page size
+#[derive(Serialize, Deserialize)]
+pub struct HugePages {
+    /// page size in bytes
+    pub size: u64,
+    pub total: u64,
+    pub free: u64,
+}
+
+impl HugePages {
+    /// Bytes set aside that nothing has mapped
+    #[must_use]
+    pub fn unused(&self) -> u64 {
+        self.free * self.size
+    }
+}
+
+/// Memory of one NUMA node, nodes have no `MemAvailable` so page cache counts as free like it does for `ram_used`
+#[derive(Serialize, Deserialize)]
+pub struct NumaNode {
+    pub node: u32,
+    pub used: u64,
+    pub total: u64,
+    /// file backed pages
+    pub cached: u64,
+}
+
+/// Pages kept compressed in RAM by zram or zswap, in bytes
+#[derive(Serialize, Deserialize)]
+pub struct Compressed {
+    pub original: u64,
+    pub compressed: u64,
+}
+
+impl Compressed {
+    #[must_use]
+    pub fn ratio(&self) -> f32 {
+        self.original as f32 / self.compressed.max(1) as f32
+    }
+}
+
+/// Ticks in a row with swap traffic before it counts as sustained swapping
+const SUSTAINED_TICKS: u32 = 3;
+
+/// `pswpin`/`pswpout` page counters from `/proc/vmstat`, kept to diff against the next tick
+#[derive(Default)]
+pub struct SwapActivity {
+    last: Option<(u64, u64)>,
+    busy_ticks: u32,
+}
+
+impl SwapActivity {
+    /// Bytes per second swapped in and out since the previous call, `delta` is the elapsed seconds
+    fn sample(&mut self, delta: f32) -> Option<(u64, u64)> {
+        let vmstat = sysfs::read("/proc/vmstat")?;
+        let counter = |name: &str| vmstat.lines().find_map(|line| line.strip_prefix(name)?.strip_prefix(' ')?.parse::<u64>().ok());
+        let now = counter("pswpin").zip(counter("pswpout"))?;
+        let last = self.last.replace(now).unwrap_or(now);
+        let page_size = page_size();
+        let rate = |pages: u64| (pages as f32 * page_size as f32 / delta.max(f32::EPSILON)) as u64;
+        let rates = (rate(now.0.saturating_sub(last.0)), rate(now.1.saturating_sub(last.1)));
+        self.busy_ticks = if rates != (0, 0) { self.busy_ticks + 1 } else { 0 };
+        Some(rates)
+    }
+}
+
+/// `delta` is the seconds since the previous sample
+pub fn sample(sys: &System, swap: &mut SwapActivity, delta: f32) -> MemorySample {
+    let meminfo = sysfs::read("/proc/meminfo").unwrap_or_default();
+    let field = |name: &str| meminfo_field(&meminfo, name);
+    let swap_rates = swap.sample(delta);
+    MemorySample {
+        ram_used: sys.used_memory(),
+        ram_total: sys.total_memory(),
+        ram_cached: field("Cached").zip(field("Buffers")).map(|(cached, buffers)| cached + buffers),
+        ram_dirty: field("Dirty"),
+        swap_used: sys.used_swap(),
+        swap_total: sys.total_swap(),
+        swap_in_bps: swap_rates.map(|(swap_in, _)| swap_in),
+        swap_out_bps: swap_rates.map(|(_, swap_out)| swap_out),
+        swapping: swap.busy_ticks >= SUSTAINED_TICKS,
+        zram: zram(),
+        zswap: zswap(&meminfo),
+        numa: numa(),
+        hugepages: hugepages(&meminfo),
+    }
+}
+
+/// Every `/sys/kernel/mm/hugepages/hugepages-<size>kB` pool, `/proc/meminfo` only has the default size
+fn hugepages(meminfo: &str) -> Vec<HugePages> {
+    let pools = std::fs::read_dir("/sys/kernel/mm/hugepages").map(|entries| entries.flatten().filter_map(|e| {
+        let size = e.file_name().to_str()?.strip_prefix("hugepages-")?.strip_suffix("kB")?.parse::<u64>().ok()? * 1024;
+        Some(HugePages { size, total: sysfs::read_num(e.path().join("nr_hugepages"))?, free: sysfs::read_num(e.path().join("free_hugepages"))? })
+    }).collect());
+    let mut pools: Vec<HugePages> = pools.unwrap_or_else(|_| {
+        // the page counts have no kB after them
+        let count = |name: &str| meminfo_field(meminfo, name).map(|count| count / 1024);
+        count("HugePages_Total").zip(count("HugePages_Free")).zip(meminfo_field(meminfo, "Hugepagesize"))
+            .map(|((total, free), size)| HugePages { size, total, free }).into_iter().collect()
+    });
+    pools.retain(|pool| pool.total > 0);
+    pools.sort_by_key(|pool| pool.size);
+    pools
+}
+
+/// `/sys/devices/system/node/node*/meminfo` lines look like `Node 1 MemFree:  1234 kB`
+fn numa() -> Vec<NumaNode> {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        return vec![];
+    };
+    let mut nodes: Vec<NumaNode> = entries.flatten().filter_map(|e| {
+        let node = e.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+        let meminfo = sysfs::read(e.path().join("meminfo"))?;
+        let prefix = format!("Node {node} ");
+        let field = |name: &str| meminfo.lines().filter_map(|line| line.strip_prefix(&prefix)).find_map(|line| meminfo_field(line, name));
+        let (total, free, cached) = (field("MemTotal")?, field("MemFree")?, field("FilePages").unwrap_or(0));
+        Some(NumaNode { node, used: total.saturating_sub(free + cached), total, cached })
+    }).collect();
+    if nodes.len() < 2 {
+        return vec![];
+    }
+    nodes.sort_by_key(|node| node.node);
+    nodes
+}
+
+/// `mm_stat` starts with the original and the compressed size of what's stored
+fn zram() -> Option<Compressed> {
+    let mut devices = std::fs::read_dir("/sys/block").ok()?.flatten().filter(|e| e.file_name().to_string_lossy().starts_with("zram")).peekable();
+    devices.peek()?;
+    let (mut original, mut compressed) = (0, 0);
+    for device in devices {
+        let stat = sysfs::read(device.path().join("mm_stat")).unwrap_or_default();
+        let mut fields = stat.split_whitespace().map(|field| field.parse::<u64>().unwrap_or(0));
+        original += fields.next().unwrap_or(0);
+        compressed += fields.next().unwrap_or(0);
+    }
+    Some(Compressed { original, compressed })
+}
+
+/// `/proc/meminfo` has the sizes since 5.19, older kernels only in debugfs which is root only
+fn zswap(meminfo: &str) -> Option<Compressed> {
+    if sysfs::read("/sys/module/zswap/parameters/enabled")? != "Y" {
+        return None;
+    }
+    if let Some((compressed, original)) = meminfo_field(meminfo, "Zswap").zip(meminfo_field(meminfo, "Zswapped")) {
+        return Some(Compressed { original, compressed });
+    }
+    let original = sysfs::read_num::<u64>("/sys/kernel/debug/zswap/stored_pages")? * page_size();
+    let compressed = sysfs::read_num("/sys/kernel/debug/zswap/pool_total_size")?;
+    Some(Compressed { original, compressed })
+}
+
+#[cfg(unix)]
+fn page_size() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64
+}
+
+/// Only used for the `/proc` page counters, which Windows doesn't have anyway
+#[cfg(not(unix))]
+fn page_size() -> u64 {
+    4096
+}
+
+/// `Name:   1234 kB` line of `/proc/meminfo` in bytes
+fn meminfo_field(meminfo: &str, name: &str) -> Option<u64> {
+    let line = meminfo.lines().find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?;
+    Some(line.split_whitespace().next()?.parse::<u64>().ok()? * 1024)
+}
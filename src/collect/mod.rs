@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{Components, Disks, Networks, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System};
+use std::{sync::{Arc, Mutex, MutexGuard, PoisonError}, time::{Duration, Instant, SystemTime}};
+
+use crate::{args::Args, error::HwtopError};
+
+pub mod clock;
+pub mod conn;
+pub mod cpu;
+pub mod diskstats;
+pub mod disks;
+pub mod energy;
+pub mod gpu;
+pub mod host;
+pub mod hwmon;
+pub mod memory;
+pub mod net;
+pub mod nvme;
+pub mod processes;
+pub mod procstat;
+pub mod psi;
+pub mod rapl;
+pub mod sensors;
+pub mod sysfs;
+pub mod throttle;
+pub mod wifi;
+pub mod worker;
+
+use clock::Clock;
+use conn::ConnSample;
+use cpu::{CpuSample, MaxFreqs};
+use diskstats::DiskStats;
+use disks::{DiskOrder, DiskSample, MountSample};
+use energy::Energy;
+use gpu::{GpuSample, Gpus};
+use host::HostSample;
+use hwmon::{FanReading, Hwmon, HwmonSample, RailReading};
+use memory::{MemorySample, SwapActivity};
+use net::{NetSample, NetTotals};
+use processes::ProcessSample;
+use procstat::{ActivitySample, ProcStat};
+use psi::PsiSample;
+use rapl::Rapl;
+use sensors::{ComponentSample, Temps};
+use throttle::Throttle;
+use wifi::Wifi;
+use worker::Worker;
+
+/// How long a tick waits for hwmon once everything else is read, a late answer shows up dimmed on a later tick
+const HWMON_DEADLINE: Duration = Duration::from_millis(50);
+/// How long a tick waits for the GPUs and connections once everything else is read, driver queries take longer than sysfs
+const BACKGROUND_DEADLINE: Duration = Duration::from_millis(250);
+/// The first tick has no earlier answer to fall back on, it waits this long for one
+const FIRST_DEADLINE: Duration = Duration::from_secs(2);
+
+/// GPUs and connections with the milliseconds each took
+type Background = (Vec<GpuSample>, f32, Option<ConnSample>, f32);
+
+/// Everything shown in one frame, sizes are in bytes, rates per second, temps in °C and clocks in MHz
+#[derive(Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub host: HostSample,
+    /// 1, 5 and 15 minute load averages
+    pub load: [f64; 3],
+    pub uptime_s: u64,
+    /// context switches, interrupts and forks per second
+    pub activity: ActivitySample,
+    pub cpu: CpuSample,
+    pub memory: MemorySample,
+    /// `None` on kernels without PSI
+    pub psi: Option<PsiSample>,
+    pub gpus: Vec<GpuSample>,
+    pub fans: Vec<FanReading>,
+    /// labeled voltages, currents and powers of board and PSU chips
+    pub rails: Vec<RailReading>,
+    pub networks: Vec<NetSample>,
+    /// `None` when `/proc/net` isn't readable
+    pub connections: Option<ConnSample>,
+    pub disks: Vec<DiskSample>,
+    pub mounts: Vec<MountSample>,
+    pub processes: Vec<ProcessSample>,
+    pub components: Vec<ComponentSample>,
+    /// fans, rails, disk and component temps are from an earlier tick, reading them missed the deadline
+    #[serde(default)]
+    pub sensors_stale: bool,
+    /// seconds since the previous snapshot when the machine was suspended or stalled in between, the rates are left out then
+    #[serde(default)]
+    pub resumed_after_s: Option<u64>,
+    /// only with `--debug-timing`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+}
+
+/// Milliseconds spent on a snapshot, for `--debug-timing`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Timing {
+    /// the whole snapshot, the parts on other threads overlap with the rest of it
+    pub collect_ms: f32,
+    /// `None` when the GPUs and connections missed the deadline
+    pub gpus_ms: Option<f32>,
+    pub connections_ms: Option<f32>,
+    /// `None` when hwmon missed the deadline
+    pub hwmon_ms: Option<f32>,
+}
+
+/// Every source a snapshot is read from, kept between ticks since most of them report rates against their last refresh
+pub struct Collectors {
+    pub sys: System,
+    /// labels of the temperature sensors, their readings are taken on the hwmon thread
+    pub component_labels: Vec<String>,
+    pub disks: Disks,
+    pub nets: Networks,
+    /// GPU rows are skipped when neither the NVIDIA driver nor an amdgpu card is available, shared with the thread reading them
+    gpus: Arc<Mutex<Gpus>>,
+    pub host: HostSample,
+    /// fans, rails, disk temps and the temperature sensors, read on their own thread since embedded controllers and drives can stall
+    hwmon: Worker<Vec<String>, (HwmonSample, Temps, Duration)>,
+    /// the hwmon thread stopping was reported by `take_errors`
+    hwmon_stopped: bool,
+    /// asked for with whether to list GPU processes and connections, read on their own thread since drivers can stall
+    background: Worker<(bool, bool), Background>,
+    /// the GPU thread stopping was reported by `take_errors`
+    background_stopped: bool,
+    max_freqs: MaxFreqs,
+    rapl: Rapl,
+    throttle: Throttle,
+    proc_stat: ProcStat,
+    net_totals: NetTotals,
+    wifi: Wifi,
+    swap: SwapActivity,
+    disk_stats: DiskStats,
+    disk_order: DiskOrder,
+    energy: Energy,
+    clock: Clock,
+    /// seconds the last snapshot covered, per-tick counters are divided by this to get rates
+    pub delta: f32,
+    /// wall clock time the last snapshot covered when it spans a suspend, see `Clock::tick`
+    pub gap: Option<Duration>,
+}
+
+/// Processes are only refreshed for `--procs`
+fn process_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing().with_cpu().with_memory().with_disk_usage().without_tasks()
+}
+
+impl Collectors {
+    pub fn new(args: &Args) -> Self {
+        let mut sys = System::new_with_specifics(RefreshKind::everything().without_processes());
+        // CPU usage needs a previous refresh to diff against
+        if args.procs.is_some() {
+            sys.refresh_processes_specifics(ProcessesToUpdate::All, true, process_kind());
+        }
+        let mut components = Components::new_with_refreshed_list();
+        let gpus = Arc::new(Mutex::new(Gpus::new(args.no_gpu)));
+        Self {
+            sys,
+            component_labels: components.iter().map(|component| component.label().to_string()).collect(),
+            disks: Disks::new_with_refreshed_list(),
+            nets: Networks::new_with_refreshed_list(),
+            host: host::sample(),
+            hwmon: {
+                // the inputs are found on the thread too, so a slow chip doesn't hold up the first frame either
+                let mut hwmon = None;
+                Worker::spawn("hwmon", move |disks: Vec<String>| {
+                    let start = Instant::now();
+                    // refreshing the list would rescan every hwmon dir, the sensors themselves don't come and go
+                    components.iter_mut().for_each(|component| component.refresh());
+                    let sample = hwmon.get_or_insert_with(Hwmon::detect).sample(&disks);
+                    (sample, sensors::temps(&components), start.elapsed())
+                })
+            },
+            hwmon_stopped: false,
+            background: {
+                let gpus = Arc::clone(&gpus);
+                Worker::spawn("gpus", move |(procs, connections): (bool, bool)| {
+                    let (gpus, gpus_ms) = timed(|| lock(&gpus).sample(procs));
+                    let (connections, connections_ms) = timed(|| connections.then(conn::sample).flatten());
+                    (gpus, gpus_ms, connections, connections_ms)
+                })
+            },
+            background_stopped: false,
+            gpus,
+            max_freqs: MaxFreqs::default(),
+            rapl: Rapl::detect(),
+            throttle: Throttle::default(),
+            proc_stat: ProcStat::new(),
+            net_totals: NetTotals::default(),
+            wifi: Wifi::default(),
+            swap: SwapActivity::default(),
+            disk_stats: DiskStats::new(),
+            disk_order: DiskOrder::default(),
+            energy: Energy::default(),
+            clock: Clock::new(Instant::now(), SystemTime::now()),
+            delta: 0.0,
+            gap: None,
+        }
+    }
+
+    /// Errors since the last call, each reported once. None of them is fatal today, a GPU or sensor thread failing only loses its rows
+    pub fn take_errors(&mut self) -> Vec<HwtopError> {
+        // a GPU thread stuck on a read holds on to its errors until a later call
+        let mut errors = self.gpus.try_lock().map(|mut gpus| gpus.take_errors()).unwrap_or_default();
+        if self.hwmon.stopped() && !std::mem::replace(&mut self.hwmon_stopped, true) {
+            errors.push(HwtopError::Sensor { context: "hwmon".to_string(), message: "the sensor thread stopped, fans, rails and temps keep their last readings".to_string() });
+        }
+        if self.background.stopped() && !std::mem::replace(&mut self.background_stopped, true) {
+            errors.push(HwtopError::Sensor { context: "gpu".to_string(), message: "the GPU thread stopped, GPUs and connections keep their last readings".to_string() });
+        }
+        errors
+    }
+
+    /// The GPUs for `--info`, waiting for a tick that's reading them
+    pub fn gpus(&self) -> MutexGuard<'_, Gpus> {
+        lock(&self.gpus)
+    }
+
+    #[must_use]
+    pub fn since_refresh(&self) -> Duration {
+        self.clock.since_tick()
+    }
+
+    fn refresh(&mut self, args: &Args) {
+        self.sys.refresh_specifics(RefreshKind::everything().without_processes());
+        if args.procs.is_some() {
+            self.sys.refresh_processes_specifics(ProcessesToUpdate::All, true, process_kind());
+        }
+        self.disks.refresh(true);
+        self.nets.refresh(true);
+
+        (self.delta, self.gap) = self.clock.tick(Instant::now(), SystemTime::now(), args.interval);
+    }
+}
+
+impl Snapshot {
+    /// Refreshes every source and reads them into a snapshot, GPUs and connections on a thread of their own and hwmon on another
+    pub fn collect(collectors: &mut Collectors, args: &Args) -> Self {
+        let start = Instant::now();
+        // the process table shows VRAM too, so GPU processes are listed for it as well
+        let gpu_procs = args.gpu_procs || args.extra || args.procs.is_some();
+        // walking every socket is the slowest read of a tick, only --extra shows them
+        let connections = args.extra || args.json || args.json_stream || args.record.is_some();
+        let disk_names = collectors.disks.iter().filter_map(|disk| disk.name().to_str()?.strip_prefix("/dev/").map(str::to_string)).collect();
+        collectors.hwmon.start(disk_names);
+        collectors.background.start((gpu_procs, connections));
+        collectors.refresh(args);
+        let Collectors { sys, disks, nets, host, hwmon, background, max_freqs, rapl, throttle, proc_stat, net_totals, wifi, swap, disk_stats, disk_order, energy, delta, gap, .. } = collectors;
+        let (delta, gap) = (*delta, *gap);
+
+        let load = System::load_average();
+        let activity = proc_stat.sample(delta);
+        let memory = memory::sample(sys, swap, delta);
+        let psi = psi::sample();
+        let networks = net::sample(nets, net_totals, wifi, delta, args);
+        let mounts = disks::mounts(disks, args.all_mounts);
+        let (hwmon, fresh) = hwmon.finish(first_or(hwmon.answered(), HWMON_DEADLINE));
+        let (hwmon, temps, hwmon_time) = hwmon.cloned().unwrap_or_default();
+        let mut cpu = cpu::sample(sys, &temps, max_freqs, rapl, throttle, delta);
+        let disks = disks::sample(disks, disk_stats, disk_order, &hwmon.disk_temps, delta, args);
+        let (background, background_fresh) = background.finish(first_or(background.answered(), BACKGROUND_DEADLINE));
+        let (mut gpus, gpus_ms, connections, connections_ms) = background.cloned().unwrap_or_default();
+        energy.add(&mut cpu, &mut gpus, delta, gap.is_some());
+        let mut snapshot = Self {
+            host: host.clone(),
+            load: [load.one, load.five, load.fifteen],
+            uptime_s: System::uptime(),
+            activity,
+            cpu,
+            memory,
+            psi,
+            processes: processes::sample(sys, &gpus, delta, args),
+            gpus,
+            fans: hwmon.fans,
+            rails: hwmon.rails,
+            networks,
+            connections,
+            disks,
+            mounts,
+            components: temps.components,
+            sensors_stale: !fresh,
+            resumed_after_s: gap.map(|gap| gap.as_secs()),
+            timing: args.debug_timing.then(|| Timing {
+                collect_ms: start.elapsed().as_secs_f32() * 1000.0,
+                gpus_ms: background_fresh.then_some(gpus_ms),
+                connections_ms: background_fresh.then_some(connections_ms),
+                hwmon_ms: fresh.then_some(hwmon_time.as_secs_f32() * 1000.0),
+            }),
+        };
+        if gap.is_some() {
+            snapshot.drop_rates();
+        }
+        snapshot
+    }
+
+    /// Clears what was counted over a suspend. The counters kept going, or jumped on waking, while the monotonic clock
+    /// rates are divided by stood still, so they'd read hours of traffic as a second of it
+    fn drop_rates(&mut self) {
+        self.activity = ActivitySample::default();
+        self.cpu.power_w = None;
+        self.cpu.packages.iter_mut().for_each(|package| package.power_w = None);
+        (self.memory.swap_in_bps, self.memory.swap_out_bps) = (None, None);
+        for net in &mut self.networks {
+            (net.rx_bps, net.tx_bps, net.rx_pps, net.tx_pps, net.errors) = (0, 0, 0, 0, 0);
+        }
+        for disk in &mut self.disks {
+            (disk.read_bps, disk.write_bps, disk.read_iops, disk.write_iops, disk.util) = (0, 0, None, None, None);
+        }
+        for process in &mut self.processes {
+            (process.read_bps, process.write_bps) = (0, 0);
+        }
+    }
+}
+
+/// `deadline` once a worker has answered, before that there's nothing to fall back on and the wait is longer
+fn first_or(answered: bool, deadline: Duration) -> Duration {
+    if answered { deadline } else { FIRST_DEADLINE }
+}
+
+/// The GPUs, still usable after a panic while reading them since each tick's samples start over
+fn lock(gpus: &Mutex<Gpus>) -> MutexGuard<'_, Gpus> {
+    gpus.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs `f` and returns its result with the milliseconds it took
+fn timed<T>(f: impl FnOnce() -> T) -> (T, f32) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed().as_secs_f32() * 1000.0)
+}
+
+/// Matches `name` against a pattern where `*` matches any run of characters
+#[must_use]
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len()).filter(|&i| name.is_char_boundary(i)).any(|i| glob_match(rest, &name[i..]))
+        }
+    }
+}
+
+/// Rate of a counter that grew by `count` over `delta` seconds
+fn per_sec(count: u64, delta: f32) -> u64 {
+    (count as f32 / delta) as u64
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{NetworkData, Networks};
+use std::collections::HashMap;
+
+use crate::args::Args;
+use super::{glob_match, per_sec, sysfs, wifi::{Wifi, WifiSample}};
+
+#[derive(Serialize, Deserialize)]
+pub struct NetSample {
+    pub name: String,
+    pub rx_bps: u64,
+    pub tx_bps: u64,
+    pub rx_pps: u64,
+    pub tx_pps: u64,
+    pub total_rx: u64,
+    pub total_tx: u64,
+    /// bytes since hwtop started
+    pub session_rx: u64,
+    pub session_tx: u64,
+    /// errors and dropped packets during the last tick
+    pub errors: u64,
+    /// `None` for wired interfaces
+    pub wifi: Option<WifiSample>,
+    /// negotiated link speed in Mbit/s, `None` for virtual interfaces and links that are down
+    pub link_mbps: Option<u32>,
+    pub duplex: Option<String>,
+    /// a gigabit NIC that negotiated 100 Mbit/s or less, usually a bad cable
+    pub link_downgraded: bool,
+}
+
+/// Bytes each interface moved this session, added up tick by tick so counters reset by a flapping link don't lose them
+#[derive(Default)]
+pub struct NetTotals {
+    interfaces: HashMap<String, Session>,
+}
+
+#[derive(Default)]
+struct Session {
+    rx: u64,
+    tx: u64,
+    last_drops: Option<u64>,
+}
+
+impl Session {
+    /// Dropped packets since the previous tick, sysinfo only counts errors
+    fn new_drops(&mut self, name: &str) -> u64 {
+        let dropped = |counter: &str| sysfs::read_num::<u64>(format!("/sys/class/net/{name}/statistics/{counter}"));
+        let Some(drops) = dropped("rx_dropped").zip(dropped("tx_dropped")).map(|(rx, tx)| rx + tx) else {
+            return 0;
+        };
+        self.last_drops.replace(drops).map_or(0, |last| drops.saturating_sub(last))
+    }
+}
+
+/// Rates of the shown interfaces, busiest first
+pub fn sample(nets: &Networks, totals: &mut NetTotals, wifi: &mut Wifi, delta: f32, args: &Args) -> Vec<NetSample> {
+    let mut networks: Vec<NetSample> = nets.iter()
+        .filter(|&(name, data)| args.iface.as_ref() == Some(name) || net_filter(name, data, &args.exclude_ifaces))
+        .map(|(name, data)| {
+            let session = totals.interfaces.entry(name.clone()).or_default();
+            // virtual interfaces report -1
+            let link_mbps = sysfs::read_num::<i64>(format!("/sys/class/net/{name}/speed")).filter(|&mbps| mbps > 0).map(|mbps| mbps as u32);
+            session.rx += data.received();
+            session.tx += data.transmitted();
+            NetSample {
+                name: name.clone(),
+                rx_bps: per_sec(data.received(), delta),
+                tx_bps: per_sec(data.transmitted(), delta),
+                rx_pps: per_sec(data.packets_received(), delta),
+                tx_pps: per_sec(data.packets_transmitted(), delta),
+                total_rx: data.total_received(),
+                total_tx: data.total_transmitted(),
+                session_rx: session.rx,
+                session_tx: session.tx,
+                errors: data.errors_on_received() + data.errors_on_transmitted() + session.new_drops(name),
+                wifi: wifi.sample(name),
+                link_mbps,
+                duplex: link_mbps.and_then(|_| sysfs::read(format!("/sys/class/net/{name}/duplex"))).filter(|duplex| duplex != "unknown"),
+                link_downgraded: link_mbps.is_some_and(|mbps| mbps < 1000 && supports_gigabit(name)),
+            }
+        }).collect();
+    networks.sort_by(|a, b| (b.total_rx + b.total_tx).cmp(&(a.total_rx + a.total_tx)).then_with(|| a.name.cmp(&b.name)));
+    networks
+}
+
+/// Whether the NIC can do 1000 Mbit/s or more, from the `supported` link modes of the ETHTOOL_GSET ioctl
+#[cfg(target_os = "linux")]
+fn supports_gigabit(iface: &str) -> bool {
+    const ETHTOOL_GSET: u32 = 1;
+    // 1000baseT half/full, 10000baseT, 2500baseX and everything from 1000baseKX on
+    const GIGABIT_MODES: u32 = 1 << 4 | 1 << 5 | 1 << 12 | 1 << 15 | !0 << 17;
+    // struct ethtool_cmd, `supported` follows `cmd`
+    let mut cmd = [0u32; 11];
+    cmd[0] = ETHTOOL_GSET;
+    let mut req: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, &src) in req.ifr_name.iter_mut().zip(iface.as_bytes().iter().take(libc::IFNAMSIZ - 1)) {
+        *dst = src as libc::c_char;
+    }
+    req.ifr_ifru.ifru_data = cmd.as_mut_ptr().cast();
+    unsafe {
+        let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            return false;
+        }
+        let ok = libc::ioctl(fd, libc::SIOCETHTOOL, &mut req) == 0;
+        libc::close(fd);
+        ok && cmd[1] & GIGABIT_MODES != 0
+    }
+}
+
+/// Only Linux has ethtool, elsewhere there's no link speed to call downgraded either
+#[cfg(not(target_os = "linux"))]
+fn supports_gigabit(_iface: &str) -> bool {
+    false
+}
+
+/// Skips idle interfaces and those matching an `exclude` glob
+pub fn net_filter(name: &str, data: &NetworkData, exclude: &[String]) -> bool {
+    !(data.total_received() == 0 && data.total_transmitted() == 0 || exclude.iter().any(|pattern| glob_match(pattern, name)))
+}
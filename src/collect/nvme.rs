@@ -0,0 +1,97 @@
+#[cfg(target_os = "linux")]
+use std::{fs::File, os::fd::AsRawFd};
+
+/// Health from the SMART / Health Information log page of an NVMe controller
+#[derive(Debug, PartialEq)]
+pub struct NvmeHealth {
+    /// estimate of the rated endurance used up, can pass 100
+    pub wear_percent: u32,
+    pub power_on_hours: u64,
+    pub unsafe_shutdowns: u64,
+    pub media_errors: u64,
+}
+
+/// struct nvme_passthru_cmd from linux/nvme_ioctl.h
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default)]
+struct PassthruCmd {
+    opcode: u8,
+    flags: u8,
+    rsvd1: u16,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    metadata: u64,
+    addr: u64,
+    metadata_len: u32,
+    data_len: u32,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+    timeout_ms: u32,
+    result: u32,
+}
+
+/// _IOWR('N', 0x41, struct nvme_passthru_cmd)
+#[cfg(target_os = "linux")]
+const NVME_IOCTL_ADMIN_CMD: libc::c_ulong = 0xc048_4e41;
+#[cfg(target_os = "linux")]
+const GET_LOG_PAGE: u8 = 0x02;
+#[cfg(target_os = "linux")]
+const SMART_LOG: u32 = 0x02;
+#[cfg(target_os = "linux")]
+const SMART_LOG_LEN: usize = 512;
+
+/// Reads the log through `/dev/<controller>` (like `nvme0`), `None` without permission to open it which is usually root only
+#[cfg(target_os = "linux")]
+pub fn health(controller: &str) -> Option<NvmeHealth> {
+    let device = File::open(format!("/dev/{controller}")).ok()?;
+    let mut log = [0u8; SMART_LOG_LEN];
+    let mut cmd = PassthruCmd {
+        opcode: GET_LOG_PAGE,
+        // the controller wide log, not a namespace's
+        nsid: u32::MAX,
+        addr: log.as_mut_ptr() as u64,
+        data_len: SMART_LOG_LEN as u32,
+        // log id in the low byte, dwords to read minus one in the upper half
+        cdw10: SMART_LOG | ((SMART_LOG_LEN / 4 - 1) as u32) << 16,
+        ..Default::default()
+    };
+    let ok = unsafe { libc::ioctl(device.as_raw_fd(), NVME_IOCTL_ADMIN_CMD as _, &mut cmd) } == 0;
+    ok.then(|| parse(&log))
+}
+
+/// The passthrough ioctl is Linux only
+#[cfg(not(target_os = "linux"))]
+pub fn health(_controller: &str) -> Option<NvmeHealth> {
+    None
+}
+
+/// Counters in the log are 128 bit little endian, the low half is plenty
+#[cfg(target_os = "linux")]
+fn parse(log: &[u8; SMART_LOG_LEN]) -> NvmeHealth {
+    let counter = |offset: usize| u64::from_le_bytes(log[offset..offset + 8].try_into().unwrap_or_default());
+    NvmeHealth { wear_percent: log[5] as u32, power_on_hours: counter(128), unsafe_shutdowns: counter(144), media_errors: counter(160) }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_smart_log() {
+        assert_eq!(std::mem::size_of::<PassthruCmd>(), 72);
+        let mut log = [0u8; SMART_LOG_LEN];
+        log[5] = 7;
+        log[128..130].copy_from_slice(&12_345u16.to_le_bytes());
+        log[144] = 42;
+        log[160] = 1;
+        // the high half of the 128 bit counter is ignored
+        log[136] = 0xff;
+        assert_eq!(parse(&log), NvmeHealth { wear_percent: 7, power_on_hours: 12_345, unsafe_shutdowns: 42, media_errors: 1 });
+    }
+}
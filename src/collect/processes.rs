@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, System, ThreadKind};
+
+use crate::args::{Args, ProcSort};
+use super::{gpu::GpuSample, per_sec};
+
+#[derive(Serialize, Deserialize)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    /// percent of one core, like top, so it goes up to 100 × cores
+    pub cpu: f32,
+    pub rss: u64,
+    pub virt: u64,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    pub kernel: bool,
+    /// summed over every GPU it runs on, `None` when it's on none or the driver can't tell
+    pub vram: Option<u64>,
+    /// still on a GPU but gone from the process list, only `pid`, `name` and `vram` are known
+    pub exited: bool,
+}
+
+/// The top `--procs` processes by `--procs-sort` with their VRAM from `gpus`, empty without `--procs`.
+/// Processes the GPUs list that already exited are added after them
+pub fn sample(sys: &System, gpus: &[GpuSample], delta: f32, args: &Args) -> Vec<ProcessSample> {
+    if args.procs.is_none() {
+        return vec![];
+    }
+    let vram = gpu_vram(gpus);
+    let max_cpu = 100.0 * sys.cpus().len().max(1) as f32;
+    let mut processes: Vec<ProcessSample> = sys.processes().values()
+        .filter(|proc| proc.thread_kind() != Some(ThreadKind::Userland))
+        .filter(|proc| !(args.procs_hide_kernel && proc.thread_kind() == Some(ThreadKind::Kernel)))
+        .map(|proc| {
+        let disk = proc.disk_usage();
+        ProcessSample {
+            pid: proc.pid().as_u32(),
+            name: proc.name().to_string_lossy().into_owned(),
+            cpu: proc.cpu_usage().min(max_cpu),
+            rss: proc.memory(),
+            virt: proc.virtual_memory(),
+            read_bps: per_sec(disk.read_bytes, delta),
+            write_bps: per_sec(disk.written_bytes, delta),
+            kernel: proc.thread_kind() == Some(ThreadKind::Kernel),
+            vram: vram.iter().find(|&&(pid, _, _)| pid == proc.pid().as_u32()).and_then(|&(_, _, vram)| vram),
+            exited: false,
+        }
+    }).collect();
+    processes.sort_by(|a, b| match args.procs_sort {
+        ProcSort::Cpu => b.cpu.total_cmp(&a.cpu),
+        ProcSort::Mem => b.rss.cmp(&a.rss),
+        ProcSort::Disk => (b.read_bps + b.write_bps).cmp(&(a.read_bps + a.write_bps)),
+    }.then(a.pid.cmp(&b.pid)));
+    processes.truncate(args.procs.unwrap_or(0));
+    let exited = vram.into_iter().filter(|&(pid, _, _)| sys.process(Pid::from_u32(pid)).is_none()).map(|(pid, name, vram)| ProcessSample {
+        pid,
+        name: if name == "?" { "<exited>".to_string() } else { name.to_string() },
+        cpu: 0.0,
+        rss: 0,
+        virt: 0,
+        read_bps: 0,
+        write_bps: 0,
+        kernel: false,
+        vram,
+        exited: true,
+    });
+    processes.extend(exited);
+    processes
+}
+
+/// `(pid, name, vram)` of every process on a GPU, a process listed as both compute and graphics is counted once per GPU
+fn gpu_vram(gpus: &[GpuSample]) -> Vec<(u32, &str, Option<u64>)> {
+    let mut vram: Vec<(u32, &str, Option<u64>)> = vec![];
+    for gpu in gpus {
+        let mut on_gpu: Vec<(u32, &str, Option<u64>)> = vec![];
+        for proc in gpu.processes.iter().flatten() {
+            match on_gpu.iter_mut().find(|(pid, _, _)| *pid == proc.pid) {
+                Some((_, _, used)) => *used = (*used).max(proc.vram_used),
+                None => on_gpu.push((proc.pid, &proc.name, proc.vram_used)),
+            }
+        }
+        for (pid, name, used) in on_gpu {
+            match vram.iter_mut().find(|(other, _, _)| *other == pid) {
+                Some((_, _, total)) => *total = total.zip(used).map(|(total, used)| total + used).or(*total).or(used),
+                None => vram.push((pid, name, used)),
+            }
+        }
+    }
+    vram
+}
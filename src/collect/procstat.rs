@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use super::sysfs;
+
+/// Kernel activity per second over the last tick, `None` on the first tick since there's nothing to diff against
+#[derive(Serialize, Deserialize, Default)]
+pub struct ActivitySample {
+    pub context_switches: Option<u64>,
+    pub interrupts: Option<u64>,
+    pub forks: Option<u64>,
+}
+
+/// `ctxt`, `intr` and `processes` counters from `/proc/stat`, kept to diff against the next tick
+pub struct ProcStat {
+    last: Option<[u64; 3]>,
+}
+
+impl Default for ProcStat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcStat {
+    pub fn new() -> Self {
+        let mut stat = Self { last: None };
+        stat.sample(1.0);
+        stat
+    }
+
+    /// Rates since the previous call, `delta` is the elapsed seconds
+    pub fn sample(&mut self, delta: f32) -> ActivitySample {
+        let stat = sysfs::read("/proc/stat").unwrap_or_default();
+        // `intr` is followed by the count of every interrupt line, the first one is their total
+        let counter = |name: &str| stat.lines().find_map(|line| line.strip_prefix(name)?.strip_prefix(' ')?.split_whitespace().next()?.parse::<u64>().ok());
+        let Some(now) = counter("ctxt").zip(counter("intr")).zip(counter("processes")).map(|((ctxt, intr), forks)| [ctxt, intr, forks]) else {
+            self.last = None;
+            return ActivitySample::default();
+        };
+        let Some(last) = self.last.replace(now) else {
+            return ActivitySample::default();
+        };
+        let rate = |i: usize| Some((now[i].saturating_sub(last[i]) as f32 / delta.max(f32::EPSILON)).round() as u64);
+        ActivitySample { context_switches: rate(0), interrupts: rate(1), forks: rate(2) }
+    }
+}
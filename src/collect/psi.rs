@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use super::sysfs;
+
+/// Percent of the last 10s that tasks stalled on a resource
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Pressure {
+    /// at least one task was waiting
+    pub some: f32,
+    /// every non-idle task was waiting at once, the cpu file has no such line on older kernels
+    pub full: Option<f32>,
+}
+
+/// Pressure Stall Information from `/proc/pressure`
+#[derive(Serialize, Deserialize)]
+pub struct PsiSample {
+    pub cpu: Option<Pressure>,
+    pub memory: Option<Pressure>,
+    pub io: Option<Pressure>,
+}
+
+/// `None` on kernels built without PSI or booted with `psi=0`
+pub fn sample() -> Option<PsiSample> {
+    let read = |name: &str| sysfs::read(format!("/proc/pressure/{name}")).and_then(|text| parse(&text));
+    let psi = PsiSample { cpu: read("cpu"), memory: read("memory"), io: read("io") };
+    (psi.cpu.is_some() || psi.memory.is_some() || psi.io.is_some()).then_some(psi)
+}
+
+/// `avg10` of the `some` and `full` lines of one pressure file
+fn parse(text: &str) -> Option<Pressure> {
+    let avg10 = |kind: &str| text.lines().find_map(|line| {
+        let fields = line.strip_prefix(kind)?.strip_prefix(' ')?;
+        fields.split_whitespace().find_map(|field| field.strip_prefix("avg10="))?.parse().ok()
+    });
+    Some(Pressure { some: avg10("some")?, full: avg10("full") })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_some_only_and_some_full_files() {
+        let cpu = "some avg10=1.25 avg60=0.80 avg300=0.10 total=123456\n";
+        assert_eq!(parse(cpu), Some(Pressure { some: 1.25, full: None }));
+        let memory = "some avg10=12.50 avg60=3.00 avg300=1.00 total=987\nfull avg10=4.00 avg60=1.00 avg300=0.20 total=654\n";
+        assert_eq!(parse(memory), Some(Pressure { some: 12.5, full: Some(4.0) }));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("some total=1\n"), None);
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::sysfs;
+
+#[derive(Serialize, Deserialize)]
+pub struct PackagePower {
+    pub name: String,
+    pub power_w: Option<f32>,
+    pub limit_w: Option<f32>,
+}
+
+/// One `/sys/class/powercap/intel-rapl:N` package zone, AMD CPUs expose theirs under the same name
+struct Package {
+    name: String,
+    path: PathBuf,
+    max_energy_uj: Option<u64>,
+    limit_w: Option<f32>,
+    last_uj: Option<u64>,
+}
+
+/// CPU package power from RAPL energy counters, zones that aren't readable (root only on most distros) are skipped
+pub struct Rapl {
+    packages: Vec<Package>,
+}
+
+impl Rapl {
+    pub fn detect() -> Self {
+        let Ok(entries) = std::fs::read_dir("/sys/class/powercap") else {
+            return Self { packages: vec![] };
+        };
+        // top level zones are packages, `intel-rapl:0:0` style subzones are cores/uncore/dram
+        let mut paths: Vec<PathBuf> = entries.flatten()
+            .filter(|e| e.file_name().to_str().and_then(|n| n.strip_prefix("intel-rapl:")).is_some_and(|n| !n.contains(':')))
+            .map(|e| e.path())
+            .collect();
+        paths.sort();
+        let packages = paths.into_iter().filter_map(|path| {
+            let last_uj = sysfs::read_num(path.join("energy_uj"))?;
+            Some(Package {
+                name: sysfs::read(path.join("name")).unwrap_or_else(|| "package".to_string()),
+                max_energy_uj: sysfs::read_num(path.join("max_energy_range_uj")),
+                limit_w: sysfs::read_num::<u64>(path.join("constraint_0_power_limit_uw")).filter(|&uw| uw > 0).map(|uw| uw as f32 / 1e6),
+                last_uj: Some(last_uj),
+                path,
+            })
+        }).collect();
+        Self { packages }
+    }
+
+    /// Average power of every package since the previous call, `delta` is the elapsed seconds
+    pub fn sample(&mut self, delta: f32) -> Vec<PackagePower> {
+        self.packages.iter_mut().map(|package| {
+            let now_uj = sysfs::read_num::<u64>(package.path.join("energy_uj"));
+            let used_uj = now_uj.zip(package.last_uj).map(|(now, last)| match now.checked_sub(last) {
+                Some(used) => used,
+                // the counter wrapped around at max_energy_range_uj
+                None => package.max_energy_uj.unwrap_or(last).saturating_sub(last) + now,
+            });
+            package.last_uj = now_uj;
+            PackagePower {
+                name: package.name.clone(),
+                power_w: used_uj.filter(|_| delta > 0.0).map(|uj| uj as f32 / 1e6 / delta),
+                limit_w: package.limit_w,
+            }
+        }).collect()
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::Components;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ComponentSample {
+    pub name: String,
+    pub kind: TempKind,
+    pub temps_c: Vec<u32>,
+}
+
+/// Component class used to pick realistic temperature color thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TempKind {
+    Cpu,
+    Gpu,
+    Nvme,
+    Ambient,
+    Other,
+    /// GDDR memory junction, only read from the GPU backends
+    GpuMem,
+    /// hottest spot of the GPU die, only read from the GPU backends
+    GpuHotspot,
+}
+
+impl TempKind {
+    pub const ALL: [TempKind; 7] = [Self::Cpu, Self::Gpu, Self::Nvme, Self::Ambient, Self::Other, Self::GpuMem, Self::GpuHotspot];
+
+    /// Key of the kind in the config's `[thresholds]`
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Gpu => "gpu",
+            Self::Nvme => "nvme",
+            Self::Ambient => "ambient",
+            Self::Other => "other",
+            Self::GpuMem => "gpu_mem",
+            Self::GpuHotspot => "gpu_hotspot",
+        }
+    }
+
+    #[must_use]
+    fn of(label: &str) -> Self {
+        let label = label.to_lowercase();
+        if label.contains("coretemp") || label.contains("k10temp") || label.contains("cpu") || label.starts_with("core ") {
+            Self::Cpu
+        } else if label.contains("amdgpu") || label.contains("nouveau") || label.contains("gpu") {
+            Self::Gpu
+        } else if label.contains("nvme") || label.contains("drivetemp") {
+            Self::Nvme
+        } else if label.contains("acpitz") || label.contains("motherboard") || label.contains("pch") {
+            Self::Ambient
+        } else {
+            Self::Other
+        }
+    }
+
+    /// °C at which the color turns sky, magenta and red, unless the config's `[thresholds]` says otherwise
+    #[must_use]
+    pub fn thresholds(self) -> [u32; 3] {
+        match self {
+            Self::Cpu => [50, 70, 85],
+            Self::Gpu => [50, 70, 83],
+            Self::Nvme => [45, 58, 70],
+            Self::Ambient => [35, 45, 55],
+            Self::Other => [45, 60, 75],
+            // GDDR6X runs hot and throttles around 105
+            Self::GpuMem => [70, 85, 95],
+            Self::GpuHotspot => [65, 85, 100],
+        }
+    }
+}
+
+/// What a sensor measures, from its sysinfo label (`chip label [model]`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sensor {
+    /// the whole CPU, several drivers may report it and the lowest `priority` wins
+    CpuPackage { priority: u8 },
+    /// one core or core complex, by index
+    Core(u32),
+    /// everything else, sensors with the same name are shown together
+    Component { name: String, kind: TempKind },
+}
+
+/// Known chips and the group their sensors are shown under, drives are named after their model when it's known
+const CHIPS: [(&str, &str, TempKind); 9] = [
+    ("coretemp", "CPU", TempKind::Cpu),
+    ("k10temp", "CPU", TempKind::Cpu),
+    ("zenpower", "CPU", TempKind::Cpu),
+    ("acpitz", "Motherboard", TempKind::Ambient),
+    ("nvme", "NVMe", TempKind::Nvme),
+    ("drivetemp", "Disk", TempKind::Nvme),
+    ("spd5118", "RAM", TempKind::Other),
+    ("iwlwifi", "Wi-Fi", TempKind::Other),
+    ("amdgpu", "GPU", TempKind::Gpu),
+];
+
+/// Sorts a sensor into the CPU package, a core or a named component
+#[must_use]
+pub fn classify(label: &str) -> Sensor {
+    let (chip, rest) = label.split_once(' ').unwrap_or((label, ""));
+    // hwmon numbers some chips, `iwlwifi_1`
+    let chip = chip.rsplit_once('_').filter(|(_, n)| n.chars().all(|c| c.is_ascii_digit())).map_or(chip, |(chip, _)| chip);
+    let number = |prefix: &str| rest.strip_prefix(prefix).and_then(|n| n.parse().ok());
+    match chip {
+        "coretemp" if rest.starts_with("Package id") => return Sensor::CpuPackage { priority: 0 },
+        "coretemp" => if let Some(core) = number("Core ") {
+            return Sensor::Core(core);
+        },
+        // Tctl is offset on some Ryzens, Tdie is the real one, each CCD takes the place of cores
+        "k10temp" | "zenpower" => match rest {
+            "Tdie" => return Sensor::CpuPackage { priority: 1 },
+            "Tctl" => return Sensor::CpuPackage { priority: 2 },
+            _ => if let Some(ccd) = number("Tccd") {
+                return Sensor::Core(ccd);
+            },
+        },
+        "cpu_thermal" => return Sensor::CpuPackage { priority: 3 },
+        _ => {}
+    }
+    if let Some(&(_, group, kind)) = CHIPS.iter().find(|&&(known, _, _)| known == chip) {
+        let model = match chip {
+            "nvme" => rest.strip_prefix("Composite").or_else(|| rest.strip_prefix("Sensor ").map(|rest| rest.trim_start_matches(|c: char| c.is_ascii_digit())))
+                .unwrap_or(rest).trim().replace("SSD ", ""),
+            "drivetemp" if !rest.starts_with("temp") => rest.to_string(),
+            _ => String::new(),
+        };
+        return Sensor::Component { name: if model.is_empty() { group.to_string() } else { model }, kind };
+    }
+    // Super I/O chips on the motherboard have their own CPU sensor (`nct6798 CPUTIN`), the worst of the CPU sources
+    if rest.to_uppercase().contains("CPU") {
+        return Sensor::CpuPackage { priority: 4 };
+    }
+    if label.to_lowercase().contains("wifi") {
+        return Sensor::Component { name: "Wi-Fi".to_string(), kind: TempKind::Other };
+    }
+    Sensor::Component { name: label.replace(" temp1", ""), kind: TempKind::of(label) }
+}
+
+/// Every temperature sensor, the CPU package and core sensors are split off for the CPU rows
+#[derive(Clone, Default)]
+pub struct Temps {
+    pub package_c: Option<u32>,
+    pub cores_c: Vec<u32>,
+    /// `coretemp Core N` readings by core id, matched to the logical CPUs on that core
+    pub by_core_id: BTreeMap<u32, u32>,
+    pub components: Vec<ComponentSample>,
+}
+
+pub fn temps(components: &Components) -> Temps {
+    let readings: Vec<(&str, u32)> = components.iter().map(|comp| (comp.label(), comp.temperature().unwrap_or(0.0).round() as u32)).collect();
+    let mut temps = group(readings);
+    temps.by_core_id = components.iter().filter_map(|comp| {
+        let id = comp.label().strip_prefix("coretemp Core ")?.parse().ok()?;
+        Some((id, comp.temperature()?.round() as u32))
+    }).collect();
+    temps
+}
+
+/// Temperatures of `(label, °C)` readings grouped by `classify`
+fn group(mut readings: Vec<(&str, u32)>) -> Temps {
+    // a drive's composite sensor comes first, then the hottest
+    readings.sort_by_key(|&(label, temp)| if label.contains("Composite") { 0 } else { 100000 - temp });
+    let mut package: Option<(u8, u32)> = None;
+    let mut cores = vec![];
+    let mut components: BTreeMap<String, (TempKind, Vec<u32>)> = BTreeMap::new();
+    for (label, temp) in readings {
+        match classify(label) {
+            Sensor::CpuPackage { priority } => {
+                if package.is_none_or(|(best, _)| priority < best) {
+                    package = Some((priority, temp));
+                }
+            }
+            Sensor::Core(index) => cores.push((index, temp)),
+            Sensor::Component { name, kind } => components.entry(name).or_insert_with(|| (kind, vec![])).1.push(temp),
+        }
+    }
+    cores.sort();
+    Temps {
+        package_c: package.map(|(_, temp)| temp),
+        cores_c: cores.into_iter().map(|(_, temp)| temp).collect(),
+        by_core_id: BTreeMap::new(),
+        components: components.into_iter().map(|(name, (kind, temps_c))| ComponentSample { name, kind, temps_c }).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(temps: &Temps) -> Vec<&str> {
+        temps.components.iter().map(|comp| comp.name.as_str()).collect()
+    }
+
+    #[test]
+    fn intel_desktop() {
+        let temps = group(vec![
+            ("coretemp Package id 0", 62), ("coretemp Core 4", 58), ("coretemp Core 0", 61), ("coretemp Core 8", 55),
+            ("nvme Composite Samsung SSD 980 PRO 1TB", 41), ("nvme Sensor 1 Samsung SSD 980 PRO 1TB", 38), ("nvme Sensor 2 Samsung SSD 980 PRO 1TB", 44),
+            ("spd5118 temp1", 39), ("acpitz temp1", 28), ("nct6798 CPUTIN", 45), ("nct6798 SYSTIN", 31),
+        ]);
+        assert_eq!(temps.package_c, Some(62));
+        assert_eq!(temps.cores_c, vec![61, 58, 55]);
+        assert_eq!(names(&temps), vec!["Motherboard", "RAM", "Samsung 980 PRO 1TB", "nct6798 SYSTIN"]);
+        assert_eq!(temps.components[2].temps_c, vec![41, 44, 38]);
+        assert_eq!(temps.components[2].kind, TempKind::Nvme);
+    }
+
+    #[test]
+    fn amd_desktop() {
+        let temps = group(vec![
+            ("k10temp Tctl", 71), ("k10temp Tccd2", 60), ("k10temp Tccd1", 66), ("nct6799 CPUTIN", 48),
+            ("amdgpu edge", 52), ("amdgpu junction", 58), ("amdgpu mem", 64), ("nvme Composite", 39), ("drivetemp WDC WD40EFRX-68N", 35),
+        ]);
+        assert_eq!(temps.package_c, Some(71));
+        assert_eq!(temps.cores_c, vec![66, 60]);
+        assert_eq!(names(&temps), vec!["GPU", "NVMe", "WDC WD40EFRX-68N"]);
+        assert_eq!(temps.components[0].temps_c, vec![64, 58, 52]);
+        // Tdie beats Tctl whatever order they come in
+        assert_eq!(group(vec![("k10temp Tctl", 80), ("k10temp Tdie", 70)]).package_c, Some(70));
+    }
+
+    #[test]
+    fn laptop() {
+        let temps = group(vec![
+            ("coretemp Package id 0", 55), ("coretemp Core 1", 52), ("coretemp Core 0", 54), ("acpitz temp1", 50), ("acpitz temp2", 27),
+            ("iwlwifi_1 temp1", 41), ("thinkpad CPU", 54), ("thinkpad GPU", 0), ("pch_cannonlake temp1", 48), ("BAT0 temp1", 30),
+        ]);
+        assert_eq!(temps.package_c, Some(55));
+        assert_eq!(temps.cores_c, vec![54, 52]);
+        assert_eq!(names(&temps), vec!["BAT0", "Motherboard", "Wi-Fi", "pch_cannonlake", "thinkpad GPU"]);
+        assert_eq!(temps.components[1].temps_c, vec![50, 27]);
+    }
+
+    #[test]
+    fn arm_board_without_a_package_sensor() {
+        let temps = group(vec![("cpu_thermal temp1", 47), ("rp1_adc temp1", 52)]);
+        assert_eq!(temps.package_c, Some(47));
+        assert_eq!(names(&temps), vec!["rp1_adc"]);
+    }
+}
@@ -0,0 +1,11 @@
+use std::{path::Path, str::FromStr};
+
+#[must_use]
+pub fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+#[must_use]
+pub fn read_num<T: FromStr>(path: impl AsRef<Path>) -> Option<T> {
+    read(path)?.parse().ok()
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::{cpu::CoreSample, sysfs};
+
+#[derive(Serialize, Deserialize)]
+pub struct ThrottleSample {
+    /// throttled during the last tick
+    pub active: bool,
+    /// throttle events since hwtop started
+    pub events: u64,
+    /// guessed from clocks under the base clock at full load, the CPU has no throttle counters (AMD)
+    pub heuristic: bool,
+}
+
+/// Ticks in a row the clock has to stay under base clock at full load before the heuristic calls it throttling
+const SUSTAINED_TICKS: u32 = 3;
+/// Usage in % that counts as full load for the heuristic
+const FULL_LOAD: f32 = 95.0;
+
+/// Thermal throttling from the `thermal_throttle` counters of Intel CPUs, guessed from clocks elsewhere
+#[derive(Default)]
+pub struct Throttle {
+    first_count: Option<u64>,
+    last_count: Option<u64>,
+    slow_ticks: u32,
+    guessed_events: u64,
+}
+
+impl Throttle {
+    /// `None` when there are neither throttle counters nor a base clock to guess against
+    pub fn sample(&mut self, usage: f32, cores: &[CoreSample]) -> Option<ThrottleSample> {
+        if let Some(count) = throttle_count(cores.len()) {
+            let first = *self.first_count.get_or_insert(count);
+            let active = self.last_count.is_some_and(|last| count > last);
+            self.last_count = Some(count);
+            return Some(ThrottleSample { active, events: count.saturating_sub(first), heuristic: false });
+        }
+        let base_mhz = base_mhz()?;
+        let avg_mhz = cores.iter().map(|core| core.freq_mhz).sum::<u64>() / cores.len().max(1) as u64;
+        if usage >= FULL_LOAD && avg_mhz > 0 && avg_mhz < base_mhz {
+            self.slow_ticks += 1;
+            if self.slow_ticks == SUSTAINED_TICKS {
+                self.guessed_events += 1;
+            }
+        } else {
+            self.slow_ticks = 0;
+        }
+        Some(ThrottleSample { active: self.slow_ticks >= SUSTAINED_TICKS, events: self.guessed_events, heuristic: true })
+    }
+}
+
+/// Core counters summed once per physical core plus the package counters once per package
+fn throttle_count(cpus: usize) -> Option<u64> {
+    let (mut cores, mut packages) = (BTreeSet::new(), BTreeSet::new());
+    let mut total = None;
+    for i in 0..cpus {
+        let cpu = format!("/sys/devices/system/cpu/cpu{i}");
+        let Some(core_count) = sysfs::read_num::<u64>(format!("{cpu}/thermal_throttle/core_throttle_count")) else {
+            continue;
+        };
+        let package = sysfs::read_num::<u32>(format!("{cpu}/topology/physical_package_id")).unwrap_or(0);
+        let core = sysfs::read_num::<u32>(format!("{cpu}/topology/core_id")).unwrap_or(i as u32);
+        let total = total.get_or_insert(0);
+        if cores.insert((package, core)) {
+            *total += core_count;
+        }
+        if packages.insert(package) {
+            *total += sysfs::read_num::<u64>(format!("{cpu}/thermal_throttle/package_throttle_count")).unwrap_or(0);
+        }
+    }
+    total
+}
+
+/// Base (non boost) clock, intel_pstate and amd-pstate both expose it in kHz
+pub fn base_mhz() -> Option<u64> {
+    let cpufreq = "/sys/devices/system/cpu/cpu0/cpufreq";
+    sysfs::read_num::<u64>(format!("{cpufreq}/base_frequency"))
+        .or_else(|| sysfs::read_num(format!("{cpufreq}/amd_pstate_nominal_freq")))
+        .filter(|&khz| khz > 0)
+        .map(|khz| khz / 1000)
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::sysfs;
+
+#[cfg(target_os = "linux")]
+mod nl80211;
+
+/// Link of a wireless interface to its access point
+#[derive(Serialize, Deserialize)]
+pub struct WifiSample {
+    pub ssid: Option<String>,
+    pub signal_dbm: Option<i32>,
+    /// negotiated transmit bitrate in Mbit/s
+    pub bitrate_mbps: Option<f32>,
+}
+
+impl WifiSample {
+    /// -100 dBm is no signal and -50 dBm or more a full one, like NetworkManager shows it
+    #[must_use]
+    pub fn signal_percent(&self) -> Option<u32> {
+        self.signal_dbm.map(|dbm| ((dbm + 100) * 2).clamp(0, 100) as u32)
+    }
+}
+
+/// Wireless links of every interface, the nl80211 socket is opened for the first one and kept
+#[derive(Default)]
+pub struct Wifi {
+    #[cfg(target_os = "linux")]
+    nl80211: Option<nl80211::Nl80211>,
+}
+
+impl Wifi {
+    /// `None` for wired interfaces, the signal falls back to `/proc/net/wireless` when nl80211 doesn't answer
+    pub fn sample(&mut self, iface: &str) -> Option<WifiSample> {
+        if !Path::new(&format!("/sys/class/net/{iface}/wireless")).exists() {
+            return None;
+        }
+        let mut wifi = WifiSample { ssid: None, signal_dbm: None, bitrate_mbps: None };
+        #[cfg(target_os = "linux")]
+        if let Some(ifindex) = sysfs::read_num::<u32>(format!("/sys/class/net/{iface}/ifindex")) {
+            if self.nl80211.is_none() {
+                self.nl80211 = nl80211::Nl80211::open();
+            }
+            // opened again on the next tick, a reloaded nl80211 module comes back with another family id
+            if let Some(nl80211) = &mut self.nl80211 && nl80211.query(ifindex, &mut wifi).is_none() {
+                self.nl80211 = None;
+            }
+        }
+        wifi.signal_dbm = wifi.signal_dbm.or_else(|| proc_wireless_signal(iface));
+        (wifi.ssid.is_some() || wifi.signal_dbm.is_some() || wifi.bitrate_mbps.is_some()).then_some(wifi)
+    }
+}
+
+/// `wlan0: 0000   70.  -40.  -256 ...`, the level column is in dBm
+fn proc_wireless_signal(iface: &str) -> Option<i32> {
+    let wireless = sysfs::read("/proc/net/wireless")?;
+    let line = wireless.lines().find_map(|line| line.trim_start().strip_prefix(iface)?.strip_prefix(':'))?;
+    let level: f32 = line.split_whitespace().nth(2)?.trim_end_matches('.').parse().ok()?;
+    (level < 0.0).then_some(level as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_percent_clamps() {
+        let wifi = |signal_dbm| WifiSample { ssid: None, signal_dbm, bitrate_mbps: None };
+        assert_eq!(wifi(Some(-40)).signal_percent(), Some(100));
+        assert_eq!(wifi(Some(-67)).signal_percent(), Some(66));
+        assert_eq!(wifi(Some(-110)).signal_percent(), Some(0));
+        assert_eq!(wifi(None).signal_percent(), None);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{mem::size_of, os::fd::{AsRawFd, FromRawFd, OwnedFd}};
+
+use super::WifiSample;
+
+// nl80211 commands and attributes from linux/nl80211.h
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+const NL80211_STA_INFO_TX_BITRATE: u16 = 8;
+const NL80211_RATE_INFO_BITRATE: u16 = 1;
+const NL80211_RATE_INFO_BITRATE32: u16 = 5;
+
+/// The generic netlink socket with nl80211's family id, kept from one tick to the next
+pub struct Nl80211 {
+    socket: Genl,
+    family: u16,
+}
+
+impl Nl80211 {
+    /// `None` without netlink or with the nl80211 module not loaded
+    pub fn open() -> Option<Self> {
+        let mut socket = Genl::open()?;
+        let family = socket.family("nl80211")?;
+        Some(Self { socket, family })
+    }
+
+    /// SSID from the interface, signal and bitrate from the station it's associated with (the access point).
+    /// `None` when the interface itself goes unanswered, the socket or the family id has gone bad then
+    pub fn query(&mut self, ifindex: u32, wifi: &mut WifiSample) -> Option<()> {
+        let ifindex = ifindex.to_ne_bytes();
+        let interface = self.socket.request(self.family, NL80211_CMD_GET_INTERFACE, 0, &[(NL80211_ATTR_IFINDEX, &ifindex)])?;
+        wifi.ssid = interface.iter().flat_map(|msg| attrs(msg)).find(|&(kind, _)| kind == NL80211_ATTR_SSID)
+            .map(|(_, ssid)| String::from_utf8_lossy(ssid).into_owned());
+        // no station is an interface that isn't associated
+        self.station(&ifindex, wifi);
+        Some(())
+    }
+
+    fn station(&mut self, ifindex: &[u8], wifi: &mut WifiSample) -> Option<()> {
+        let stations = self.socket.request(self.family, NL80211_CMD_GET_STATION, libc::NLM_F_DUMP as u16, &[(NL80211_ATTR_IFINDEX, ifindex)])?;
+        let info = stations.iter().flat_map(|msg| attrs(msg)).find(|&(kind, _)| kind == NL80211_ATTR_STA_INFO)?.1;
+        for (kind, value) in attrs(info) {
+            match kind {
+                NL80211_STA_INFO_SIGNAL => wifi.signal_dbm = value.first().map(|&dbm| dbm as i8 as i32),
+                NL80211_STA_INFO_TX_BITRATE => {
+                    // both in 100 kbit/s, the 32 bit one exists since the 16 bit one overflows on fast links
+                    let rate = attrs(value).fold(None, |rate, (kind, value)| match kind {
+                        NL80211_RATE_INFO_BITRATE32 => value.try_into().ok().map(u32::from_ne_bytes),
+                        NL80211_RATE_INFO_BITRATE if rate.is_none() => value.try_into().ok().map(u16::from_ne_bytes).map(u32::from),
+                        _ => rate,
+                    });
+                    wifi.bitrate_mbps = rate.map(|rate| rate as f32 / 10.0);
+                }
+                _ => {}
+            }
+        }
+        Some(())
+    }
+}
+
+/// Attributes of a netlink payload as `(type, value)`, stops at the first malformed one
+fn attrs(mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        let len = u16::from_ne_bytes(data.get(0..2)?.try_into().ok()?) as usize;
+        // the top bits flag nested and network byte order attributes
+        let kind = u16::from_ne_bytes(data.get(2..4)?.try_into().ok()?) & 0x3fff;
+        let value = data.get(4..len)?;
+        data = data.get(align(len)..).unwrap_or_default();
+        Some((kind, value))
+    })
+}
+
+/// Netlink messages and attributes are padded to 4 bytes
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Generic netlink socket, only the few requests nl80211 needs
+struct Genl {
+    fd: OwnedFd,
+    /// sequence number of the last request, replies to an earlier one that timed out are told apart by it
+    seq: u32,
+}
+
+impl Genl {
+    fn open() -> Option<Self> {
+        let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_GENERIC) };
+        if fd < 0 {
+            return None;
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        // a kernel that never answers shouldn't freeze the frame
+        let timeout = libc::timeval { tv_sec: 0, tv_usec: 200_000 };
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as u16;
+        unsafe {
+            libc::setsockopt(fd.as_raw_fd(), libc::SOL_SOCKET, libc::SO_RCVTIMEO, (&raw const timeout).cast(), size_of::<libc::timeval>() as u32);
+            if libc::bind(fd.as_raw_fd(), (&raw const addr).cast(), size_of::<libc::sockaddr_nl>() as u32) < 0 {
+                return None;
+            }
+        }
+        Some(Self { fd, seq: 0 })
+    }
+
+    /// Id of a generic netlink family, they're assigned when the module loads
+    fn family(&mut self, name: &str) -> Option<u16> {
+        let name = [name.as_bytes(), &[0]].concat();
+        let reply = self.request(libc::GENL_ID_CTRL as u16, libc::CTRL_CMD_GETFAMILY as u8, 0, &[(libc::CTRL_ATTR_FAMILY_NAME as u16, &name)])?;
+        let (_, id) = reply.iter().flat_map(|msg| attrs(msg)).find(|&(kind, _)| kind == libc::CTRL_ATTR_FAMILY_ID as u16)?;
+        Some(u16::from_ne_bytes(id.try_into().ok()?))
+    }
+
+    /// Payloads (after the genl header) of every reply, `None` when the kernel answers with an error
+    fn request(&mut self, family: u16, cmd: u8, flags: u16, attributes: &[(u16, &[u8])]) -> Option<Vec<Vec<u8>>> {
+        const HEADERS: usize = size_of::<libc::nlmsghdr>() + size_of::<libc::genlmsghdr>();
+        let mut msg = vec![0; HEADERS];
+        msg[HEADERS - 4] = cmd;
+        for (kind, value) in attributes {
+            msg.extend(((4 + value.len()) as u16).to_ne_bytes());
+            msg.extend(kind.to_ne_bytes());
+            msg.extend(*value);
+            msg.resize(align(msg.len()), 0);
+        }
+        let len = msg.len() as u32;
+        msg[0..4].copy_from_slice(&len.to_ne_bytes());
+        msg[4..6].copy_from_slice(&family.to_ne_bytes());
+        msg[6..8].copy_from_slice(&(libc::NLM_F_REQUEST as u16 | flags).to_ne_bytes());
+        self.seq = self.seq.wrapping_add(1);
+        msg[8..12].copy_from_slice(&self.seq.to_ne_bytes());
+        if unsafe { libc::send(self.fd.as_raw_fd(), msg.as_ptr().cast(), msg.len(), 0) } < 0 {
+            return None;
+        }
+
+        let mut replies = vec![];
+        let mut buf = vec![0u8; 32 << 10];
+        loop {
+            let read = unsafe { libc::recv(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+            let mut data = buf.get(..usize::try_from(read).ok()?)?;
+            while data.len() >= HEADERS {
+                let len = u32::from_ne_bytes(data[0..4].try_into().ok()?) as usize;
+                let kind = u16::from_ne_bytes(data[4..6].try_into().ok()?) as i32;
+                let seq = u32::from_ne_bytes(data[8..12].try_into().ok()?);
+                match kind {
+                    _ if seq != self.seq => {}
+                    libc::NLMSG_DONE => return Some(replies),
+                    // an error code of 0 is just an acknowledgement
+                    libc::NLMSG_ERROR => return (i32::from_ne_bytes(data[16..20].try_into().ok()?) == 0).then_some(replies),
+                    _ => replies.push(data.get(HEADERS..len)?.to_vec()),
+                }
+                data = data.get(align(len)..).unwrap_or_default();
+            }
+            // a dump ends with NLMSG_DONE, anything else is a single reply
+            if flags & libc::NLM_F_DUMP as u16 == 0 && !replies.is_empty() {
+                return Some(replies);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attrs_skip_padding_and_flags() {
+        // a 5 byte SSID padded to 8, then a nested attribute with the NLA_F_NESTED bit set
+        let mut data = vec![9, 0, 52, 0, b'h', b'o', b'm', b'e', b'!', 0, 0, 0];
+        data.extend([8, 0, 8, 0x80, 5, 0, 0, 0]);
+        let parsed: Vec<(u16, &[u8])> = attrs(&data).collect();
+        assert_eq!(parsed, vec![(NL80211_ATTR_SSID, &b"home!"[..]), (NL80211_STA_INFO_TX_BITRATE, &[5, 0, 0, 0][..])]);
+        assert_eq!(attrs(&[200, 0, 1, 0]).count(), 0);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{sync::mpsc::{self, Receiver, RecvTimeoutError, Sender}, thread, time::Duration};
+
+/// A collector on its own thread, a tick waits for its answer until a deadline and otherwise goes on with the last one
+pub struct Worker<Req, T> {
+    requests: Sender<Req>,
+    results: Receiver<T>,
+    last: Option<T>,
+    /// a request is still being worked on, the next one waits until it's answered
+    busy: bool,
+    /// the thread is gone, it panicked or couldn't be spawned
+    stopped: bool,
+}
+
+impl<Req: Send + 'static, T: Send + 'static> Worker<Req, T> {
+    /// `work` answers each request on a thread called `name`, which ends when the worker is dropped
+    pub fn spawn(name: &str, mut work: impl FnMut(Req) -> T + Send + 'static) -> Self {
+        let (requests, inbox) = mpsc::channel::<Req>();
+        let (outbox, results) = mpsc::channel();
+        let spawned = thread::Builder::new().name(name.to_string()).spawn(move || {
+            for request in inbox {
+                if outbox.send(work(request)).is_err() {
+                    break;
+                }
+            }
+        });
+        // without a thread every request goes unanswered and the worker keeps reporting nothing
+        Self { requests, results, last: None, busy: spawned.is_err(), stopped: spawned.is_err() }
+    }
+
+    /// Hands `request` to the thread, dropped when the previous one is still running since the thread is stuck on it
+    pub fn start(&mut self, request: Req) {
+        if let Ok(result) = self.results.try_recv() {
+            self.last = Some(result);
+            self.busy = false;
+        }
+        if !self.busy {
+            self.busy = self.requests.send(request).is_ok();
+            self.stopped |= !self.busy;
+        }
+    }
+
+    /// Whether the thread is gone, the last answer is all there will be
+    #[must_use]
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Whether an answer ever came in
+    #[must_use]
+    pub fn answered(&self) -> bool {
+        self.last.is_some()
+    }
+
+    /// Waits up to `deadline` for the answer to the last request, `true` with it when it made it in time,
+    /// `false` with the last one that did (if any) when it didn't
+    pub fn finish(&mut self, deadline: Duration) -> (Option<&T>, bool) {
+        if !self.busy {
+            return (self.last.as_ref(), false);
+        }
+        match self.results.recv_timeout(deadline) {
+            Ok(result) => {
+                self.busy = false;
+                (Some(&*self.last.insert(result)), true)
+            }
+            Err(RecvTimeoutError::Timeout) => (self.last.as_ref(), false),
+            Err(RecvTimeoutError::Disconnected) => {
+                self.busy = false;
+                self.stopped = true;
+                (self.last.as_ref(), false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_answers_keep_the_last_one() {
+        let mut worker = Worker::spawn("test", |delay: u64| {
+            thread::sleep(Duration::from_millis(delay));
+            delay
+        });
+        assert!(!worker.answered());
+        worker.start(0);
+        assert_eq!(worker.finish(Duration::from_secs(5)), (Some(&0), true));
+        assert!(worker.answered());
+        worker.start(300);
+        assert_eq!(worker.finish(Duration::from_millis(1)), (Some(&0), false));
+        // still busy with 300, so 1 is dropped and 300 is what the next tick gets
+        worker.start(1);
+        assert_eq!(worker.finish(Duration::from_secs(5)), (Some(&300), true));
+    }
+}
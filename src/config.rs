@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+/// Value of one config key, TOML's numbers and strings both end up as the text a flag would get
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Text(String),
+    List(Vec<String>),
+}
+
+/// `key = value` line of the config, keys under a `[table]` header are prefixed like `table.key`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub line: usize,
+    pub key: String,
+    pub value: Value,
+}
+
+/// `$XDG_CONFIG_HOME/hwtop/config.toml`, or `~/.config/hwtop/config.toml` when it isn't set
+pub fn path() -> Option<PathBuf> {
+    let env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let dir = env("XDG_CONFIG_HOME").or_else(|| env("HOME").map(|home| home.join(".config")))?;
+    Some(dir.join("hwtop").join("config.toml"))
+}
+
+/// Entries of the config file, `None` when there isn't one
+pub fn load(path: &Path) -> Result<Option<Vec<Entry>>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse(&text).map(Some).map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("can't read '{}': {e}", path.display())),
+    }
+}
+
+/// The subset of TOML a flat list of options needs: `key = value` with strings, numbers, booleans,
+/// single line arrays, `[table]` headers and `#` comments
+pub fn parse(text: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = vec![];
+    let mut table = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |msg: String| format!("line {line_no}: {msg}");
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name.strip_suffix(']').ok_or_else(|| err(format!("unclosed table header '{line}'")))?.trim();
+            if !is_key(name) {
+                return Err(err(format!("invalid table name '{name}'")));
+            }
+            table = format!("{name}.");
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| err(format!("expected key = value, got '{line}'")))?;
+        let key = key.trim();
+        if !is_key(key) {
+            return Err(err(format!("invalid key '{key}'")));
+        }
+        let value = value.trim();
+        let value = if let Some(items) = value.strip_prefix('[') {
+            let items = items.strip_suffix(']').ok_or_else(|| err(format!("arrays have to close on the same line, '{key}' doesn't")))?;
+            Value::List(split_list(items).into_iter().map(|item| scalar(item.trim())).collect::<Result<_, _>>().map_err(err)?)
+        } else {
+            match value {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => Value::Text(scalar(value).map_err(err)?),
+            }
+        };
+        entries.push(Entry { line: line_no, key: format!("{table}{key}"), value });
+    }
+    Ok(entries)
+}
+
+fn is_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Cuts a `#` comment off the line, `#` inside a string is kept
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), _) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// Splits array items on the commas outside of strings, a trailing comma is allowed
+fn split_list(items: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut quote) = (0, None);
+    for (i, c) in items.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q && !items[..i].ends_with('\\') => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ',') => {
+                parts.push(&items[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&items[start..]);
+    parts.into_iter().filter(|part| !part.trim().is_empty()).collect()
+}
+
+/// A quoted string or a bare number
+fn scalar(value: &str) -> Result<String, String> {
+    if let Some(literal) = value.strip_prefix('\'') {
+        return literal.strip_suffix('\'').filter(|s| !s.contains('\'')).map(str::to_string).ok_or_else(|| format!("unclosed string {value}"));
+    }
+    if let Some(quoted) = value.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"').ok_or_else(|| format!("unclosed string {value}"))?;
+        let mut out = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            out.push(match (c, c == '\\') {
+                (_, true) => match chars.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    other => return Err(format!("unsupported escape '\\{}' in {value}", other.map(String::from).unwrap_or_default())),
+                },
+                ('"', false) => return Err(format!("unescaped quote in {value}")),
+                (c, false) => c,
+            });
+        }
+        return Ok(out);
+    }
+    // bare values are numbers, `1_000` style separators included
+    if value.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) && value.chars().all(|c| c.is_ascii_alphanumeric() || "+-._".contains(c)) {
+        return Ok(value.replace('_', ""));
+    }
+    Err(format!("expected a string, number, true, false or [array], got '{value}'"))
+}
+
+/// Quotes `value` as a TOML string
+#[must_use]
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t"))
+}
+
+/// Replaces `key`'s line in the config file at `path` with `line`, or appends it when the key isn't there
+pub fn set_key(path: &Path, key: &str, line: &str) -> std::io::Result<()> {
+    let text = std::fs::read_to_string(path)?;
+    let mut found = false;
+    // only top level keys, the first table header ends them
+    let mut top_level = true;
+    let mut lines: Vec<String> = text.lines().map(|existing| {
+        let trimmed = existing.trim_start();
+        top_level &= !trimmed.starts_with('[');
+        let is_key = trimmed.split_once('=').is_some_and(|(k, _)| k.trim().replace('-', "_") == key);
+        if top_level && is_key && !found {
+            found = true;
+            line.to_string()
+        } else {
+            existing.to_string()
+        }
+    }).collect();
+    if !found {
+        let first_table = lines.iter().position(|existing| existing.trim_start().starts_with('[')).unwrap_or(lines.len());
+        lines.insert(first_table, line.to_string());
+    }
+    std::fs::write(path, lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(line: usize, key: &str, value: Value) -> Entry {
+        Entry { line, key: key.to_string(), value }
+    }
+
+    #[test]
+    fn parses_values() {
+        let text = "\
+# hwtop config
+interval = 0.5
+color = \"never\" # trailing comment
+fahrenheit = true
+cores = false
+min_disk_size = '500M'
+exclude_iface = [\"virbr*\", 'tun#0',]
+
+[colors]
+dim = \"#888888\"
+";
+        assert_eq!(parse(text), Ok(vec![
+            entry(2, "interval", Value::Text("0.5".to_string())),
+            entry(3, "color", Value::Text("never".to_string())),
+            entry(4, "fahrenheit", Value::Bool(true)),
+            entry(5, "cores", Value::Bool(false)),
+            entry(6, "min_disk_size", Value::Text("500M".to_string())),
+            entry(7, "exclude_iface", Value::List(vec!["virbr*".to_string(), "tun#0".to_string()])),
+            entry(10, "colors.dim", Value::Text("#888888".to_string())),
+        ]));
+    }
+
+    #[test]
+    fn malformed_lines_name_the_line() {
+        assert_eq!(parse("interval = 1\ncolor never"), Err("line 2: expected key = value, got 'color never'".to_string()));
+        assert_eq!(parse("color = \"never"), Err("line 1: unclosed string \"never".to_string()));
+        assert_eq!(parse("disk = [\"sda\""), Err("line 1: arrays have to close on the same line, 'disk' doesn't".to_string()));
+        assert_eq!(parse("[theme"), Err("line 1: unclosed table header '[theme'".to_string()));
+        assert_eq!(parse("iface = eth 0"), Err("line 1: expected a string, number, true, false or [array], got 'eth 0'".to_string()));
+        assert_eq!(parse("my key = 1"), Err("line 1: invalid key 'my key'".to_string()));
+    }
+
+    #[test]
+    fn quote_round_trips() {
+        for value in ["plain", "with \"quotes\"", "back\\slash", "#hash"] {
+            assert_eq!(parse(&format!("key = {}", quote(value))), Ok(vec![entry(1, "key", Value::Text(value.to_string()))]));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::{fs::OpenOptions, io::{self, Write}, time::SystemTime};
+
+use crate::{collect::Snapshot, error::HwtopError};
+
+/// Appends one row per tick, columns are fixed by the first snapshot so rows stay aligned with the header
+pub struct CsvLog {
+    out: Box<dyn Write>,
+    write_header: bool,
+    columns: Option<(usize, Vec<String>)>, // core count, disk names
+    path: String,
+}
+
+impl CsvLog {
+    /// Opens `path` for appending, `-` logs to stdout
+    pub fn open(path: &str) -> Result<Self, HwtopError> {
+        let (out, write_header): (Box<dyn Write>, bool) = if path == "-" {
+            (Box::new(io::stdout()), true)
+        } else {
+            let file = OpenOptions::new().create(true).append(true).open(path).map_err(HwtopError::io(format!("can't open '{path}'")))?;
+            let empty = file.metadata().map_err(HwtopError::io(format!("can't open '{path}'")))?.len() == 0;
+            (Box::new(file), empty)
+        };
+        Ok(Self { out, write_header, columns: None, path: path.to_string() })
+    }
+
+    pub fn write(&mut self, snapshot: &Snapshot) -> Result<(), HwtopError> {
+        let result = self.write_row(snapshot);
+        if self.path == "-" {
+            return result.map_err(HwtopError::terminal("can't write CSV to stdout"));
+        }
+        result.map_err(HwtopError::io(format!("can't write '{}'", self.path)))
+    }
+
+    fn write_row(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let (cores, disks) = self.columns.get_or_insert_with(|| {
+            let mut disks: Vec<String> = snapshot.disks.iter().map(|disk| disk.name.clone()).collect();
+            disks.sort();
+            (snapshot.cpu.cores.len(), disks)
+        });
+        let cores = *cores;
+        if self.write_header {
+            self.write_header = false;
+            let mut header = vec!["timestamp".to_string(), "cpu".into(), "cpu_temp".into()];
+            header.extend((0..cores).map(|i| format!("core{i}")));
+            header.extend(["ram_used", "ram_total", "gpu", "gpu_temp", "gpu_power_w", "vram_used", "net_rx_bps", "net_tx_bps"].map(String::from));
+            for disk in disks.iter() {
+                header.push(format!("{disk}_read_bps"));
+                header.push(format!("{disk}_write_bps"));
+            }
+            writeln!(self.out, "{}", header.join(","))?;
+        }
+
+        let cell = |value: Option<String>| value.unwrap_or_default();
+        let gpu = snapshot.gpus.first();
+        let mut row = vec![timestamp(SystemTime::now()), format!("{:.1}", snapshot.cpu.usage), cell(snapshot.cpu.temp_c.map(|t| t.to_string()))];
+        row.extend((0..cores).map(|i| cell(snapshot.cpu.cores.get(i).map(|core| format!("{:.1}", core.usage)))));
+        row.push(snapshot.memory.ram_used.to_string());
+        row.push(snapshot.memory.ram_total.to_string());
+        row.push(cell(gpu.and_then(|gpu| gpu.usage).map(|v| v.to_string())));
+        row.push(cell(gpu.and_then(|gpu| gpu.temp_c).map(|v| v.to_string())));
+        row.push(cell(gpu.and_then(|gpu| gpu.power_w).map(|v| v.to_string())));
+        row.push(cell(gpu.and_then(|gpu| gpu.vram_used).map(|v| v.to_string())));
+        row.push(snapshot.networks.iter().map(|net| net.rx_bps).sum::<u64>().to_string());
+        row.push(snapshot.networks.iter().map(|net| net.tx_bps).sum::<u64>().to_string());
+        for name in disks.iter() {
+            let disk = snapshot.disks.iter().find(|disk| &disk.name == name);
+            row.push(cell(disk.map(|disk| disk.read_bps.to_string())));
+            row.push(cell(disk.map(|disk| disk.write_bps.to_string())));
+        }
+        writeln!(self.out, "{}", row.join(","))?;
+        self.out.flush()
+    }
+}
+
+/// UTC `YYYY-MM-DDTHH:MM:SSZ`, which spreadsheets parse as a date
+#[must_use]
+fn timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // days since epoch to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", secs / 3600, secs % 3600 / 60, secs % 60)
+}
@@ -0,0 +1,98 @@
+use std::{fmt, io};
+
+#[cfg(feature = "nvidia")]
+use nvml_wrapper::error::NvmlError;
+
+/// Everything that can go wrong, the context names the device, file or query involved so a report says which part broke
+#[derive(Debug)]
+pub enum HwtopError {
+    /// an NVML query, the context names the GPU and what was asked
+    #[cfg(feature = "nvidia")]
+    Nvml { context: String, source: NvmlError },
+    /// a sensor that was found but stopped answering
+    Sensor { context: String, message: String },
+    /// reading or writing a config, recording or CSV log
+    Io { context: String, source: io::Error },
+    /// drawing frames or writing to stdout
+    Terminal { context: String, source: io::Error },
+    /// a recording line that can't be read as JSON
+    Parse { context: String, message: String },
+    /// a command line, config or `get` key that can't be made sense of, exits with 2 like other CLIs
+    Usage { message: String },
+}
+
+impl HwtopError {
+    /// For `map_err`, NVML errors of the query in `context`
+    #[cfg(feature = "nvidia")]
+    pub fn nvml(context: impl Into<String>) -> impl FnOnce(NvmlError) -> Self {
+        move |source| Self::Nvml { context: context.into(), source }
+    }
+
+    /// For `map_err`, IO errors of the file in `context`
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        move |source| Self::Io { context: context.into(), source }
+    }
+
+    /// For `map_err`, IO errors of the terminal write in `context`
+    pub fn terminal(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        move |source| Self::Terminal { context: context.into(), source }
+    }
+
+    /// A GPU or sensor failing only loses its rows, the rest keeps going. A terminal that can't be drawn to, a file that can't be
+    /// written or a recording that can't be read leave nothing to show
+    #[must_use]
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            #[cfg(feature = "nvidia")]
+            Self::Nvml { .. } => false,
+            Self::Sensor { .. } => false,
+            Self::Io { .. } | Self::Terminal { .. } | Self::Parse { .. } | Self::Usage { .. } => true,
+        }
+    }
+}
+
+impl fmt::Display for HwtopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "nvidia")]
+            Self::Nvml { context, source } => write!(f, "NVML: {context}: {source}"),
+            Self::Sensor { context, message } | Self::Parse { context, message } => write!(f, "{context}: {message}"),
+            Self::Io { context, source } => write!(f, "{context}: {source}"),
+            Self::Terminal { context, source } => write!(f, "terminal: {context}: {source}"),
+            Self::Usage { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for HwtopError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "nvidia")]
+            Self::Nvml { source, .. } => Some(source),
+            Self::Io { source, .. } | Self::Terminal { source, .. } => Some(source),
+            Self::Sensor { .. } | Self::Parse { .. } | Self::Usage { .. } => None,
+        }
+    }
+}
+
+/// Frames and listings are built in a `String`, so this only happens when a `Display` impl fails
+impl From<fmt::Error> for HwtopError {
+    fn from(e: fmt::Error) -> Self {
+        Self::Terminal { context: "can't format the output".to_string(), source: io::Error::other(e) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_names_the_part_that_broke() {
+        let error = HwtopError::terminal("can't draw the frame")(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert_eq!(error.to_string(), "terminal: can't draw the frame: broken pipe");
+        assert!(error.is_fatal());
+        let error = HwtopError::Sensor { context: "hwmon".to_string(), message: "the sensor thread stopped".to_string() };
+        assert_eq!(error.to_string(), "hwmon: the sensor thread stopped");
+        assert!(!error.is_fatal());
+    }
+}
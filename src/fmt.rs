@@ -0,0 +1,617 @@
+use crate::{glyphs, theme::Theme};
+
+/// Divisor used by `format_size`, binary (KiB, MiB, ..) by default or SI (kB, MB, ..) with `--si`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Units {
+    #[default]
+    Binary,
+    Si,
+}
+
+#[must_use]
+pub fn format_size(bytes: u64, units: Units) -> String {
+    let base = match units {
+        Units::Binary => 1u64 << 10,
+        Units::Si => 1000,
+    };
+    if bytes < base {
+        return format!("{bytes}B");
+    }
+    let scaled = |unit: u64, suffix: &str| {
+        let val = bytes as f64 / unit as f64;
+        if bytes >= 100 * unit {
+            format!("{val:.0}{suffix}")
+        } else {
+            let val = (val * 10.0).round() / 10.0;
+            format!("{val}{suffix}")
+        }
+    };
+    let mut unit = base;
+    for suffix in ["K", "M", "G"] {
+        if bytes < unit * base {
+            return scaled(unit, suffix);
+        }
+        unit *= base;
+    }
+    scaled(unit, "T")
+}
+
+/// Watt-hours with about three significant digits, kWh from 1000 on
+#[must_use]
+pub fn format_energy(wh: f64) -> String {
+    match wh {
+        wh if wh < 10.0 => format!("{wh:.2}Wh"),
+        wh if wh < 1000.0 => format!("{wh:.1}Wh"),
+        wh => format!("{:.2}kWh", wh / 1000.0),
+    }
+}
+
+/// Columns a terminal shows `text` in, escapes like colors take none and wide characters (CJK, emoji) two
+#[must_use]
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI is `ESC [`, parameters, then a final byte in `@..=~`, other escapes are one more character
+            if chars.next() == Some('[') {
+                chars.by_ref().find(|c| ('@'..='~').contains(c));
+            }
+            continue;
+        }
+        width += char_width(c);
+    }
+    width
+}
+
+/// Terminal columns of one character, the common wide and zero width ranges without a full Unicode table
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        // combining marks, zero width spaces and joiners, variation selectors
+        0x0300..=0x036f | 0x200b..=0x200f | 0xfe00..=0xfe0f => 0,
+        // Hangul Jamo, CJK, Hangul syllables, fullwidth forms, emoji
+        0x1100..=0x115f | 0x2e80..=0x303e | 0x3041..=0x33ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xa000..=0xa4cf | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff | 0xfe30..=0xfe4f | 0xff00..=0xff60 | 0xffe0..=0xffe6 | 0x1f300..=0x1f64f | 0x1f900..=0x1f9ff | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// Eighth of a cell `percent` fills, for the glyph tables
+fn eighths(percent: u32) -> usize {
+    match percent {
+        0..=12 => 0,
+        13..=25 => 1,
+        26..=37 => 2,
+        38..=50 => 3,
+        51..=62 => 4,
+        63..=75 => 5,
+        76..=87 => 6,
+        _ => 7,
+    }
+}
+
+/// One bar of a row rising to `percent`
+#[must_use]
+pub fn percent_bar(percent: u32) -> &'static str {
+    glyphs::get().bars[eighths(percent)]
+}
+
+/// The partial last cell of a bar filled to `percent` of it
+#[must_use]
+pub fn percent_slider(percent: u32) -> &'static str {
+    glyphs::get().slider[eighths(percent)]
+}
+
+/// `[███▌    ]`, `width` cells between the brackets filled to `ratio`
+#[must_use]
+pub fn fill_bar(theme: &Theme, ratio: f64, width: u32) -> String {
+    stacked_bar(theme, ratio, 0.0, width)
+}
+
+/// `[███▌░░  ]`, `fill_bar` followed by `cached` more of the width in a dim shade, rounded to whole cells
+#[must_use]
+pub fn stacked_bar(theme: &Theme, ratio: f64, cached: f64, width: u32) -> String {
+    reserved_bar(theme, ratio, 0.0, cached, width)
+}
+
+/// `[██▒▒░░  ]`, `stacked_bar` with the last `reserved` of `ratio` in a shade of its own, for memory that's taken but unused
+#[must_use]
+pub fn reserved_bar(theme: &Theme, ratio: f64, reserved: f64, cached: f64, width: u32) -> String {
+    let reset = theme.reset();
+    let used = ratio.clamp(0.0, 1.0);
+    let reserved = reserved.clamp(0.0, used);
+    let ratio = used - reserved;
+    let full_blocks = (ratio * width as f64) as u32;
+    let col = theme.bar((used * 100.0).round() as u32);
+    let glyphs = glyphs::get();
+    // bar glyphs take up to 3 bytes, the room for them and the colors is taken up front
+    let mut bar = String::with_capacity(width as usize * 3 + 64);
+    bar.push('[');
+    bar.push_str(col);
+    bar.extend(std::iter::repeat_n(glyphs.full, full_blocks as usize));
+    if full_blocks == width {
+        bar.push_str(reset);
+        bar.push(']');
+        return bar;
+    }
+    let remainder_percent = ((ratio * width as f64).fract() * 100.0).round() as u32;
+    let cell_end = |extra: f64| ((ratio + extra).min(1.0) * width as f64).round() as u32;
+    let end = cell_end(reserved + cached.max(0.0));
+    // an empty partial cell gives way to the shade when there's any
+    let remainder = if remainder_percent == 0 && end > full_blocks { "" } else { percent_slider(remainder_percent) };
+    let used_cells = full_blocks + u32::from(!remainder.is_empty());
+    let reserved_end = cell_end(reserved).max(used_cells);
+    let filled = end.max(reserved_end);
+    bar.push_str(remainder);
+    bar.push_str(reset);
+    for (glyph, cells) in [(glyphs.reserved, reserved_end - used_cells), (glyphs.shade, filled - reserved_end)] {
+        if cells > 0 {
+            bar.push_str(theme.dim());
+            bar.extend(std::iter::repeat_n(glyph, cells as usize));
+            bar.push_str(reset);
+        }
+    }
+    bar.extend(std::iter::repeat_n(' ', (width - filled) as usize));
+    bar.push(']');
+    bar
+}
+
+/// `[███▌  ┃ ]`, puts the peak glyph on the cell `peak` reaches in a bar of `width` cells filled to `ratio`,
+/// a peak inside the filled part leaves the bar as it is. Anything after the bar, like `mem_bar`'s text, is kept
+#[must_use]
+pub fn mark_peak(theme: &Theme, bar: &str, ratio: f64, peak: f64, width: u32) -> String {
+    let cell = ((peak.clamp(0.0, 1.0) * width as f64).ceil() as u32).max(1) - 1;
+    let filled = (ratio.clamp(0.0, 1.0) * width as f64).ceil() as u32;
+    if cell < filled || cell >= width {
+        return bar.to_string();
+    }
+    // the cell is found by its visible position, the one color that was on comes back after the marker
+    let (mut visible, mut escape, mut active) = (0, None, "");
+    for (i, c) in bar.char_indices() {
+        if let Some(start) = escape {
+            if c.is_ascii_alphabetic() {
+                active = &bar[start..=i];
+                escape = None;
+            }
+            continue;
+        }
+        if c == '\x1b' {
+            escape = Some(i);
+            continue;
+        }
+        // the opening bracket comes first
+        if visible == cell + 1 {
+            let col = theme.bar((peak * 100.0).round() as u32);
+            return format!("{}{col}{}{}{active}{}", &bar[..i], glyphs::get().peak, theme.reset(), &bar[i + c.len_utf8()..]);
+        }
+        visible += 1;
+    }
+    bar.to_string()
+}
+
+/// `5.2G/15.5G` colored by how full it is, an empty `total` counts as 0%
+#[must_use]
+pub fn mem_usage(theme: &Theme, used: u64, total: u64, units: Units) -> String {
+    let reset = theme.reset();
+    let used_str = format_size(used, units);
+    let total_str = format_size(total, units);
+    let percent = (ratio(used, total) * 100.0).round() as u32;
+    format!("{}{used_str}{reset}/{}{total_str}{reset}", theme.bar(percent), theme.bar(percent))
+}
+
+/// `stacked_bar` followed by `mem_usage`, with `cached` the text also shows what's available (`total - used`)
+#[must_use]
+pub fn mem_bar(theme: &Theme, used: u64, cached: u64, total: u64, width: u32, units: Units) -> String {
+    ram_bar(theme, used, 0, cached, total, width, units)
+}
+
+/// `mem_bar` with `reserved` bytes of `used` in their own shade, like hugepages nothing has mapped yet
+#[must_use]
+pub fn ram_bar(theme: &Theme, used: u64, reserved: u64, cached: u64, total: u64, width: u32, units: Units) -> String {
+    let bar = reserved_bar(theme, ratio(used, total), ratio(reserved, total), ratio(cached, total), width);
+    if cached == 0 {
+        return format!("{bar} {}", mem_usage(theme, used, total, units));
+    }
+    let (col, dim, reset) = (theme.bar((ratio(used, total) * 100.0).round() as u32), theme.dim(), theme.reset());
+    format!("{bar} {col}{}{reset}/{dim}{}{reset}/{col}{}{reset}", format_size(used, units), format_size(total.saturating_sub(used), units), format_size(total, units))
+}
+
+fn ratio(used: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { used as f64 / total as f64 }
+}
+
+/// Splits a row at its `;`s, skipping the ones inside escapes like `\x1b[38;5;33m`
+fn cells(row: &str) -> Vec<&str> {
+    let mut cells = vec![];
+    let (mut start, mut escape, mut csi) = (0, false, false);
+    for (i, byte) in row.bytes().enumerate() {
+        match byte {
+            0x1b => escape = true,
+            b'[' if escape && !csi => csi = true,
+            b'@'..=b'~' if escape => (escape, csi) = (false, false),
+            b';' if !escape => {
+                cells.push(&row[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    cells.push(&row[start..]);
+    cells
+}
+
+/// Text of one `rows` cell, a `;` in names read from the system would split it in two
+#[must_use]
+pub fn cell(text: &str) -> String {
+    text.replace(';', "")
+}
+
+/// Side of its column a `rows` cell sticks to, numbers read best on the right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Cells laid out in columns, kept from one frame to the next so the cells' strings are written over instead of allocated again.
+/// Cells are written into with `write!`, a `;` in them is just text
+#[derive(Default)]
+pub struct Table {
+    cells: Vec<String>,
+    /// cells in use, the ones past it are spare room from bigger tables
+    used: usize,
+    /// where each row's cells end
+    ends: Vec<usize>,
+    widths: Vec<usize>,
+}
+
+impl Table {
+    /// A new empty cell at the end of the current row
+    pub fn cell(&mut self) -> &mut String {
+        if self.used == self.cells.len() {
+            self.cells.push(String::new());
+        }
+        let cell = &mut self.cells[self.used];
+        cell.clear();
+        self.used += 1;
+        cell
+    }
+
+    /// Ends the current row, the next cell starts another one
+    pub fn end_row(&mut self) {
+        self.ends.push(self.used);
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[String]> {
+        let mut start = 0;
+        self.ends.iter().map(move |&end| {
+            let row = &self.cells[start..end];
+            start = end;
+            row
+        })
+    }
+
+    /// Appends the rows to `out` in columns as wide as their widest cell, colors don't count, and empties the table
+    pub fn write(&mut self, out: &mut String, aligns: &[Align]) {
+        let mut widths = std::mem::take(&mut self.widths);
+        widths.clear();
+        for row in self.rows() {
+            for (i, cell) in row.iter().enumerate() {
+                if i == widths.len() {
+                    widths.push(0);
+                }
+                widths[i] = widths[i].max(visible_width(cell));
+            }
+        }
+        self.write_sized(out, &widths, aligns);
+        self.widths = widths;
+    }
+
+    /// Appends the rows to `out` in columns of `sizes` characters aligned by `aligns`, left by default, and empties the table.
+    /// A left aligned last column isn't padded, columns without a size aren't either and sizes without a column are ignored
+    pub fn write_sized(&mut self, out: &mut String, sizes: &[usize], aligns: &[Align]) {
+        let columns = self.rows().map(<[String]>::len).max().unwrap_or(0);
+        for row in self.rows() {
+            // short rows get empty cells so the columns after them stay in place
+            for i in 0..columns {
+                let item = row.get(i).map_or("", String::as_str);
+                let align = aligns.get(i).copied().unwrap_or(Align::Left);
+                let size = if i == columns - 1 && align == Align::Left {
+                    0
+                } else { sizes.get(i).copied().unwrap_or(0) };
+                let pad = size.saturating_sub(visible_width(item));
+                match align {
+                    Align::Left => {
+                        out.push_str(item);
+                        out.extend(std::iter::repeat_n(' ', pad));
+                    }
+                    Align::Right => {
+                        out.extend(std::iter::repeat_n(' ', pad));
+                        out.push_str(item);
+                    }
+                }
+                out.push(' ');
+            }
+            out.push('\n');
+        }
+        self.used = 0;
+        self.ends.clear();
+    }
+
+    /// A table of `;` separated rows
+    fn split(rows: &[String]) -> Self {
+        let mut table = Self::default();
+        for row in rows {
+            for item in cells(row) {
+                table.cell().push_str(item);
+            }
+            table.end_row();
+        }
+        table
+    }
+}
+
+/// Lays out `;` separated rows in columns of `sizes` characters aligned by `aligns`, see `Table::write_sized`
+#[must_use]
+pub fn sized_rows(rows: &[String], sizes: &[usize], aligns: &[Align]) -> String {
+    let mut out = String::new();
+    Table::split(rows).write_sized(&mut out, sizes, aligns);
+    out
+}
+
+/// Lays out `;` separated rows in left aligned columns as wide as their widest cell, colors don't count
+#[must_use]
+pub fn rows(rows: &[String]) -> String {
+    aligned_rows(rows, &[])
+}
+
+/// `rows` with each column aligned by `aligns`, columns past its end are left aligned
+#[must_use]
+pub fn aligned_rows(rows: &[String], aligns: &[Align]) -> String {
+    let mut out = String::new();
+    Table::split(rows).write(&mut out, aligns);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Preset;
+
+    fn themes() -> [Theme; 3] {
+        [Theme::plain(), Theme::new(Preset::Default, &[], false, false), Theme::new(Preset::Default, &[], true, true)]
+    }
+
+    #[test]
+    fn format_size_binary() {
+        let units = Units::Binary;
+        assert_eq!(format_size(0, units), "0B");
+        assert_eq!(format_size(1023, units), "1023B");
+        assert_eq!(format_size(1024, units), "1K");
+        assert_eq!(format_size(1536, units), "1.5K");
+        assert_eq!(format_size(100 * 1024 - 1, units), "100K");
+        assert_eq!(format_size(100 * 1024, units), "100K");
+        assert_eq!(format_size(1 << 20, units), "1M");
+        assert_eq!(format_size(99 * (1 << 30) + 900 * (1 << 20), units), "99.9G");
+        assert_eq!(format_size(100 * (1 << 30), units), "100G");
+        assert_eq!(format_size(1 << 40, units), "1T");
+        assert_eq!(format_size(2560 * (1 << 40), units), "2560T");
+    }
+
+    #[test]
+    fn format_size_si() {
+        let units = Units::Si;
+        assert_eq!(format_size(999, units), "999B");
+        assert_eq!(format_size(1000, units), "1K");
+        assert_eq!(format_size(1023, units), "1K");
+        assert_eq!(format_size(1500, units), "1.5K");
+        assert_eq!(format_size(99_900_000_000, units), "99.9G");
+        assert_eq!(format_size(100_000_000_000, units), "100G");
+        assert_eq!(format_size(1_000_000_000_000, units), "1T");
+        assert_eq!(format_size(4_000_000_000_000, units), "4T");
+    }
+
+    #[test]
+    fn format_size_unit_boundaries() {
+        // last value of each unit, then the first of the next
+        for (bytes, short) in [((1 << 20) - 1, "1024K"), (1 << 20, "1M"), ((1 << 30) - 1, "1024M"), (1 << 30, "1G"), ((1 << 40) - 1, "1024G"), (1 << 40, "1T")] {
+            assert_eq!(format_size(bytes, Units::Binary), short, "{bytes}");
+        }
+        for (bytes, short) in [(999_999, "1000K"), (1_000_000, "1M"), (999_999_999_999, "1000G"), (u64::MAX, "18446744T")] {
+            assert_eq!(format_size(bytes, Units::Si), short, "{bytes}");
+        }
+        // one decimal below 100 of a unit, none from 100 on
+        assert_eq!(format_size(100 * (1 << 30) - (1 << 20), Units::Binary), "100G");
+        assert_eq!(format_size(99 * (1 << 40), Units::Binary), "99T");
+        assert_eq!(format_size(99 * (1 << 40) + (1 << 39), Units::Binary), "99.5T");
+        assert_eq!(format_size(100 * (1 << 40) + (1 << 39), Units::Binary), "100T");
+    }
+
+    #[test]
+    fn format_energy_steps_to_kwh() {
+        assert_eq!(format_energy(0.0), "0.00Wh");
+        assert_eq!(format_energy(3.456), "3.46Wh");
+        assert_eq!(format_energy(14.23), "14.2Wh");
+        assert_eq!(format_energy(999.9), "999.9Wh");
+        assert_eq!(format_energy(1234.0), "1.23kWh");
+    }
+
+    #[test]
+    fn mem_bar_edge_cases() {
+        let theme = Theme::plain();
+        let full = glyphs::get().full;
+        assert_eq!(mem_bar(&theme, 3 << 30, 0, 2 << 30, 4, Units::Binary), format!("[{}] 3G/2G", full.repeat(4)));
+        assert_eq!(mem_bar(&theme, 0, 0, 0, 4, Units::Binary), format!("[{}   ] 0B/0B", percent_slider(0)));
+        assert_eq!(mem_bar(&theme, 1 << 30, 0, 2 << 30, 1, Units::Binary), format!("[{}] 1G/2G", percent_slider(50)));
+        assert_eq!(mem_bar(&theme, 2 << 30, 0, 2 << 30, 10, Units::Binary), format!("[{}] 2G/2G", full.repeat(10)));
+    }
+
+    #[test]
+    fn stacked_bar_rounds_to_whole_cells() {
+        let theme = Theme::plain();
+        let (full, shade) = (glyphs::get().full, glyphs::get().shade);
+        assert_eq!(stacked_bar(&theme, 0.25, 0.25, 4), format!("[{full}{shade}  ]"));
+        assert_eq!(stacked_bar(&theme, 0.3, 0.3, 4), format!("[{full}{}  ]", percent_slider(20)));
+        assert_eq!(stacked_bar(&theme, 0.3, 0.4, 4), format!("[{full}{}{shade} ]", percent_slider(20)));
+        assert_eq!(stacked_bar(&theme, 0.5, 0.6, 4), format!("[{}{}]", full.repeat(2), shade.repeat(2)));
+        assert_eq!(stacked_bar(&theme, 1.0, 0.5, 4), format!("[{}]", full.repeat(4)));
+        assert_eq!(stacked_bar(&theme, 0.3, 0.0, 4), fill_bar(&theme, 0.3, 4));
+        for width in 0..=12 {
+            for used in 0..=20 {
+                for cached in 0..=20 {
+                    let bar = stacked_bar(&theme, used as f64 / 20.0, cached as f64 / 20.0, width);
+                    assert_eq!(visible_width(&bar), width as usize + 2, "{used} {cached} of {width} is '{bar}'");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_memory_sits_between_used_and_cached() {
+        let theme = Theme::plain();
+        let (full, shade, reserved) = (glyphs::get().full, glyphs::get().shade, glyphs::get().reserved);
+        assert_eq!(reserved_bar(&theme, 0.5, 0.25, 0.25, 4), format!("[{full}{reserved}{shade} ]"));
+        assert_eq!(reserved_bar(&theme, 0.5, 1.0, 0.0, 4), format!("[{}  ]", reserved.repeat(2)));
+        assert_eq!(ram_bar(&theme, 2 << 30, 1 << 30, 0, 4 << 30, 4, Units::Binary), format!("[{full}{reserved}  ] 2G/4G"));
+        for width in 0..=12 {
+            for used in 0..=20 {
+                for reserved in 0..=20 {
+                    let bar = reserved_bar(&theme, used as f64 / 20.0, reserved as f64 / 20.0, 0.3, width);
+                    assert_eq!(visible_width(&bar), width as usize + 2, "{used} {reserved} of {width} is '{bar}'");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mem_bar_shows_what_is_available_next_to_cache() {
+        let theme = Theme::plain();
+        assert_eq!(mem_bar(&theme, 1 << 30, 1 << 30, 4 << 30, 4, Units::Binary), format!("[{}{}  ] 1G/3G/4G", glyphs::get().full, glyphs::get().shade));
+    }
+
+    #[test]
+    fn peaks_are_marked_past_the_filled_cells() {
+        let plain = Theme::plain();
+        let (full, peak) = (glyphs::get().full, glyphs::get().peak);
+        assert_eq!(mark_peak(&plain, &fill_bar(&plain, 0.25, 4), 0.25, 0.75, 4), format!("[{full}{}{peak} ]", percent_slider(0)));
+        assert_eq!(mark_peak(&plain, &mem_bar(&plain, 1 << 30, 1 << 30, 4 << 30, 4, Units::Binary), 0.25, 0.5, 4), format!("[{full}{peak}  ] 1G/3G/4G"));
+        // nothing to mark inside what's filled or in a bar without cells
+        assert_eq!(mark_peak(&plain, &fill_bar(&plain, 0.5, 4), 0.5, 0.4, 4), fill_bar(&plain, 0.5, 4));
+        assert_eq!(mark_peak(&plain, &fill_bar(&plain, 0.0, 0), 0.0, 1.0, 0), "[]");
+        for theme in themes() {
+            for width in 1..=12 {
+                for step in 0..=20 {
+                    let bar = stacked_bar(&theme, 0.1, 0.2, width);
+                    let marked = mark_peak(&theme, &bar, 0.1, step as f64 / 20.0, width);
+                    assert_eq!(visible_width(&marked), width as usize + 2, "peak {step} of {width} is '{marked}'");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bars_have_the_requested_width() {
+        for theme in themes() {
+            for width in 0..=20 {
+                for step in -10..=210 {
+                    let ratio = step as f64 / 200.0;
+                    let bar = fill_bar(&theme, ratio, width);
+                    assert_eq!(visible_width(&bar), width as usize + 2, "{ratio} of {width} is '{bar}'");
+                }
+            }
+            for percent in 0..=150 {
+                assert_eq!(visible_width(percent_bar(percent)), 1);
+                assert_eq!(visible_width(percent_slider(percent)), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn rows_edge_cases() {
+        assert_eq!(rows(&[]), "");
+        assert_eq!(rows(&["a".to_string(), "bcd".to_string()]), "a \nbcd \n");
+        assert_eq!(rows(&["a;b".to_string(), "ccc;d".to_string()]), "a   b \nccc d \n");
+    }
+
+    #[test]
+    fn ragged_rows_are_padded() {
+        let ragged = ["a;b;c".to_string(), "dd".to_string(), "e;ff".to_string()];
+        assert_eq!(rows(&ragged), "a  b  c \ndd     \ne  ff  \n");
+    }
+
+    #[test]
+    fn sizes_and_columns_can_differ() {
+        let table = ["a;b;c".to_string(), "d;e".to_string()];
+        // more columns than sizes, the unsized middle one isn't padded
+        assert_eq!(sized_rows(&table, &[3], &[]), "a   b c \nd   e  \n");
+        // more sizes than columns
+        assert_eq!(sized_rows(&table, &[3, 2, 9, 9], &[]), "a   b  c \nd   e   \n");
+        assert_eq!(sized_rows(&[], &[4], &[]), "");
+    }
+
+    #[test]
+    fn cells_cant_split_rows() {
+        let disk = format!("{};{}", cell("my;disk"), cell("50G"));
+        assert_eq!(rows(&[disk, "sda;1T".to_string()]), "mydisk 50G \nsda    1T \n");
+    }
+
+    #[test]
+    fn table_cells_are_reused() {
+        let mut table = Table::default();
+        let mut out = String::new();
+        for (name, size) in [("my;disk", "50G"), ("sda", "1T")] {
+            table.cell().push_str(name);
+            table.cell().push_str(size);
+            table.end_row();
+        }
+        table.write(&mut out, &[Align::Left, Align::Right]);
+        assert_eq!(out, "my;disk 50G \nsda      1T \n");
+        // written tables start over, with the cells of the last one as spare room
+        table.cell().push('a');
+        table.end_row();
+        table.write(&mut out, &[]);
+        assert_eq!(out, "my;disk 50G \nsda      1T \na \n");
+        assert_eq!(table.cells.len(), 4);
+    }
+
+    #[test]
+    fn visible_width_skips_escapes() {
+        assert_eq!(visible_width("plain"), 5);
+        assert_eq!(visible_width("\x1b[38;2;80;120;255m61°C\x1b[0m"), 4);
+        assert_eq!(visible_width("\x1b[1;5;7mCPU\x1b[0m \x1b[2K"), 4);
+        assert_eq!(visible_width("GPU 日本"), 8);
+        assert_eq!(visible_width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn colored_rows_align_like_plain_ones() {
+        let strip = |text: &str| {
+            let mut plain = String::new();
+            let mut chars = text.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1b' {
+                    chars.by_ref().find(|&c| c == 'm');
+                } else {
+                    plain.push(c);
+                }
+            }
+            plain
+        };
+        let plain = ["nvme0n1;45°C;R 1.2M/s".to_string(), "sda;--;R 0B/s".to_string()];
+        // a different number of escapes per cell, like disks colored by their temperature, with `;`s of their own
+        let colored = ["\x1b[94mnvme0n1\x1b[0m;\x1b[38;5;201m45°C\x1b[0m;\x1b[96mR\x1b[0m 1.2M/s".to_string(), "\x1b[94msda\x1b[0m;\x1b[2m--\x1b[0m;R 0B/s".to_string()];
+        assert_eq!(strip(&rows(&colored)), rows(&plain));
+        assert_eq!(rows(&plain), "nvme0n1 45°C R 1.2M/s \nsda     --   R 0B/s \n");
+    }
+
+    #[test]
+    fn numbers_align_right() {
+        let temps = ["nvme;45°C".to_string(), "Motherboard;27°C, 105°C".to_string(), "RAM;9°C".to_string()];
+        assert_eq!(aligned_rows(&temps, &[Align::Left, Align::Right]), "nvme               45°C \nMotherboard 27°C, 105°C \nRAM                 9°C \n");
+        let colored = ["\x1b[94mvda\x1b[0m;\x1b[38;2;1;2;3m173G\x1b[0m/252G;R 0B/s".to_string(), "sda;1T/2T;R 12.5M/s".to_string()];
+        let aligns = [Align::Left, Align::Right, Align::Right];
+        assert_eq!(aligned_rows(&colored, &aligns).replace("\x1b[94m", "").replace("\x1b[38;2;1;2;3m", "").replace("\x1b[0m", ""), "vda 173G/252G    R 0B/s \nsda     1T/2T R 12.5M/s \n");
+        // sizes still win over the cells, and a short right aligned column still fills its size
+        assert_eq!(sized_rows(&["a;1".to_string(), "b;22".to_string()], &[2, 4], &aligns), "a     1 \nb    22 \n");
+    }
+}
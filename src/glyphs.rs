@@ -0,0 +1,124 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static USE_ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Characters the display draws with, every glyph takes one column in both sets so the layout stays the same
+pub struct Glyphs {
+    /// bars rising in eighths
+    pub bars: [&'static str; 8],
+    /// partial cell of a bar filling left to right, in eighths
+    pub slider: [&'static str; 8],
+    pub full: &'static str,
+    /// memory the kernel can reclaim in the RAM bar
+    pub shade: &'static str,
+    /// hugepages set aside but not in use in the RAM bar
+    pub reserved: &'static str,
+    /// held peak of a bar, where it reached in the last few seconds
+    pub peak: &'static str,
+    /// download/upload markers of NETW and PCIE
+    pub rx: &'static str,
+    pub tx: &'static str,
+    /// moves on every snapshot in the footer
+    pub spinner: [&'static str; 8],
+    /// same for the one line `--statusline`
+    pub down: &'static str,
+    pub up: &'static str,
+    pub branch: &'static str,
+    pub last_branch: &'static str,
+    pub ellipsis: char,
+    /// original size to compressed size of zram and zswap
+    pub to: &'static str,
+    pub degree: &'static str,
+    /// only in the ALERT line, which isn't aligned with anything, so it may be wider in ASCII
+    pub at_least: &'static str,
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    bars: ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"],
+    slider: ["▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"],
+    full: "█",
+    shade: "░",
+    reserved: "▒",
+    peak: "┃",
+    rx: "▼",
+    tx: "▲",
+    spinner: ["⠁", "⠂", "⠄", "⡀", "⢀", "⠠", "⠐", "⠈"],
+    down: "↓",
+    up: "↑",
+    branch: "├─",
+    last_branch: "└─",
+    ellipsis: '…',
+    to: "→",
+    degree: "°",
+    at_least: "≥",
+};
+
+pub const ASCII: Glyphs = Glyphs {
+    bars: [".", ":", "-", "=", "+", "*", "#", "@"],
+    slider: [" ", " ", ".", ".", ":", ":", "#", "#"],
+    full: "#",
+    shade: "-",
+    reserved: "=",
+    peak: "|",
+    rx: "v",
+    tx: "^",
+    spinner: ["|", "/", "-", "\\", "|", "/", "-", "\\"],
+    down: "v",
+    up: "^",
+    branch: "|-",
+    last_branch: "`-",
+    ellipsis: '~',
+    to: ">",
+    degree: " ",
+    at_least: ">=",
+};
+
+/// Picks the glyph set for the rest of the run
+pub fn set_ascii(ascii: bool) {
+    USE_ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn get() -> &'static Glyphs {
+    if USE_ASCII.load(Ordering::Relaxed) { &ASCII } else { &UNICODE }
+}
+
+/// Whether the locale can't be trusted with Unicode, the first of `LC_ALL`, `LC_CTYPE` and `LANG` that's set decides
+#[must_use]
+pub fn locale_is_ascii() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+    !locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    })
+}
+
+/// Character of one braille cell, in ASCII the top and bottom half of the dots become `'`, `.` or `:`
+#[must_use]
+pub fn braille(bits: u8) -> char {
+    if !USE_ASCII.load(Ordering::Relaxed) {
+        return char::from_u32(0x2800 + bits as u32).unwrap_or(' ');
+    }
+    // dots 1, 2, 4 and 5 are the top two rows
+    match (bits & 0x1b != 0, bits & 0xe4 != 0) {
+        (true, true) => ':',
+        (true, false) => '\'',
+        (false, true) => '.',
+        (false, false) => ' ',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_have_the_same_widths() {
+        let widths = |glyphs: &Glyphs| {
+            let mut strs = [glyphs.bars.as_slice(), glyphs.slider.as_slice(), glyphs.spinner.as_slice()].concat();
+            strs.extend([glyphs.full, glyphs.peak, glyphs.rx, glyphs.tx, glyphs.down, glyphs.up, glyphs.branch, glyphs.last_branch, glyphs.degree]);
+            strs.iter().map(|glyph| glyph.chars().count()).collect::<Vec<_>>()
+        };
+        assert_eq!(widths(&UNICODE), widths(&ASCII));
+        assert!(ASCII.bars.iter().chain(&ASCII.slider).all(|glyph| glyph.is_ascii()));
+    }
+}
@@ -0,0 +1,88 @@
+use std::{collections::{HashMap, VecDeque}, time::{Duration, Instant}};
+
+/// Last `len` per-tick samples, oldest first, pre-filled with zeros so graphs don't jump while it fills up
+pub struct History<T> {
+    samples: VecDeque<T>,
+}
+
+impl<T: Copy + Default> History<T> {
+    pub fn new(len: usize) -> Self {
+        Self { samples: std::iter::repeat_n(T::default(), len).collect() }
+    }
+
+    pub fn push(&mut self, sample: T) {
+        if self.samples.is_empty() {
+            return;
+        }
+        self.samples.pop_front();
+        self.samples.push_back(sample);
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.samples.iter().copied().collect()
+    }
+
+    /// Back to zeros, for samples from before a suspend
+    pub fn clear(&mut self) {
+        self.samples.iter_mut().for_each(|sample| *sample = T::default());
+    }
+}
+
+/// Highest value of each series over the last `hold`, after which it falls by `decay` per second like a level meter's peak
+pub struct Peaks {
+    hold: Duration,
+    decay: f64,
+    held: HashMap<String, Vec<(f64, Instant)>>,
+}
+
+impl Peaks {
+    pub fn new(hold: Duration, decay: f64) -> Self {
+        Self { hold, decay, held: HashMap::new() }
+    }
+
+    /// Records the `values` of series `key` at `now` and returns the peak shown for each, never below the value itself.
+    /// A series that changes length, like hotplugged cores, starts over
+    pub fn update(&mut self, key: &str, values: &[f64], now: Instant) -> Vec<f64> {
+        let (hold, decay) = (self.hold, self.decay);
+        // known series are looked up first, their key is only copied once
+        let held = match self.held.get_mut(key) {
+            Some(held) => held,
+            None => self.held.entry(key.to_string()).or_default(),
+        };
+        if held.len() != values.len() {
+            *held = values.iter().map(|&value| (value, now)).collect();
+        }
+        values.iter().zip(held.iter_mut()).map(|(&value, (peak, at))| {
+            let falling = now.saturating_duration_since(*at).saturating_sub(hold).as_secs_f64();
+            let shown = *peak - decay * falling;
+            if value >= shown {
+                (*peak, *at) = (value, now);
+            }
+            shown.max(value)
+        }).collect()
+    }
+
+    /// Drops every held peak, the next update starts from its values
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peaks_hold_then_decay() {
+        let (mut peaks, start) = (Peaks::new(Duration::from_secs(2), 10.0), Instant::now());
+        let at = |secs: f64| start + Duration::from_secs_f64(secs);
+        assert_eq!(peaks.update("cpu", &[90.0, 10.0], at(0.0)), [90.0, 10.0]);
+        assert_eq!(peaks.update("cpu", &[20.0, 30.0], at(1.0)), [90.0, 30.0]);
+        assert_eq!(peaks.update("cpu", &[20.0, 10.0], at(2.0)), [90.0, 30.0]);
+        assert_eq!(peaks.update("cpu", &[20.0, 10.0], at(5.0)), [60.0, 10.0]);
+        // caught up with the value, a new peak is held from there
+        assert_eq!(peaks.update("cpu", &[20.0, 10.0], at(20.0)), [20.0, 10.0]);
+        assert_eq!(peaks.update("cpu", &[0.0, 0.0], at(21.0)), [20.0, 10.0]);
+        assert_eq!(peaks.update("cpu", &[5.0], at(22.0)), [5.0]);
+    }
+}
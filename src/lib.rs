@@ -0,0 +1,22 @@
+//! Hardware monitor: `collect` reads every source into a plain-data `Snapshot`, `render` draws snapshots as text
+
+pub mod alert;
+pub mod args;
+pub mod braille;
+pub mod check;
+pub mod collect;
+pub mod config;
+pub mod csv;
+pub mod error;
+pub mod fmt;
+pub mod glyphs;
+pub mod history;
+pub mod record;
+pub mod remote;
+pub mod render;
+pub mod smooth;
+pub mod statusline;
+pub mod summary;
+pub mod template;
+pub mod term;
+pub mod theme;
@@ -1,509 +1,407 @@
-use sysinfo::{Components, Disks, Motherboard, NetworkData, Networks, RefreshKind, System};
-use nvml_wrapper::{enum_wrappers::device::{Clock, PcieUtilCounter, TemperatureSensor}, Nvml};
-use std::{cmp::Reverse, collections::BTreeMap, fmt::Write, time};
-
-
-#[must_use]
-fn sized_rows(rows: &[String], sizes: &[usize]) -> String {
-    let cols = rows.iter().map(|r| r.split(";").collect::<Vec<_>>()).collect::<Vec<_>>();
-    if cols.is_empty() {
-        return Default::default();
+use std::{io::IsTerminal, process::ExitCode, time};
+
+use hwtop::{alert, check, config, csv, glyphs, record, remote, smooth, summary, statusline, template, term, theme};
+use hwtop::args::{self, Args, ColorMode, Section, StatuslineFormat};
+use hwtop::collect::{Collectors, Snapshot};
+use hwtop::error::HwtopError;
+use hwtop::fmt::Units;
+use hwtop::render::{Style, View};
+use hwtop::theme::Theme;
+
+// TODO: DISK-IO, FIX REFRESH, PROCESS RESOURCES
+fn main() -> ExitCode {
+    // errors the live view can't print over its frames, they wait until the terminal is restored
+    let mut deferred = vec![];
+    let result = run(&mut deferred);
+    for error in &deferred {
+        eprintln!("hwtop: {error}");
     }
-    assert_eq!(cols[0].len(), sizes.len());
-    let mut out = String::new();
-    for row in cols {
-        let len = row.len();
-        for (i, item) in row.into_iter().enumerate() {
-            let size = if i == len - 1 {
-                0
-            } else { sizes[i] };
-            out += &format!("{item:<width$} ", width = size)
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e @ HwtopError::Usage { .. }) => {
+            eprintln!("hwtop: {e}");
+            ExitCode::from(2)
+        }
+        Err(e) => {
+            eprintln!("hwtop: {e}");
+            ExitCode::FAILURE
         }
-        out.push('\n');
     }
-    out
 }
 
-#[must_use]
-fn rows(rows: &[String]) -> String {
-    let cols = rows.iter().map(|r| r.split(";").collect::<Vec<_>>()).collect::<Vec<_>>();
-    if cols.is_empty() {
-        return Default::default();
+/// `hwtop get cpu.temp mem.used`, one raw value per line for scripts, never draws anything
+fn get(args: &mut Args) -> Result<(), HwtopError> {
+    let fields: Vec<template::Field> = args.get.iter().map(|key| template::Field::parse(key)).collect::<Result<_, _>>().map_err(|message| HwtopError::Usage { message })?;
+    // loading NVML takes a while and fails on machines without it, CPU and memory keys don't need it
+    args.no_gpu |= !fields.iter().any(template::Field::needs_gpu);
+    let mut collectors = Collectors::new(args);
+    // CPU usage and rates need two refreshes that far apart
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(collectors.since_refresh()));
+    let snapshot = Snapshot::collect(&mut collectors, args);
+    let style = Style { theme: Theme::plain(), units: Units::Binary, fahrenheit: args.fahrenheit, use_ansi: false, thresholds: args.thresholds.clone(), bar_width: args.bar_width };
+    let mut missing = vec![];
+    for (key, field) in args.get.iter().zip(&fields) {
+        match field.raw(&snapshot, |celsius| style.temp(celsius)) {
+            Some(value) => println!("{value}"),
+            None => {
+                // the line stays so values still line up with the keys
+                println!();
+                missing.push(key.as_str());
+            }
+        }
     }
-    let max_lens: Vec<usize> = (0..cols[0].len())
-        .map(|i| cols.iter().map(|row| row[i].len()).max().unwrap_or(0))
-        .collect();
-    sized_rows(rows, &max_lens)
-}
-
-fn percent_bar(percent: u32) -> &'static str {
-    match percent {
-        0..=12 => "▁",
-        13..=25 => "▂",
-        26..=37 => "▃",
-        38..=50 => "▄",
-        51..=62 => "▅",
-        63..=75 => "▆",
-        76..=87 => "▇",
-        _ => "█",
+    if missing.is_empty() {
+        return Ok(());
     }
+    Err(HwtopError::Sensor { context: missing.join(", "), message: "not available on this machine".to_string() })
 }
 
-fn percent_slider(percent: u32) -> &'static str {
-    match percent {
-        0..=12 => "▏",
-        13..=25 => "▎",
-        26..=37 => "▍",
-        38..=50 => "▌",
-        51..=62 => "▋",
-        63..=75 => "▊",
-        76..=87 => "▉",
-        _ => "█",
+/// Everything up to the terminal being restored, which happens when the screen is dropped on the way out
+fn run(deferred: &mut Vec<HwtopError>) -> Result<(), HwtopError> {
+    // config first so the command line overrides it
+    let config_path = config::path();
+    let mut args = Args::default();
+    if let Some(path) = &config_path {
+        let entries = config::load(path).map_err(|message| HwtopError::Usage { message })?;
+        let warnings = args.apply_config(&entries.unwrap_or_default()).map_err(|e| HwtopError::Usage { message: format!("{}: {e}", path.display()) })?;
+        warnings.iter().for_each(|warning| eprintln!("hwtop: {}: {warning}", path.display()));
     }
-}
-
-// TODO: DISK-IO, CPU FANS, FIX REFRESH, FIX CURSOR, PROCESS RESOURCES
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let refresh_kind = RefreshKind::everything().without_processes();
-    let mut sys = System::new_with_specifics(refresh_kind);
-    let mut components = Components::new_with_refreshed_list();
-    let mut disks = Disks::new_with_refreshed_list();
-    let mut nets = Networks::new_with_refreshed_list();
-    let nvml = Nvml::init()?;
-    let mobo = Motherboard::new().ok_or("No motherboard")?;
-
-    let args: Vec<String> = std::env::args().collect();
-
-    let mut once = args.contains(&"once".to_string());
-    let mut use_ansi = !args.contains(&"plain".to_string());
-    let waybar = args.contains(&"waybar".to_string());
-    if waybar {
-        once = true;
-        use_ansi = false;
+    args.apply_args(std::env::args().skip(1)).map_err(|e| HwtopError::Usage { message: format!("{e}\n\n{}", args::USAGE) })?;
+    if args.help {
+        println!("{}", args::USAGE);
+        return Ok(());
     }
-
-    let (red, green, magenta, cyan, sky, blue, reset, dim) = if use_ansi {
-        ("\x1b[31m",
-        "\x1b[32m",
-        "\x1b[35m",
-        "\x1b[36m",
-        "\x1b[96m",
-        "\x1b[94m",
-        "\x1b[0m",
-        "\x1b[2m")
-    } else {
-        Default::default()
-    };
-
-    #[must_use]
-    fn format_size(bytes: u64) -> String {
-        let bytes_f = bytes as f64;
-        let kib = 1u64 << 10; // 1 KiB
-        let mib = 1u64 << 20; // 1 MiB
-        let gib = 1u64 << 30; // 1 GiB
-        let tib = 1u64 << 40; // 1 TiB
-        
-        if bytes < kib {
-            format!("{bytes}B")
-        } else if bytes < mib {
-            let kib_val = bytes_f / kib as f64;
-            if bytes >= 100 * kib {
-                format!("{kib_val:.0}K")
-            } else {
-                let kib_val = (kib_val * 10.0).round() / 10.0;
-                format!("{kib_val}K")
-            }
-        } else if bytes < gib {
-            let mib_val = bytes_f / mib as f64;
-            if bytes >= 100 * mib {
-                format!("{mib_val:.0}M")
-            } else {
-                let mib_val = (mib_val * 10.0).round() / 10.0;
-                format!("{mib_val}M")
-            }
-        } else if bytes < tib {
-            let gib_val = bytes_f / gib as f64;
-            if bytes >= 100 * gib {
-                format!("{gib_val:.0}G")
-            } else {
-                let gib_val = (gib_val * 10.0).round() / 10.0;
-                format!("{gib_val}G")
-            }
-        } else {
-            let tib_val = bytes_f / tib as f64;
-            if bytes >= 100 * tib {
-                format!("{tib_val:.0}T")
-            } else {
-                let tib_val = (tib_val * 10.0).round() / 10.0;
-                format!("{tib_val}T")
-            }
-        }
+    if args.list_keys {
+        println!("{}", template::list_keys());
+        return Ok(());
     }
-
-    let percent_col = |percent: u32| -> &'static str {
-        match percent {
-            0..=12 => blue,
-            13..=25 => blue,
-            26..=37 => sky,
-            38..=50 => sky,
-            51..=62 => magenta,
-            63..=75 => magenta,
-            76..=87 => red,
-            _ => red,
+    if args.version {
+        println!("hwtop {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    if args.write_config {
+        let path = config_path.ok_or_else(|| HwtopError::io("can't find the config directory")(std::io::Error::other("neither XDG_CONFIG_HOME nor HOME is set")))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(HwtopError::io(format!("can't create '{}'", dir.display())))?;
         }
-    };
-
-    let mem_usage = |used: u64, total: u64| -> String {
-        let used_str = format_size(used);
-        let total_str = format_size(total);
-        let percent = ((used as f64 / total as f64) * 100.0).round() as u32;
-        format!("{}{used_str}{reset}/{}{total_str}{reset}", percent_col(percent), percent_col(percent))
-    };
+        std::fs::write(&path, args.to_config()).map_err(HwtopError::io(format!("can't write '{}'", path.display())))?;
+        println!("wrote {}", path.display());
+        return Ok(());
+    }
 
-    let mhz = |clock: u32, max_clock: u32| -> String {
-        let ratio = clock as f32 / max_clock as f32;
-        let ratio = ratio * ratio; // better colors
-        let percent = (ratio * 100.0).round() as u32;
-        let col = percent_col(percent);
-        format!(" {col}{}{reset}", percent_bar(percent))
-    };
+    if !args.get.is_empty() {
+        return get(&mut args);
+    }
 
-    let mem_bar = |used: u64, total: u64, width: u32| -> String {
-        let ratio = (used as f64 / total as f64).min(1.0);
-        let full_blocks = (ratio * width as f64) as u32;
-        let col = percent_col((ratio * 100.0).round() as u32);
-        let bar = "█".repeat(full_blocks as usize);
-        let usage = mem_usage(used, total);
-        if full_blocks == width {
-            return format!("[{col}{bar}{reset}] {usage}");
-        }
-        let remainder_percent = ((ratio * width as f64).fract() * 100.0).round() as u32;
-        let remainder = percent_slider(remainder_percent);
-        let empty = &" ".repeat((width - full_blocks - 1) as usize);
-        format!("[{col}{bar}{remainder}{reset}{empty}] {usage}")
+    // a replay draws the recorded snapshots, so this machine isn't read at all
+    let mut replay = match &args.replay {
+        Some(path) => Some(record::Replay::open(path)?),
+        None => None,
     };
-
-    let bars = |percentages: &[u32]| -> String {
-        percentages.into_iter().map(|&percent| {
-            let color = percent_col(percent);
-            let bar = percent_bar(percent);
-            format!("{color}{bar}{reset}")
-        }).collect()
+    let mut collectors = replay.is_none().then(|| Collectors::new(&args));
+    // errors that only lost some rows, printed right away unless the live view is up
+    let mut report = |error: HwtopError, tui: bool| if tui { deferred.push(error) } else { eprintln!("hwtop: {error}") };
+
+    let mut once = args.once;
+    // single decision point for colors, everything below only looks at use_ansi
+    let is_tty = std::io::stdout().is_terminal();
+    let mut use_ansi = match args.color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => is_tty && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
     };
-
-    fn max_freq(cpu_id: usize) -> std::io::Result<u32> {
-        let path = format!("/sys/devices/system/cpu/cpu{cpu_id}/cpufreq/cpuinfo_max_freq");
-        let contents = std::fs::read_to_string(path)?;
-        Ok(contents.trim().parse::<u32>().unwrap_or(0) / 1000) // MHz
-    }
-
-    fn net_filter(net: (&String, &NetworkData)) -> bool {
-        let (name, data) = net;
-        !(data.total_received() == 0 && data.total_transmitted() == 0 || name == "lo" || name.starts_with("br-") || name.contains("veth") || name.contains("docker"))
+    let waybar = args.waybar;
+    if waybar || args.json || args.check {
+        once = true;
+        use_ansi = false;
     }
-
-    fn get_comp_temps(components: &mut Components) -> BTreeMap<String, Vec<u32>> {
-        let mut comp_temps: BTreeMap<String, Vec<u32>> = BTreeMap::new();
-        let mut comps = components.iter_mut().collect::<Vec<_>>();
-        comps.sort_by_key(|c| {
-            if c.label().contains("Composite") {
-                0
-            } else {
-                100000 - c.temperature().unwrap_or(0.0) as u32
-            }
-        });
-        for comp in comps {
-            let name = comp.label().replace("Core ", "").replace("coretemp ", "core ");
-            let is_core = name.strip_prefix("core ").map_or(false, |suffix| suffix.chars().all(|c| c.is_ascii_digit()));
-            let temp = comp.temperature().unwrap_or(0.0).round() as u32;
-            if name.contains("core Package") {
-                comp_temps.insert("CPU".to_string(), vec![temp]);
-                continue;
-            }
-            if is_core {
-                comp_temps.entry("Core".to_string())
-                    .and_modify(|temps: &mut Vec<u32>| temps.push(temp))
-                    .or_insert_with(|| vec![temp]);
-                continue;
-            }
-            let name = if let Some(rest) = name.strip_prefix("nvme Sensor ") {
-                rest.chars().skip_while(|c| c.is_ascii_digit() || c.is_whitespace()).collect()
-            } else if let Some(rest) = name.strip_prefix("nvme Composite ") {
-                rest.to_string()
-            } else {
-                name
-            };
-            let mut name = name.replace("SSD ", "").replace(" temp1", "").replace("acpitz", "Motherboard").replace("spd5118", "RAM");
-            if name.contains("wifi") {
-                name = "Wi-Fi".to_string();
-            }
-            comp_temps
-                .entry(name.clone())
-                .and_modify(|temps: &mut Vec<u32>| temps.push(temp))
-                .or_insert_with(|| vec![temp]);
-        }
-        comp_temps
+    if args.csv.as_deref() == Some("-") || args.statusline || args.format.is_some() || args.json_stream {
+        use_ansi = false;
     }
-    
-    if args.contains(&"info".to_string()) {
-        // CPU INFO
-        let cpus = sys.cpus();
-        let brand = cpus[0].brand().replace("(R)", "").replace("(TM)", "").replace("Intel ", "").replace("Core ", "");
-        println!("{sky}CPU{reset} {brand} {blue}x{} Cores{reset}", cpus.len());
-
-        // GPU INFO
-        let num_gpus = nvml.device_count()?;
-        for i in 0..num_gpus {
-            let gpu = nvml.device_by_index(i)?;
-            let gpu_name = gpu.name()?.replace("NVIDIA ", "").replace("GeForce ", "");
-            println!("{magenta}GPU{reset} {gpu_name}", );
-            let mem_max_clk = gpu.max_clock_info(Clock::Memory)?;
-            let tab = format!("{dim}{magenta}├─{reset}");
-            println!("{tab} VRAM {green}{}GB{reset} {blue}{mem_max_clk}MHz{reset}", gpu.memory_info()?.total as f64 / (1u64 << 30u64) as f64);
-            let gfx_max_clk = gpu.max_clock_info(Clock::Graphics)?; 
-            let sm_max_clk = gpu.max_clock_info(Clock::SM)?;
-            let vid_max_clk = gpu.max_clock_info(Clock::Video)?;
-            println!("{tab} Clock {dim}Gfx{reset} {blue}{gfx_max_clk}MHz{reset}  {dim}SM{reset} {blue}{sm_max_clk}MHz{reset}  {dim}Vid{reset} {blue}{vid_max_clk}MHz{reset}");
-            println!("{tab} Cores {blue}{}{reset}", gpu.num_cores()?);
-            println!("{tab} Consumed {blue}{}MJ{reset}", (gpu.total_energy_consumption()? as f32 / 1e9 * 100.0).round() / 100.0);
-            println!("{tab} Driver {blue}{}{reset}", nvml.sys_driver_version()?);
-            println!("{tab} Perf {blue}{:?}{reset} {dim}(0-15, 0 = max){reset}", gpu.performance_state()?.as_c());
-            println!("{dim}{magenta}└─{reset} CUDA {blue}{}{reset}", nvml.sys_cuda_driver_version()?);
-        }
-
-        // MOTHERBOARD INFO
-        println!("{red}MOBO{reset} {}", mobo.name().unwrap_or_default());
-        
-        // COMPONENT INFO
-        let mut comp_names: Vec<String> = vec![];
-        for comp in components.iter() {
-            let name = comp.label().replace("Core ", "").replace("coretemp ", "core ");
-            let is_core = name.strip_prefix("core ").map_or(false, |suffix| suffix.chars().all(|c| c.is_ascii_digit()));
-            if name.contains("core Package") | is_core {
-                continue;
-            }
-            let name = if let Some(rest) = name.strip_prefix("nvme Sensor ") {
-                rest.chars().skip_while(|c| c.is_ascii_digit() || c.is_whitespace()).collect()
-            } else if let Some(rest) = name.strip_prefix("nvme Composite ") {
-                rest.to_string()
-            } else {
-                name
-            };
-            let mut name = name.replace("SSD ", "").replace(" temp1", "").replace("acpitz", "Motherboard").replace("spd5118", "RAM");
-            if name.contains("wifi") {
-                name = "Wi-Fi".to_string();
-            }
-            if name == "Motherboard" {
-                continue;
-            }
-            if !comp_names.iter().any(|existing| {
-                existing.starts_with(&name) || name.starts_with(existing)
-            }) {
-                comp_names.push(format!("{name}"));
-            }
-        }
-        comp_names.sort();
-        for (i, name) in comp_names.iter().enumerate() {
-            let mut tab = format!("{dim}{red}├─{reset}");
-            if i == comp_names.len() - 1 {
-                tab = format!("{dim}{red}└─{reset}");
-            }
-            println!("{tab} {name}");
-        }
-
-        println!("{cyan}Networks{reset} ");
-        let mut net_iter = nets.iter().filter(|&net| net_filter(net)).peekable();
-        while let Some((name, data)) = net_iter.next() {
-            let ips = data.ip_networks().iter().map(|ip| format!("{}{}{reset}]", if ip.prefix == 24 { format!("ipv4[{dim}") } else { format!("ipv6[{dim}") }, ip.addr)).collect::<Vec<String>>().join(", ");
-            let mut tab = format!("{dim}{cyan}├─{reset}");
-            if net_iter.peek().is_none() {
-                tab = format!("{dim}{cyan}└─{reset}");
-            }
-            println!("{tab} {blue}{name}{reset} {ips}{reset} mac[{dim}{}{reset}]", &data.mac_address());
-        }
+    // old Windows consoles print escape sequences as text, plain output is all they get
+    use_ansi = use_ansi && term::enable_ansi();
+
+    glyphs::set_ascii(args.ascii.unwrap_or_else(glyphs::locale_is_ascii));
+    // gradient bars need 24-bit color, mono keeps its bars uncolored unless they're asked for
+    let gradient = args.gradient.unwrap_or(theme::truecolor() && args.theme != theme::Preset::Mono);
+    let units = if args.si { Units::Si } else { Units::Binary };
+    // one for this machine and one for every --remote
+    let new_style = || {
+        let theme = if use_ansi { Theme::new(args.theme, &args.colors, theme::truecolor(), gradient) } else { Theme::plain() };
+        Style { theme, units, fahrenheit: args.fahrenheit, use_ansi, thresholds: args.thresholds.clone(), bar_width: args.bar_width }
+    };
+    let style = new_style();
 
+    if let Some(collectors) = collectors.as_mut().filter(|_| args.info) {
+        collectors.take_errors().into_iter().for_each(|error| report(error, false));
+        print!("{}", style.info(collectors, &args)?);
         return Ok(());
     }
-    if !once {
-        print!("\x1b[?1049h");
+    // alt screen and cursor escapes are only used for the live colored view
+    let tui = !once && use_ansi && is_tty;
+    term::install_signal_handlers();
+    // the first frame is drawn right away, so it waits out sysinfo's minimum here for valid CPU usage,
+    // before the alt screen so the terminal isn't blank meanwhile
+    if let Some(collectors) = &collectors {
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(collectors.since_refresh()));
     }
-    let mut start = time::Instant::now();
+    let screen = tui.then(term::Screen::enter);
+    // sysinfo needs at least MINIMUM_CPU_UPDATE_INTERVAL between refreshes for valid CPU usage
+    let mut interval = if once { sysinfo::MINIMUM_CPU_UPDATE_INTERVAL } else { args.interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL) };
+    let mut csv = match &args.csv {
+        Some(path) => Some(csv::CsvLog::open(path)?),
+        None => None,
+    };
+    let mut recorder = match &args.record {
+        Some(path) => Some(record::Recorder::open(path)?),
+        None => None,
+    };
+    let mut frames = term::Frames::default();
+    let mut view = View::new(style, &args, interval, once);
+    // every --remote has a view and alerts of its own, and keeps its last frame until the next snapshot comes in
+    let mut remotes: Vec<_> = args.remotes.iter()
+        .map(|target| (remote::Remote::connect(target, args.remote_args()), View::new(new_style(), &args, interval, once), alert::Alerts::new(args.alerts.clone()), String::new()))
+        .collect();
+    let mut summary = args.summary.then(summary::Summary::new);
+    // 1 draws the measured values as they are, not averaged with themselves
+    let mut smoother = (args.smooth < 1.0).then(|| smooth::Smoother::new(args.smooth));
+    let mut alerts = alert::Alerts::new(args.alerts.clone());
+    // live view key state
+    // the key hint would be a third line under --compact
+    let (mut paused, mut show_keys, mut show_help) = (false, !args.compact, false);
+    let mut last_snapshot: Option<Snapshot> = None;
+    let mut first = true;
+    // snapshots collected, for --count and the footer's spinner
+    let mut collected = 0;
+    // milliseconds the last snapshot took to collect, for the footer, `None` in a replay
+    let mut collect_ms = None;
+    // the frame is drawn over the last one, its buffer is only allocated once
+    let mut out = String::new();
     loop {
-        // refresh screen
-        if !once {
-            print!("\x1b[H\x1b[2J\x1b[?25l");
+        if args.count > 0 && collected == args.count {
+            break;
         }
-
         // REFRESH
-        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-        sys.refresh_specifics(RefreshKind::everything().without_processes());
-        disks.refresh(true);
-        nets.refresh(true);
-        components.refresh(true);
-        
-        let mut out = String::new();
-
-        let gpu = nvml.device_by_index(0)?;
-
-        // GPU FANS
-        let num_fans = gpu.num_fans().unwrap_or(1);
-        let mut fan_str = String::new();
-        for i in 0..num_fans {
-            let fan_percent = gpu.fan_speed(i).unwrap_or(0);
-            let fan_rpm = gpu.fan_speed_rpm(i).unwrap_or(0);
-            fan_str += &format!("{}{fan_percent}%{reset} {dim}{fan_rpm:>4}rpm{reset}", percent_col(fan_percent));
-            if i != num_fans - 1 {
-                fan_str += ", ";
+        if !std::mem::take(&mut first) {
+            term::sleep(interval);
+        }
+        if term::interrupted() {
+            break;
+        }
+        // KEYS
+        let keys = term::keys();
+        let mut quit = false;
+        // any key closes the help, without doing what it normally does
+        if show_help && !keys.is_empty() {
+            show_help = false;
+        } else {
+            for &key in &keys {
+                match key {
+                    term::Key::Char('q') | term::Key::Esc => quit = true,
+                    term::Key::Char('p') => paused = !paused,
+                    term::Key::Char('e') => args.extra = !args.extra,
+                    term::Key::Char('+') => interval = (interval / 2).max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL),
+                    term::Key::Char('-') => interval = (interval * 2).min(time::Duration::from_secs(10)),
+                    term::Key::Char('g') => view.next_gpu(),
+                    term::Key::Char('h') => show_keys = !show_keys,
+                    term::Key::Char(c @ '1'..='6') => {
+                        // a partly hidden group is hidden the rest of the way first
+                        let sections = Section::KEYS[c as usize - '1' as usize];
+                        let all_hidden = sections.iter().all(|section| args.hide.contains(section));
+                        args.hide.retain(|hidden| !sections.contains(hidden));
+                        if !all_hidden {
+                            args.hide.extend(sections);
+                        }
+                        // remembered for next time when there's a config file, a failed write only loses that
+                        if let Some(path) = config_path.as_deref().filter(|path| path.exists()) {
+                            let hidden: Vec<&str> = args.hide.iter().map(|section| section.name()).collect();
+                            let _ = config::set_key(path, "hide", &format!("hide = {}", config::quote(&hidden.join(","))));
+                        }
+                    }
+                    term::Key::Char('?') => show_help = true,
+                    _ => {}
+                }
             }
         }
-
-        // COMPONENT TEMPS
-        let mut comp_temps = get_comp_temps(&mut components);
-        let cpu_temp = comp_temps.remove("CPU").map(|v| v[0]).unwrap_or(0);
-        let core_temps = comp_temps.remove("Core").unwrap_or_default();
-        
-        // SYSTEM UTILIZATION
-        let cpu_usage = sys.global_cpu_usage().round() as u32;
-        let gpu_utilization = gpu.utilization_rates()?;
-        let gpu_usage = gpu_utilization.gpu;
-        let gpu_temp = gpu.temperature(TemperatureSensor::Gpu)?;
-        let gpu_mem_percent = gpu_utilization.memory;
-        let gpu_power_usage = gpu.power_usage()? / 1000;
-        let gpu_max_power = gpu.power_management_limit()? / 1000;
-        let gpu_power_usage_percent = (gpu_power_usage as f32 / gpu_max_power as f32 * 100.0).round() as u32;
-        let cpu_usage_str = format!(" {green}CPU{reset}{}{cpu_usage:>3}%{reset}{}{cpu_temp:>4}°C{reset}", 
-            percent_col(cpu_usage), percent_col(cpu_temp));
-        let gpu_usage_str = format!(" {magenta}GPU{reset}{}{gpu_usage:>3}%{reset}{}{gpu_temp:>4}°C {reset}{}{gpu_power_usage:>3}W{reset}{dim}/{reset}{}{gpu_max_power}W{reset}", 
-            percent_col(gpu_usage), percent_col(gpu_temp), percent_col(gpu_power_usage_percent), percent_col(gpu_power_usage_percent));
-        write!(out, "{cpu_usage_str}\n{gpu_usage_str}\n")?;
-
-        // MEMORY USAGES
-        let ram = mem_bar(sys.used_memory(), sys.total_memory(), 14);
-        let swap = mem_usage(sys.used_swap(), sys.total_swap());
-        write!(out, " {red}RAM{reset} {ram}  {swap}\n")?;
-
-        let gpu_mem_info = gpu.memory_info()?;
-        let vram = mem_bar(gpu_mem_info.used, gpu_mem_info.total, 14);
-        write!(out, "{red}VRAM {reset}{vram}     {}{gpu_mem_percent}%{reset}\n", percent_col(gpu_mem_percent))?;
-
-        // CORE USAGES
-        let cpus = sys.cpus();
-        let cores: Vec<u32> = cpus.iter().map(|cpu| cpu.cpu_usage() as u32).collect();
-        let max_core = cores.iter().copied().max().unwrap_or(0);
-        
-        let max_core_freqs: Vec<u32> = (0..cpus.len()).map(|i| max_freq(i).unwrap_or(1)).collect();
-        let core_freqs: Vec<u32> = cpus.iter().zip(max_core_freqs.iter()).map(|(cpu, max_freq)| (cpu.frequency() as f32 / *max_freq as f32 * 100.0).round().min(100.0) as u32).collect();
-        
-        // CORE FREQS
-        let max_core_freq = cpus.iter().map(|cpu| cpu.frequency()).max().unwrap_or(0);
-        let max_core_freq_pct = core_freqs.iter().copied().max().unwrap_or(0);
-        let max_core_freq_str = format!("{max_core_freq}");
-        let mut rating = String::new();
-        if !waybar {
-            let min_core_freq_rating = max_core_freqs.iter().copied().min().unwrap_or(0);
-            let max_core_freq_rating = max_core_freqs.iter().copied().max().unwrap_or(0);
-            let single_core_freq_rating = min_core_freq_rating == max_core_freq_rating;
-            rating = format!("{min_core_freq_rating}{}MHz", if single_core_freq_rating { String::new() } else  { format!("-{max_core_freq_rating}") });
+        if quit {
+            break;
         }
-
-        // CORE TEMPS
-        let max_core_temp = core_temps.iter().copied().max().unwrap_or(0);
-
-        let off = if use_ansi { 5 } else { 0 };
-        write!(out, "{blue}CORE{reset} {}{:>w$} {max_core}%{reset}\n", &bars(&cores), percent_col(max_core), w = off)?;
-        write!(out, "{blue}FREQ{reset} {}{:>w$} {max_core_freq_str:<4}MHz {reset}{dim}{rating}{reset}\n", bars(&core_freqs), percent_col(max_core_freq_pct), w = off)?;
-        write!(out, "{blue}TEMP{reset} {}{:>w$} {max_core_temp}C{reset}\n", bars(&core_temps), percent_col(max_core_temp), w = off + cores.len() - core_temps.len())?;
-
-        // GPU CLOCK
-        let gfx_clk = gpu.clock_info(Clock::Graphics).unwrap_or(0);
-        let gfx_max_clk = gpu.max_clock_info(Clock::Graphics).unwrap_or(0);
-        let mem_clk = gpu.clock_info(Clock::Memory).unwrap_or(0);
-        let mem_max_clk = gpu.max_clock_info(Clock::Memory).unwrap_or(0);
-        let sm_clk = gpu.clock_info(Clock::SM).unwrap_or(0);
-        let sm_max_clk = gpu.max_clock_info(Clock::SM).unwrap_or(0);
-        let vid_clk = gpu.clock_info(Clock::Video).unwrap_or(0);
-        let vid_max_clk = gpu.max_clock_info(Clock::Video).unwrap_or(0);
-        write!(out, "{blue}CLCK{reset} {dim}GFX{reset}{}  {dim}MEM{reset}{}  {dim}SM{reset}{}  {dim}VID{reset}{}\n", 
-            mhz(gfx_clk, gfx_max_clk), mhz(mem_clk, mem_max_clk), mhz(sm_clk, sm_max_clk), mhz(vid_clk, vid_max_clk))?;
-
-        // GPU FANS
-        write!(out, "{sky}FANS{reset} {fan_str}\n")?;
-
-        // PCIE
-        let rx = gpu.pcie_throughput(PcieUtilCounter::Receive)? * 50; // KB/s
-        let tx = gpu.pcie_throughput(PcieUtilCounter::Send)? * 50; // KB/s
-        let pcie_gen = gpu.max_pcie_link_gen()?;
-        let pcie_width = gpu.max_pcie_link_width()?;
-        // PCIe throughput per lane in KB/s (accounting for encoding overhead)
-        let pcie_throughput_per_lane = match pcie_gen {
-            1 => 250,   // PCIe 1.0: 2.5 GT/s * 0.8 (8b/10b encoding) / 8 bits = 250 MB/s
-            2 => 500,   // PCIe 2.0: 5.0 GT/s * 0.8 / 8 = 500 MB/s  
-            3 => 985,   // PCIe 3.0: 8.0 GT/s * 0.9846 (128b/130b encoding) / 8 = 985 MB/s
-            4 => 1969,  // PCIe 4.0: 16.0 GT/s * 0.9846 / 8 = 1969 MB/s
-            5 => 3938,  // PCIe 5.0: 32.0 GT/s * 0.9846 / 8 = 3938 MB/s
-            _ => 1969,  // Default to PCIe 4.0 if unknown
-        } * 1000;
-        let max_pcie_throughtput = pcie_throughput_per_lane as u64 * pcie_width as u64;
-        let max_pcie_throughtput_str = format_size(max_pcie_throughtput * 1000);
-        let rx_col = percent_col((rx as f32 / max_pcie_throughtput as f32 * 100.0).round() as u32);
-        let tx_col = percent_col((tx as f32 / max_pcie_throughtput as f32 * 100.0).round() as u32);
-        let rx_str = format_size(rx as u64 * 1000);
-        let tx_str = format_size(tx as u64 * 1000);
-        write!(out, "{sky}PCIE{reset} {green}▼{reset}{rx_col}{rx_str:>6}{reset}  {magenta}▲{reset}{tx_col}{tx_str:>6}{reset}   {dim}{max_pcie_throughtput_str}/s{reset}\n", )?;
-
-        let now = time::Instant::now();
-        let delta = (now - start).as_secs_f32();
-        start = now;
-
-        // NETWORK
-        let net_iter = nets.iter().filter(|&net| net_filter(net)).collect::<Vec<_>>();
-        if let Some((name, data)) = net_iter.iter().max_by_key(|(_, data)| Reverse(data.total_transmitted() + data.total_received())) {
-            let rx = format_size((data.received() as f32 / delta) as u64);
-            let tx = format_size((data.transmitted() as f32 / delta) as u64);
-            let prx = (data.packets_received() as f32 / delta) as u32;
-            let ptx = (data.packets_transmitted() as f32 / delta) as u32;
-            write!(out, "{sky}NETW{reset} {green}▼{reset}{blue}{rx:>6}{reset}  {magenta}▲{reset}{blue}{tx:>6}{reset} {green}{prx:>4}{reset}/{magenta}{ptx:<4}{reset} {dim}{name}{reset}\n")?;
+        let resized = term::take_resized();
+        if resized {
+            frames.clear();
         }
-
-        // DISKS
-        let mut disk_infos = vec![];
-        for disk in disks.iter_mut() {
-            let total = disk.total_space();
-            const MIN_DISK_SIZE: u64 = 8 * (1 << 30); // 8 GiB
-            if total <= MIN_DISK_SIZE { continue; }
-            let free = disk.available_space();
-            let usage = disk.usage();
-            let name = disk.name().to_str().and_then(|d| d.strip_prefix("/dev/")).unwrap_or_default();
-            let read_bytes = (usage.read_bytes as f32 / delta) as u64;
-            let written_bytes = (usage.written_bytes as f32 / delta) as u64;
-            let rw = format!("{green}{:>4}{reset}/{magenta}{:<4}{reset}", format_size(read_bytes), format_size(written_bytes));
-            let total_rw = format!("{green}{}{reset}/{magenta}{}{reset}", format_size(usage.total_read_bytes), format_size(usage.total_written_bytes));
-            let usage = mem_usage(total - free, total);
-            disk_infos.push(format!("{sky}{name}{reset};{usage};{rw};Tot {total_rw}"))  
+        // a paused view keeps its last snapshot, it's only drawn again after a key press or resize
+        if paused && keys.is_empty() && !resized && last_snapshot.is_some() {
+            continue;
         }
-        write!(out, "{}", rows(&disk_infos))?;
+        // a snapshot drawn again while paused doesn't move the histories
+        let (snapshot, fresh) = match last_snapshot.take() {
+            Some(snapshot) if paused => (snapshot, false),
+            previous => {
+                let (snapshot, delta) = match (&mut collectors, &mut replay) {
+                    (Some(collectors), _) => {
+                        // a resize or key press redraws right away, but not faster than sysinfo can measure CPU usage
+                        if resized || !keys.is_empty() {
+                            std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(collectors.since_refresh()));
+                        }
+                        let started = time::Instant::now();
+                        let snapshot = Snapshot::collect(collectors, &args);
+                        collect_ms = Some(started.elapsed().as_secs_f32() * 1000.0);
+                        for error in collectors.take_errors() {
+                            if error.is_fatal() {
+                                return Err(error);
+                            }
+                            report(error, tui);
+                        }
+                        (snapshot, collectors.delta)
+                    }
+                    (None, Some(replay)) => match replay.advance()? {
+                        Some(recorded) => {
+                            // waits out the recorded gap to the next snapshot, key presses still redraw right away
+                            interval = replay.until_next().map_or(interval, |gap| gap.div_f64(args.speed));
+                            (recorded.snapshot, recorded.delta)
+                        }
+                        // the live view stays on the last snapshot, paused, until it's quit
+                        None if tui && previous.is_some() => {
+                            paused = true;
+                            last_snapshot = previous;
+                            continue;
+                        }
+                        None => break,
+                    },
+                    (None, None) => unreachable!("either collectors or a replay"),
+                };
+                collected += 1;
+                if let Some(recorder) = &mut recorder {
+                    recorder.write(&snapshot, delta)?;
+                }
+                if let Some(summary) = &mut summary {
+                    summary.record(&snapshot, delta);
+                }
+                // only the live view rings, in JSON, CSV or a status line the BEL would be part of the output
+                if alerts.update(&snapshot) && args.bell && tui {
+                    print!("\x07");
+                    std::io::Write::flush(&mut std::io::stdout()).map_err(HwtopError::terminal("can't ring the bell"))?;
+                }
+                if args.check {
+                    let results = check::run(&snapshot, &args);
+                    for (outcome, text) in &results {
+                        let status = match outcome {
+                            check::Outcome::Pass => "ok   ",
+                            check::Outcome::Fail => "FAIL ",
+                            check::Outcome::Error => "ERROR",
+                        };
+                        println!("{status} {text}");
+                    }
+                    std::process::exit(check::exit_code(&results));
+                }
+                if args.json {
+                    println!("{}", serde_json::to_string(&snapshot).map_err(|e| HwtopError::terminal("can't write the JSON snapshot")(e.into()))?);
+                    break;
+                }
+                if args.json_stream {
+                    let line = serde_json::to_string(&snapshot).map_err(|e| HwtopError::terminal("can't write the JSON snapshot")(e.into()))?;
+                    // the reader going away, like a dropped ssh connection, ends the stream
+                    let mut stdout = std::io::stdout();
+                    std::io::Write::write_all(&mut stdout, (line + "\n").as_bytes()).and_then(|_| std::io::Write::flush(&mut stdout)).map_err(HwtopError::terminal("can't write to stdout"))?;
+                    if once {
+                        break;
+                    }
+                    continue;
+                }
+                if let Some(csv) = &mut csv {
+                    csv.write(&snapshot)?;
+                    // CSV on stdout replaces the display
+                    if args.csv.as_deref() == Some("-") {
+                        if once {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+                // everything above got the measured values, only the display is smoothed
+                let mut snapshot = snapshot;
+                if let Some(smoother) = &mut smoother {
+                    smoother.apply(&mut snapshot, interval, time::Instant::now());
+                }
+                (snapshot, true)
+            }
+        };
 
-        // EXTRA COMPONENTS
-        if args.contains(&"extra".to_string()) {
-            let mut comp_temps = comp_temps.into_iter().collect::<Vec<_>>();
-            comp_temps.sort_unstable();
-            let comp_temps: Vec<String> = comp_temps.into_iter().map(|(name, temps)| {
-                let mut comp_temp = format!("{blue}{name}{reset} ;");
-                let mut first = true;
-                for temp in temps {
-                    if !first {
-                        comp_temp += ", ";
+        if let Some(template) = &args.format {
+            println!("{}", template.render(&snapshot, units, |celsius| view.style.temp(celsius)));
+            std::io::Write::flush(&mut std::io::stdout()).map_err(HwtopError::terminal("can't write to stdout"))?;
+            if once {
+                break;
+            }
+            continue;
+        }
+        // rows shrink to fit the terminal, piped output without --width is left as is
+        let width = args.width.or_else(term::width);
+        if args.compact {
+            out = view.compact(&snapshot, width);
+        } else {
+            view.frame(&mut out, &snapshot, fresh, &args, &alerts, width)?;
+        }
+        // remote machines go under this one, each under its hostname
+        if !remotes.is_empty() {
+            let now = time::Instant::now();
+            out.insert_str(0, &view.style.host_header(snapshot.host.hostname.as_deref().unwrap_or("localhost"), None, width));
+            for (remote, remote_view, remote_alerts, block) in &mut remotes {
+                if remote.poll() && let Some(remote_snapshot) = &remote.snapshot {
+                    remote_alerts.update(remote_snapshot);
+                    if args.compact {
+                        *block = remote_view.compact(remote_snapshot, width);
                     } else {
-                        first = false;
+                        remote_view.frame(block, remote_snapshot, true, &args, remote_alerts, width)?;
                     }
-                    comp_temp += &format!("{}{temp}°C{reset}", percent_col(temp));
                 }
-                comp_temp
-            }).collect();
-            write!(out, "{}", rows(&comp_temps))?;
+                // a lost connection leaves the last frame up, dimmed
+                let note = remote.note(now);
+                out += &view.style.host_header(remote.name(), note.as_deref(), width);
+                if note.is_some() {
+                    out += &view.style.dimmed(block);
+                } else {
+                    out += block;
+                }
+            }
         }
-        if !once {
-            print!("{out}\x1b[?25h");
+        // status bars read one line per refresh, the full view goes into the waybar tooltip
+        if args.statusline {
+            let text = statusline::line(&snapshot, units, |celsius| view.style.temp(celsius));
+            match args.statusline_format {
+                StatuslineFormat::Text => println!("{text}"),
+                StatuslineFormat::Json => {
+                    let class = if !alerts.firing().is_empty() { "critical" } else if alerts.near(&snapshot) { "warning" } else { "normal" };
+                    println!("{}", serde_json::json!({ "text": text, "tooltip": out.trim_end(), "class": class }));
+                }
+            }
+            std::io::Write::flush(&mut std::io::stdout()).map_err(HwtopError::terminal("can't write to stdout"))?;
+            if once {
+                break;
+            }
+            continue;
+        }
+        // HELP
+        if show_help {
+            out = view.style.help(&snapshot, collectors.as_ref().map(|collectors| &collectors.sys), &args, interval, width);
+        }
+        // the title is only ever set by the live view, which puts it back on exit
+        if tui && args.title {
+            frames.set_title(&statusline::title(&snapshot, |celsius| view.style.temp(celsius))).map_err(HwtopError::terminal("can't set the title"))?;
+        }
+        last_snapshot = Some(snapshot);
+        if tui {
+            let hint = if show_help { String::new() } else { view.style.key_hint(show_keys, paused, interval, width) };
+            // --compact keeps to its two lines
+            let footer = if show_help || !args.footer || args.compact { String::new() } else { view.style.footer(paused, interval, collect_ms, collected, width) };
+            out += &hint;
+            out += &footer;
+            frames.draw(&out).map_err(HwtopError::terminal("can't draw the frame"))?;
+        } else if !once {
+            // frames scroll by instead of redrawing, a blank line between them tells them apart in logs
+            println!("{out}");
+            std::io::Write::flush(&mut std::io::stdout()).map_err(HwtopError::terminal("can't write to stdout"))?;
         } else {
             if waybar {
                 print!("{{\"tooltip\":\"{}\"}}", out.replace("\n", "\\n"));
@@ -513,8 +411,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             break;
         }
     }
-    if !once {
-        print!("\x1b[?1049l");
+    // the summary goes to the normal screen so it stays in the scrollback
+    drop(screen);
+    if let Some(summary) = summary {
+        print!("{}", view.style.summary(&summary, args.kwh_price));
     }
     Ok(())
-}
\ No newline at end of file
+}
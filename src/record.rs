@@ -0,0 +1,190 @@
+use std::{fs::{File, OpenOptions}, io::{BufRead, BufReader, Lines, Write}, time::{Duration, SystemTime}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{collect::Snapshot, error::HwtopError};
+
+/// Bumped whenever `Snapshot` changes in a way older recordings can't be read as
+pub const VERSION: u32 = 1;
+
+/// First line of a recording
+#[derive(Serialize, Deserialize)]
+struct Header {
+    hwtop_recording: u32,
+}
+
+#[derive(Serialize)]
+struct FrameOut<'a> {
+    /// seconds since the Unix epoch
+    time: f64,
+    delta: f32,
+    snapshot: &'a Snapshot,
+}
+
+#[derive(Deserialize)]
+struct FrameIn {
+    time: f64,
+    delta: f32,
+    snapshot: Snapshot,
+}
+
+/// One snapshot of a recording
+pub struct Recorded {
+    /// wall time since the previous snapshot, zero for the first
+    pub gap: Duration,
+    /// seconds the snapshot's rates were measured over
+    pub delta: f32,
+    pub snapshot: Snapshot,
+}
+
+/// Appends one JSON line per snapshot for `hwtop record`
+pub struct Recorder {
+    file: File,
+    path: String,
+}
+
+impl Recorder {
+    /// Opens `path` for appending, a new file gets the version header and an existing one has to be a recording of this version
+    pub fn open(path: &str) -> Result<Self, HwtopError> {
+        let mut file = OpenOptions::new().create(true).append(true).read(true).open(path).map_err(HwtopError::io(format!("can't open '{path}'")))?;
+        let empty = file.metadata().map_err(HwtopError::io(format!("can't open '{path}'")))?.len() == 0;
+        if empty {
+            let header = serde_json::to_string(&Header { hwtop_recording: VERSION }).map_err(|e| HwtopError::io(format!("can't write '{path}'"))(e.into()))?;
+            writeln!(file, "{header}").map_err(HwtopError::io(format!("can't write '{path}'")))?;
+        } else {
+            let mut first = String::new();
+            BufReader::new(&file).read_line(&mut first).map_err(HwtopError::io(format!("can't read '{path}'")))?;
+            check_header(&first).map_err(|message| HwtopError::Parse { context: format!("can't append to '{path}'"), message })?;
+        }
+        Ok(Self { file, path: path.to_string() })
+    }
+
+    /// Each line is written at once, so a recording cut off by a crash or power loss keeps every complete tick
+    pub fn write(&mut self, snapshot: &Snapshot, delta: f32) -> Result<(), HwtopError> {
+        let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0.0, |time| time.as_secs_f64());
+        let line = serde_json::to_string(&FrameOut { time, delta, snapshot }).map_err(|e| HwtopError::io(format!("can't write '{}'", self.path))(e.into()))? + "\n";
+        self.file.write_all(line.as_bytes()).map_err(HwtopError::io(format!("can't write '{}'", self.path)))
+    }
+}
+
+/// Reads a recording back for `hwtop replay`, one snapshot ahead so the wait before the next one is known
+pub struct Replay {
+    lines: Lines<BufReader<File>>,
+    path: String,
+    /// line number of `next`, for errors
+    line: usize,
+    last_time: Option<f64>,
+    next: Option<Recorded>,
+}
+
+impl Replay {
+    /// Fails on anything that isn't a recording of this version or has no snapshots
+    pub fn open(path: &str) -> Result<Self, HwtopError> {
+        let file = File::open(path).map_err(HwtopError::io(format!("can't open '{path}'")))?;
+        let mut lines = BufReader::new(file).lines();
+        let header = lines.next().unwrap_or(Ok(String::new())).map_err(HwtopError::io(format!("can't read '{path}'")))?;
+        check_header(&header).map_err(|message| HwtopError::Parse { context: format!("can't replay '{path}'"), message })?;
+        let mut replay = Self { lines, path: path.to_string(), line: 1, last_time: None, next: None };
+        replay.next = replay.read()?;
+        if replay.next.is_none() {
+            return Err(HwtopError::Parse { context: format!("can't replay '{path}'"), message: "there are no snapshots in it".to_string() });
+        }
+        Ok(replay)
+    }
+
+    /// Wall time between the last snapshot taken and the next one, `None` at the end
+    #[must_use]
+    pub fn until_next(&self) -> Option<Duration> {
+        self.next.as_ref().map(|next| next.gap)
+    }
+
+    /// The next snapshot, `None` at the end of the recording
+    pub fn advance(&mut self) -> Result<Option<Recorded>, HwtopError> {
+        let next = self.next.take();
+        self.next = self.read()?;
+        Ok(next)
+    }
+
+    fn read(&mut self) -> Result<Option<Recorded>, HwtopError> {
+        loop {
+            let Some(line) = self.lines.next() else {
+                return Ok(None);
+            };
+            self.line += 1;
+            let line = line.map_err(HwtopError::io(format!("can't read '{}'", self.path)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // a recording cut off mid-line ends at its last complete snapshot
+            let frame: FrameIn = match serde_json::from_str(&line) {
+                Ok(frame) => frame,
+                Err(e) if e.is_eof() => return Ok(None),
+                Err(e) => return Err(HwtopError::Parse { context: format!("{}:{}", self.path, self.line), message: e.to_string() }),
+            };
+            let gap = self.last_time.map_or(0.0, |last| (frame.time - last).max(0.0));
+            self.last_time = Some(frame.time);
+            return Ok(Some(Recorded { gap: Duration::from_secs_f64(gap), delta: frame.delta, snapshot: frame.snapshot }));
+        }
+    }
+}
+
+fn check_header(line: &str) -> Result<(), String> {
+    match serde_json::from_str::<Header>(line.trim()) {
+        Ok(Header { hwtop_recording: VERSION }) => Ok(()),
+        Ok(Header { hwtop_recording: version }) => Err(format!("it's a version {version} recording, this hwtop reads version {VERSION}")),
+        Err(_) => Err("it isn't an hwtop recording".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_versions() {
+        assert_eq!(check_header(&format!("{{\"hwtop_recording\":{VERSION}}}\n")), Ok(()));
+        assert_eq!(check_header("{\"hwtop_recording\":99}"), Err(format!("it's a version 99 recording, this hwtop reads version {VERSION}")));
+        assert_eq!(check_header("timestamp,cpu,cpu_temp"), Err("it isn't an hwtop recording".to_string()));
+        assert_eq!(check_header(""), Err("it isn't an hwtop recording".to_string()));
+    }
+
+    fn recording(name: &str) -> String {
+        std::env::temp_dir().join(format!("hwtop-{}-{name}.jsonl", std::process::id())).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn snapshots_replay_as_written() {
+        let path = recording("round-trip");
+        let mut recorder = Recorder::open(&path).unwrap();
+        recorder.write(&Snapshot { uptime_s: 60, ..Default::default() }, 0.5).unwrap();
+        recorder.write(&Snapshot { uptime_s: 61, ..Default::default() }, 1.0).unwrap();
+        // appending to it checks the header instead of writing another
+        Recorder::open(&path).unwrap().write(&Snapshot { uptime_s: 62, ..Default::default() }, 1.0).unwrap();
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.until_next(), Some(Duration::ZERO));
+        let mut read = vec![];
+        while let Some(recorded) = replay.advance().unwrap() {
+            read.push((recorded.snapshot.uptime_s, recorded.delta));
+        }
+        assert_eq!(read, vec![(60, 0.5), (61, 1.0), (62, 1.0)]);
+        assert_eq!(replay.until_next(), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_cut_off_last_line_ends_the_replay() {
+        let path = recording("cut-off");
+        let mut recorder = Recorder::open(&path).unwrap();
+        recorder.write(&Snapshot { uptime_s: 60, ..Default::default() }, 1.0).unwrap();
+        recorder.write(&Snapshot { uptime_s: 61, ..Default::default() }, 1.0).unwrap();
+        let full = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, &full[..full.len() - 20]).unwrap();
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.advance().unwrap().map(|recorded| recorded.snapshot.uptime_s), Some(60));
+        assert!(replay.advance().unwrap().is_none());
+        // a broken line with more after it is still an error
+        std::fs::write(&path, full.replacen("\"delta\"", "\"delta\"x", 1)).unwrap();
+        assert!(matches!(Replay::open(&path), Err(HwtopError::Parse { .. })));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
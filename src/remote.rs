@@ -0,0 +1,168 @@
+use std::{io::{BufRead, BufReader}, process::{Command, Stdio}, sync::mpsc::{self, Receiver, Sender, TryRecvError}, thread, time::{Duration, Instant}};
+
+use crate::collect::Snapshot;
+
+/// Longest wait between reconnects, the first retry is a second after the pipe drops and each failed one doubles it
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+enum Message {
+    Snapshot(Box<Snapshot>),
+    /// the pipe closed or sent something that isn't a snapshot, with why
+    Dropped(String),
+}
+
+/// A machine shown with `--remote`, read from `ssh <target> hwtop --json-stream` on a thread of its own that reconnects when the pipe drops
+pub struct Remote {
+    /// `user@host` as given, ssh resolves it with the user's ssh config
+    pub target: String,
+    messages: Receiver<Message>,
+    /// the newest snapshot, kept while disconnected
+    pub snapshot: Option<Snapshot>,
+    /// when the newest snapshot came in
+    last_seen: Option<Instant>,
+    /// why the pipe dropped, `None` while it's up
+    dropped: Option<String>,
+}
+
+impl Remote {
+    /// Starts reading `target`, `args` are passed on to the hwtop there
+    pub fn connect(target: &str, args: Vec<String>) -> Self {
+        let (outbox, messages) = mpsc::channel();
+        let host = target.to_string();
+        // no password prompts, the terminal belongs to the live view, and a dead link is noticed within 15s
+        let command = move || {
+            let mut command = Command::new("ssh");
+            command.args(["-o", "BatchMode=yes", "-o", "ServerAliveInterval=5", "-o", "ServerAliveCountMax=3", &host, "hwtop", "--json-stream"]).args(&args);
+            command
+        };
+        let spawned = thread::Builder::new().name(format!("remote {target}")).spawn(move || stream(command, &outbox));
+        let dropped = spawned.err().map(|e| format!("can't start a thread: {e}"));
+        Self { target: target.to_string(), messages, snapshot: None, last_seen: None, dropped }
+    }
+
+    /// Takes what came in since the last call, `true` when there's a new snapshot to draw
+    pub fn poll(&mut self) -> bool {
+        let mut fresh = false;
+        loop {
+            match self.messages.try_recv() {
+                Ok(Message::Snapshot(snapshot)) => {
+                    self.snapshot = Some(*snapshot);
+                    self.last_seen = Some(Instant::now());
+                    self.dropped = None;
+                    fresh = true;
+                }
+                Ok(Message::Dropped(reason)) => self.dropped = Some(reason),
+                Err(TryRecvError::Empty) => break,
+                // the thread only ends when this is dropped, unless it panicked
+                Err(TryRecvError::Disconnected) => {
+                    self.dropped.get_or_insert_with(|| "the connection thread stopped".to_string());
+                    break;
+                }
+            }
+        }
+        fresh
+    }
+
+    /// Heading of the machine's block, its hostname once a snapshot tells it
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.snapshot.as_ref().and_then(|snapshot| snapshot.host.hostname.as_deref()).unwrap_or(&self.target)
+    }
+
+    /// What's wrong with the connection at `now`, `None` while snapshots come in
+    #[must_use]
+    pub fn note(&self, now: Instant) -> Option<String> {
+        match (&self.dropped, self.last_seen) {
+            (None, Some(_)) => None,
+            (None, None) => Some("connecting".to_string()),
+            (Some(reason), Some(seen)) => Some(format!("disconnected {}s ago: {reason}", now.saturating_duration_since(seen).as_secs())),
+            (Some(reason), None) => Some(format!("can't connect: {reason}")),
+        }
+    }
+}
+
+/// Runs `command` over and over with a growing wait in between, until the `Remote` reading `messages` is dropped
+fn stream(command: impl Fn() -> Command, messages: &Sender<Message>) {
+    let mut backoff = Duration::from_secs(1);
+    while let Some(reason) = read(command(), messages, &mut backoff) {
+        if messages.send(Message::Dropped(reason)).is_err() {
+            return;
+        }
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Sends the snapshots `command` prints until its output ends and returns why it did, `None` when nobody's reading anymore.
+/// Every snapshot resets `backoff`, a connection that worked for a while is retried right away
+fn read(mut command: Command, messages: &Sender<Message>, backoff: &mut Duration) -> Option<String> {
+    let mut child = match command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => return Some(format!("can't run ssh: {e}")),
+    };
+    // ssh and the hwtop there explain failures on stderr, it's read on the side so a chatty one can't fill the pipe and stall stdout
+    let stderr = child.stderr.take().map(|stderr| thread::spawn(move || BufReader::new(stderr).lines().map_while(Result::ok).filter(|line| !line.trim().is_empty()).last()));
+    let Some(stdout) = child.stdout.take() else {
+        return Some("ssh has no output".to_string());
+    };
+    let mut reason = None;
+    for line in BufReader::new(stdout).lines() {
+        let snapshot = match line.map_err(|e| e.to_string()).and_then(|line| serde_json::from_str::<Snapshot>(&line).map_err(|e| e.to_string())) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                reason = Some(format!("not a snapshot, is the same hwtop version installed there? {e}"));
+                break;
+            }
+        };
+        *backoff = Duration::from_secs(1);
+        if messages.send(Message::Snapshot(Box::new(snapshot))).is_err() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+    }
+    let _ = child.kill();
+    let status = child.wait();
+    let stderr = stderr.and_then(|stderr| stderr.join().ok().flatten());
+    Some(reason.or(stderr).unwrap_or_else(|| match status {
+        Ok(status) => format!("ssh exited with {status}"),
+        Err(e) => format!("ssh didn't exit: {e}"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dropped_pipes_say_why() {
+        let (outbox, _messages) = mpsc::channel();
+        let mut backoff = Duration::from_secs(8);
+        assert_eq!(read(sh("echo 'bash: hwtop: command not found' >&2; exit 127"), &outbox, &mut backoff).as_deref(), Some("bash: hwtop: command not found"));
+        assert!(read(sh("echo '{'"), &outbox, &mut backoff).unwrap().starts_with("not a snapshot"));
+        assert_eq!(backoff, Duration::from_secs(8));
+    }
+
+    #[test]
+    fn notes_tell_how_long_ago_it_dropped() {
+        let (_outbox, messages) = mpsc::channel();
+        let mut remote = Remote { target: "pi@nas".to_string(), messages, snapshot: None, last_seen: None, dropped: None };
+        let now = Instant::now();
+        assert_eq!(remote.note(now).as_deref(), Some("connecting"));
+        assert_eq!(remote.name(), "pi@nas");
+        remote.dropped = Some("Connection refused".to_string());
+        assert_eq!(remote.note(now).as_deref(), Some("can't connect: Connection refused"));
+        remote.last_seen = Some(now);
+        assert_eq!(remote.note(now + Duration::from_secs(12)).as_deref(), Some("disconnected 12s ago: Connection refused"));
+        remote.dropped = None;
+        assert_eq!(remote.note(now), None);
+    }
+}
@@ -0,0 +1,1835 @@
+#[cfg(feature = "nvidia")]
+use nvml_wrapper::{Nvml, enum_wrappers::device::{Clock, EccCounter, EncoderType, MemoryError}, enums::device::SampleValue, error::NvmlError, structs::device::FieldId, sys_exports::field_id::NVML_FI_DEV_NVLINK_SPEED_MBPS_COMMON};
+use sysinfo::{Motherboard, System};
+use std::{borrow::Cow, collections::{HashMap, HashSet, VecDeque}, fmt::{self, Write}, path::Path, time::{Duration, Instant, SystemTime}};
+
+use crate::{alert::{self, Alerts}, args::{Args, Section}, braille, error::HwtopError, glyphs, history::{History, Peaks}, summary::{Stat, Summary}, term, theme::Theme};
+use crate::fmt::{Align, Table, Units, cell, fill_bar, format_energy, format_size, mark_peak, mem_bar, mem_usage, percent_bar, ram_bar, rows, visible_width};
+use crate::collect::{Collectors, Snapshot, disks::DiskSample, gpu::{GpuProcessKind, GpuSample}, hwmon::RailUnit, net::{NetSample, net_filter}, sensors::{Sensor, TempKind, classify}, sysfs};
+#[cfg(feature = "nvidia")]
+use crate::collect::gpu::nvidia;
+
+/// Spaces after a bar row of `len` bars so it ends with the widest of `columns` bars
+#[must_use]
+fn bar_pad(len: usize, columns: usize) -> usize {
+    columns.saturating_sub(len)
+}
+
+/// Held peak of each of `values` in the series `key`, the values themselves without `peaks`
+fn held(peaks: &mut Option<Peaks>, key: &str, values: &[u32], now: Instant) -> Vec<u32> {
+    let Some(peaks) = peaks else {
+        return values.to_vec();
+    };
+    peaks.update(key, &values.iter().map(|&value| value as f64).collect::<Vec<_>>(), now).into_iter().map(|peak| peak.round() as u32).collect()
+}
+
+/// `bar` of `width` cells filled to `used / total` with the peak held for `key` marked on it
+fn peak_bar(theme: &Theme, peaks: &mut Option<Peaks>, key: &str, bar: String, (used, total): (u64, u64), width: u32, now: Instant) -> String {
+    let Some(peaks) = peaks else {
+        return bar;
+    };
+    let ratio = if total == 0 { 0.0 } else { used as f64 / total as f64 };
+    let peak = peaks.update(key, &[ratio * 100.0], now)[0];
+    mark_peak(theme, &bar, ratio, peak / 100.0, width)
+}
+
+/// Temps to draw under the CORE bars, physical core sensors are repeated across SMT siblings
+/// (logical CPUs `i` and `i + cores / 2`) so both rows line up
+#[must_use]
+fn align_temps(core_temps: &[u32], cores: usize) -> Vec<u32> {
+    if !core_temps.is_empty() && cores > core_temps.len() && cores.is_multiple_of(core_temps.len()) {
+        core_temps.iter().copied().cycle().take(cores).collect()
+    } else {
+        core_temps.to_vec()
+    }
+}
+
+/// Cuts `line` to `width` visible characters ending in `…`, color escapes are kept and closed
+#[must_use]
+fn truncate_visible(line: &str, width: usize) -> String {
+    if visible_width(line) <= width {
+        return line.to_string();
+    }
+    let mut out = String::new();
+    let mut shown = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            for c in chars.by_ref() {
+                out.push(c);
+                if c == 'm' {
+                    break;
+                }
+            }
+        } else if shown + 1 < width {
+            out.push(c);
+            shown += 1;
+        } else {
+            break;
+        }
+    }
+    out.push(glyphs::get().ellipsis);
+    if line.contains('\x1b') {
+        out += "\x1b[0m";
+    }
+    out
+}
+
+/// Appends two blocks of lines side by side to `out`, the left one padded to its widest line
+fn beside(out: &mut String, left: &str, right: &str, gap: usize) {
+    let width = left.lines().map(visible_width).max().unwrap_or(0) + gap;
+    let (mut left, mut right) = (left.lines(), right.lines());
+    while let (l, r) = (left.next(), right.next()) && (l.is_some() || r.is_some()) {
+        let (l, r) = (l.unwrap_or_default(), r.unwrap_or_default());
+        let start = out.len();
+        out.push_str(l);
+        out.extend(std::iter::repeat_n(' ', width - visible_width(l)));
+        out.push_str(r);
+        out.truncate(start + out[start..].trim_end().len());
+        out.push('\n');
+    }
+}
+
+/// Merges neighbouring values, keeping the highest, until at most `max` bars are left, values that fit are borrowed as they are
+#[must_use]
+fn fit_bars<T: Copy + Ord + Default>(values: &[T], max: usize) -> Cow<'_, [T]> {
+    if values.len() <= max || max == 0 {
+        return Cow::Borrowed(values);
+    }
+    values.chunks(values.len().div_ceil(max)).map(|group| group.iter().copied().max().unwrap_or_default()).collect()
+}
+
+/// Packet counts, kept to 5 characters by switching to `12.3k` / `1.2M` above 9999. Each arm ends where rounding
+/// would carry into another digit, 99_950 is `100k` rather than `100.0k`
+#[must_use]
+fn format_count(count: u64) -> String {
+    match count {
+        0..=9999 => count.to_string(),
+        10_000..=99_949 => format!("{:.1}k", count as f64 / 1e3),
+        99_950..=999_499 => format!("{:.0}k", count as f64 / 1e3),
+        999_500..=99_949_999 => format!("{:.1}M", count as f64 / 1e6),
+        _ => format!("{:.0}M", count as f64 / 1e6),
+    }
+}
+
+/// Seconds since the Unix epoch split up in the local timezone
+fn local_time(epoch: u64) -> Option<libc::tm> {
+    let time = epoch as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    #[cfg(unix)]
+    let ok = !unsafe { libc::localtime_r(&time, &mut tm) }.is_null();
+    #[cfg(windows)]
+    let ok = unsafe { libc::localtime_s(&mut tm, &time) } == 0;
+    ok.then_some(tm)
+}
+
+/// `2024-05-01 09:30` in the local timezone of seconds since the Unix epoch
+fn format_local_time(epoch: u64) -> String {
+    local_time(epoch).map_or("--".to_string(), |tm| format!("{}-{:02}-{:02} {:02}:{:02}", tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min))
+}
+
+/// `09:30:05`, the time of day of `format_local_time` to the second
+fn format_clock(epoch: u64) -> String {
+    local_time(epoch).map_or("--:--:--".to_string(), |tm| format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec))
+}
+
+/// `100M`, `1G`, `2.5G` of a link speed in Mbit/s
+fn format_link_speed(mbps: u32) -> String {
+    if mbps < 1000 { format!("{mbps}M") } else { format!("{}G", mbps as f32 / 1000.0) }
+}
+
+/// Rates of the last few ticks, averaged so a single burst doesn't flicker between 0 and a spike
+#[derive(Default)]
+struct RateHistory {
+    rates: VecDeque<u64>,
+    /// highest smoothed rate this session, used as the color scale
+    peak: u64,
+}
+
+impl RateHistory {
+    const TICKS: usize = 3;
+
+    /// Adds this tick's rate and returns the smoothed one
+    fn push(&mut self, rate: u64) -> u64 {
+        self.rates.push_back(rate);
+        if self.rates.len() > Self::TICKS {
+            self.rates.pop_front();
+        }
+        let avg = self.smoothed();
+        self.peak = self.peak.max(avg);
+        avg
+    }
+
+    /// The smoothed rate of the last tick, for drawing it again
+    fn smoothed(&self) -> u64 {
+        self.rates.iter().sum::<u64>() / self.rates.len().max(1) as u64
+    }
+}
+
+/// Cuts the start of `path` to fit `width` characters, `/home/george/data` becomes `…me/george/data`
+#[must_use]
+fn truncate_left(path: &str, width: usize) -> String {
+    let len = path.chars().count();
+    if len <= width {
+        return path.to_string();
+    }
+    glyphs::get().ellipsis.to_string() + &path.chars().skip(len + 1 - width).collect::<String>()
+}
+
+/// `3d 4h 12m`, leading zero units are dropped
+#[must_use]
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, mins) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{mins}m"),
+        (0, _) => format!("{hours}h {mins}m"),
+        _ => format!("{days}d {hours}h {mins}m"),
+    }
+}
+
+/// Seconds under a minute, otherwise like `format_uptime`
+#[must_use]
+fn format_duration(secs: u64) -> String {
+    if secs < 60 { format!("{secs}s") } else { format_uptime(secs) }
+}
+
+#[must_use]
+fn mobo_name(mobo: Option<&Motherboard>) -> String {
+    mobo.and_then(Motherboard::name).filter(|name| !name.is_empty()).unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Readings that failed this tick are shown as `--`
+fn or_dash(value: Option<u32>) -> impl fmt::Display {
+    fmt::from_fn(move |f| match value {
+        Some(value) => fmt::Display::fmt(&value, f),
+        None => f.pad("--"),
+    })
+}
+
+/// Colors and units every rendered row shares
+pub struct Style {
+    pub theme: Theme,
+    pub units: Units,
+    /// temps are displayed in the chosen unit but always colored by their Celsius value
+    pub fahrenheit: bool,
+    /// false for plain output, firing rows are only highlighted and bar rows only padded for escapes with colors
+    pub use_ansi: bool,
+    /// `[thresholds]` of the config, kinds that aren't there keep `TempKind::thresholds`
+    pub thresholds: Vec<(TempKind, [u32; 3])>,
+    /// cells of the memory bars, `None` picks them by the width
+    pub bar_width: Option<u32>,
+}
+
+impl Style {
+    /// `celsius` in the shown unit
+    #[must_use]
+    pub fn temp(&self, celsius: u32) -> u32 {
+        if self.fahrenheit { (celsius as f32 * 9.0 / 5.0 + 32.0).round() as u32 } else { celsius }
+    }
+
+    fn temp_unit(&self) -> &'static str {
+        if self.fahrenheit { "F" } else { "C" }
+    }
+
+    fn temp_col(&self, celsius: u32, kind: TempKind) -> &str {
+        let thresholds = self.thresholds.iter().find(|&&(known, _)| known == kind).map_or_else(|| kind.thresholds(), |&(_, thresholds)| thresholds);
+        self.theme.heat(celsius, thresholds)
+    }
+
+    fn mhz(&self, clock: u32, max_clock: u32) -> String {
+        let ratio = clock as f32 / max_clock as f32;
+        let ratio = ratio * ratio; // better colors
+        let percent = (ratio * 100.0).round() as u32;
+        let col = self.theme.bar(percent);
+        format!(" {col}{}{}", percent_bar(percent), self.theme.reset())
+    }
+
+    fn opt_col(&self, value: Option<u32>) -> &str {
+        value.map_or(self.theme.dim(), |percent| self.theme.bar(percent))
+    }
+
+    fn bars(&self, percentages: &[u32]) -> impl fmt::Display {
+        fmt::from_fn(move |f| percentages.iter().try_for_each(|&percent| {
+            let color = self.theme.bar(percent);
+            let bar = percent_bar(percent);
+            write!(f, "{color}{bar}{}", self.theme.reset())
+        }))
+    }
+
+    /// `bars` colored by the peak each one held, so a core that spiked between frames stays hot for a moment
+    fn peak_bars(&self, percentages: &[u32], peaks: &[u32]) -> impl fmt::Display {
+        fmt::from_fn(move |f| percentages.iter().zip(peaks).try_for_each(|(&percent, &peak)| {
+            let color = self.theme.bar(peak.max(percent));
+            let bar = percent_bar(percent);
+            write!(f, "{color}{bar}{}", self.theme.reset())
+        }))
+    }
+
+    fn temp_bars(&self, temps: &[u32]) -> impl fmt::Display {
+        fmt::from_fn(move |f| temps.iter().try_for_each(|&temp| {
+            let color = self.temp_col(temp, TempKind::Cpu);
+            let bar = percent_bar(temp);
+            write!(f, "{color}{bar}{}", self.theme.reset())
+        }))
+    }
+
+    /// load is colored against the core count, so a load of 16 on 16 cores is 100%
+    fn load_line(&self, load: [f64; 3], uptime: u64, num_cpus: usize) -> impl fmt::Display {
+        let (heading, info, dim, reset) = (self.theme.heading(), self.theme.info(), self.theme.dim(), self.theme.reset());
+        fmt::from_fn(move |f| {
+            write!(f, "{heading}LOAD{reset}")?;
+            for (i, avg) in load.into_iter().enumerate() {
+                let sep = if i > 0 { " " } else { "" };
+                write!(f, "{sep}{}{avg:>5.1}{reset}", self.theme.bar((avg / num_cpus as f64 * 100.0).round() as u32))?;
+            }
+            write!(f, "  {dim}up{reset} {info}{}{reset}", format_uptime(uptime))
+        })
+    }
+
+    /// The `--info` listing of the hardware found
+    pub fn info(&self, collectors: &Collectors, args: &Args) -> Result<String, HwtopError> {
+        let theme = &self.theme;
+        let (ram_accent, net_accent) = (theme.ram_accent(), theme.net_accent());
+        let (heading, info, dim, reset) = (theme.heading(), theme.info(), theme.dim(), theme.reset());
+        let glyphs = glyphs::get();
+        let (branch, last_branch) = (glyphs.branch, glyphs.last_branch);
+        let mut out = String::new();
+
+        // HOST INFO
+        let host = &collectors.host;
+        let os = [host.os.as_deref(), host.os_version.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+        let kernel = host.kernel.as_ref().map_or(String::new(), |kernel| format!(" {dim}kernel{reset} {info}{kernel}{reset}"));
+        writeln!(out, "{heading}HOST{reset} {} {info}{os}{reset}{kernel} {dim}booted{reset} {info}{}{reset} {dim}({} ago){reset}",
+            host.hostname.as_deref().unwrap_or("--"), format_local_time(host.boot_time), format_uptime(System::uptime()))?;
+
+        // LOAD INFO
+        let load = System::load_average();
+        let cpus = collectors.sys.cpus();
+        writeln!(out, "{}", self.load_line([load.one, load.five, load.fifteen], System::uptime(), cpus.len().max(1)))?;
+
+        // CPU INFO
+        let brand = cpus[0].brand().replace("(R)", "").replace("(TM)", "").replace("Intel ", "").replace("Core ", "");
+        writeln!(out, "{heading}CPU{reset} {brand} {info}x{} Cores{reset}", cpus.len())?;
+        let mut cpu_rows = vec![];
+        let cpu_dir = |i: usize, file: &str| sysfs::read(format!("/sys/devices/system/cpu/cpu{i}/{file}"));
+        // hybrid CPUs are told apart by their max clock, the fastest cores are P-cores
+        let max_freqs: Vec<Option<u64>> = (0..cpus.len()).map(|i| cpu_dir(i, "cpufreq/cpuinfo_max_freq").and_then(|khz| khz.parse().ok())).collect();
+        let top_freq = max_freqs.iter().flatten().max().copied();
+        let physical = |is_p: &dyn Fn(usize) -> bool| (0..cpus.len()).filter(|&i| is_p(i))
+            .map(|i| (cpu_dir(i, "topology/physical_package_id"), cpu_dir(i, "topology/core_id"))).collect::<HashSet<_>>().len();
+        let split = if max_freqs.iter().flatten().any(|&freq| Some(freq) != top_freq) {
+            let p_cores = physical(&|i| max_freqs[i] == top_freq);
+            let e_cores = physical(&|i| max_freqs[i] != top_freq);
+            format!(" {dim}({p_cores}P + {e_cores}E){reset}")
+        } else {
+            String::new()
+        };
+        let cores = System::physical_core_count().map_or("--".to_string(), |cores| cores.to_string());
+        cpu_rows.push(format!("Cores {info}{cores}{reset}{split} {dim}threads{reset} {info}{}{reset}", cpus.len()));
+        let base = crate::collect::throttle::base_mhz().map(|mhz| format!("{info}{mhz}MHz{reset} {dim}base{reset}"));
+        let max = top_freq.map(|khz| format!("{info}{}MHz{reset} {dim}max{reset}", khz / 1000));
+        if base.is_some() || max.is_some() {
+            cpu_rows.push(format!("Clock {}", [base, max].into_iter().flatten().collect::<Vec<_>>().join(" ")));
+        }
+        // every cache once however many CPUs share it, the L3 is usually one for the whole package
+        let mut caches = HashSet::new();
+        let mut cache_sizes = [0u64; 3];
+        for i in 0..cpus.len() {
+            for index in 0.. {
+                let cache = |file: &str| cpu_dir(i, &format!("cache/index{index}/{file}"));
+                let Some(level) = cache("level").and_then(|level| level.parse::<usize>().ok()) else {
+                    break;
+                };
+                let size = cache("size").and_then(|size| size.strip_suffix('K').and_then(|kb| kb.parse::<u64>().ok())).unwrap_or(0) << 10;
+                if (1..=3).contains(&level) && caches.insert((level, cache("type"), cache("shared_cpu_list"))) {
+                    cache_sizes[level - 1] += size;
+                }
+            }
+        }
+        if cache_sizes.iter().any(|&size| size > 0) {
+            let levels: Vec<String> = cache_sizes.iter().enumerate().filter(|&(_, &size)| size > 0).map(|(i, &size)| format!("{dim}L{}{reset} {info}{}{reset}", i + 1, format_size(size, self.units))).collect();
+            cpu_rows.push(format!("Cache {}", levels.join(" ")));
+        }
+        // without cpufreq, as in most VMs, there's nothing to list
+        let cpufreq = |file: &str| sysfs::read(format!("/sys/devices/system/cpu/cpu0/cpufreq/{file}"));
+        if let Some(driver) = cpufreq("scaling_driver") {
+            let governors = cpufreq("scaling_available_governors").unwrap_or_default();
+            cpu_rows.push(format!("Scaling {info}{driver}{reset} {dim}governors{reset} {info}{}{reset}", governors.split_whitespace().collect::<Vec<_>>().join(", ")));
+        }
+        for (i, row) in cpu_rows.iter().enumerate() {
+            writeln!(out, "{dim}{}{reset} {row}", if i + 1 == cpu_rows.len() { last_branch } else { branch })?;
+        }
+
+        // GPU INFO
+        #[cfg(any(feature = "nvidia", feature = "amd"))]
+        let gpus = collectors.gpus();
+        #[cfg(feature = "nvidia")]
+        if let Some(nvml) = &gpus.nvml {
+            self.nvidia_info(nvml, &mut out)?;
+        }
+        #[cfg(feature = "amd")]
+        for gpu in gpus.amd() {
+            let gpu_accent = theme.gpu_accent();
+            writeln!(out, "{gpu_accent}GPU{reset} {}", gpu.name)?;
+            let vram = gpu.vram_total().unwrap_or(0) as f64 / (1u64 << 30u64) as f64;
+            writeln!(out, "{dim}{gpu_accent}{last_branch}{reset} VRAM {info}{vram}GB{reset}")?;
+        }
+
+        // MOTHERBOARD INFO
+        // DMI data is missing in containers and some VMs
+        let mobo = Motherboard::new();
+        writeln!(out, "{ram_accent}MOBO{reset} {}", mobo_name(mobo.as_ref()))?;
+        let dmi = |file: &str| sysfs::read(format!("/sys/class/dmi/id/{file}")).filter(|value| !value.is_empty());
+        let joined = |files: &[&str]| Some(files.iter().filter_map(|file| dmi(file)).collect::<Vec<_>>().join(" ")).filter(|value| !value.is_empty());
+        let mut mobo_rows = vec![];
+        if let Some(board) = joined(&["board_vendor", "board_version"]) {
+            mobo_rows.push(format!("Board {info}{board}{reset}"));
+        }
+        if let Some(bios) = joined(&["bios_vendor", "bios_version"]) {
+            let date = dmi("bios_date").map_or(String::new(), |date| format!(" {dim}{date}{reset}"));
+            mobo_rows.push(format!("BIOS {info}{bios}{reset}{date}"));
+        }
+        // the SecureBoot variable is 4 bytes of attributes and then the state
+        // containers may hide /sys/firmware altogether, that says nothing about how the host booted
+        if Path::new("/sys/firmware/efi").exists() {
+            let secure_boot = std::fs::read("/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c").ok().and_then(|var| var.get(4).copied())
+                .map_or(String::new(), |state| format!(" {dim}secure boot{reset} {info}{}{reset}", if state == 1 { "on" } else { "off" }));
+            mobo_rows.push(format!("Boot {info}UEFI{reset}{secure_boot}"));
+        } else if Path::new("/sys/firmware").exists() {
+            mobo_rows.push(format!("Boot {info}legacy BIOS{reset}"));
+        }
+        for (i, row) in mobo_rows.iter().enumerate() {
+            writeln!(out, "{dim}{}{reset} {row}", if i + 1 == mobo_rows.len() { last_branch } else { branch })?;
+        }
+
+        // COMPONENT INFO
+        let mut comp_names: Vec<String> = vec![];
+        for label in &collectors.component_labels {
+            let Sensor::Component { name, .. } = classify(label) else {
+                continue;
+            };
+            if name != "Motherboard" && !comp_names.contains(&name) {
+                comp_names.push(name);
+            }
+        }
+        comp_names.sort();
+        for (i, name) in comp_names.iter().enumerate() {
+            let mut tab = format!("{dim}{ram_accent}{branch}{reset}");
+            if i == comp_names.len() - 1 {
+                tab = format!("{dim}{ram_accent}{last_branch}{reset}");
+            }
+            writeln!(out, "{tab} {name}")?;
+        }
+
+        // DISK INFO
+        // SMART needs the NVMe character device, which is usually root only, SATA would need ATA passthrough
+        let disks = crate::collect::disks::physical(args.min_disk_size);
+        if !disks.is_empty() {
+            writeln!(out, "{heading}DISK{reset}")?;
+        }
+        for (i, disk) in disks.iter().enumerate() {
+            let model = disk.model.as_ref().map_or(String::new(), |model| format!(" {model}"));
+            let health = disk.health.as_ref().map_or(format!(" {dim}health unknown{reset}"), |health| {
+                let errors_col = if health.media_errors > 0 { theme.alert() } else { info };
+                format!(" {dim}wear{reset} {}{}%{reset} {dim}on{reset} {info}{}h{reset} {dim}unsafe shutdowns{reset} {info}{}{reset} {dim}media errors{reset} {errors_col}{}{reset}",
+                    theme.bar(health.wear_percent.min(100)), health.wear_percent, health.power_on_hours, health.unsafe_shutdowns, health.media_errors)
+            });
+            writeln!(out, "{dim}{}{reset} {info}{}{reset}{model} {info}{}{reset}{health}", if i + 1 == disks.len() { last_branch } else { branch }, disk.name, format_size(disk.size, self.units))?;
+        }
+
+        writeln!(out, "{net_accent}Networks{reset} ")?;
+        let mut net_iter = collectors.nets.iter().filter(|&(name, data)| net_filter(name, data, &args.exclude_ifaces)).peekable();
+        while let Some((name, data)) = net_iter.next() {
+            let ips = data.ip_networks().iter().map(|ip| format!("{}{}{reset}]", if ip.prefix == 24 { format!("ipv4[{dim}") } else { format!("ipv6[{dim}") }, ip.addr)).collect::<Vec<String>>().join(", ");
+            let mut tab = format!("{dim}{net_accent}{branch}{reset}");
+            if net_iter.peek().is_none() {
+                tab = format!("{dim}{net_accent}{last_branch}{reset}");
+            }
+            writeln!(out, "{tab} {info}{name}{reset} {ips}{reset} mac[{dim}{}{reset}]", &data.mac_address())?;
+        }
+        Ok(out)
+    }
+
+    /// `--info` rows of every NVIDIA GPU
+    #[cfg(feature = "nvidia")]
+    fn nvidia_info(&self, nvml: &Nvml, out: &mut String) -> Result<(), HwtopError> {
+        let theme = &self.theme;
+        let (gpu_accent, info, dim, reset) = (theme.gpu_accent(), theme.info(), theme.dim(), theme.reset());
+        let glyphs = glyphs::get();
+        let (branch, last_branch) = (glyphs.branch, glyphs.last_branch);
+        let num_gpus = nvml.device_count().map_err(HwtopError::nvml("GPU count"))?;
+        // a failing query, like the energy counter before Volta, prints unknown on its line instead of hiding the rest
+        let or_unknown = |value: Result<String, NvmlError>| value.unwrap_or_else(|_| "unknown".to_string());
+        for i in 0..num_gpus {
+            let Ok(gpu) = nvml.device_by_index(i) else {
+                writeln!(out, "{gpu_accent}GPU{reset} {info}unknown{reset}")?;
+                continue;
+            };
+            writeln!(out, "{gpu_accent}GPU{reset} {}", or_unknown(gpu.name().map(|name| name.replace("NVIDIA ", "").replace("GeForce ", ""))))?;
+            let max_clock = |clock| or_unknown(gpu.max_clock_info(clock).map(|mhz| format!("{mhz}MHz")));
+            let tab = format!("{dim}{gpu_accent}{branch}{reset}");
+            writeln!(out, "{tab} VRAM {info}{}{reset} {info}{}{reset}", or_unknown(gpu.memory_info().map(|memory| format!("{}GB", memory.total as f64 / (1u64 << 30u64) as f64))), max_clock(Clock::Memory))?;
+            writeln!(out, "{tab} Clock {dim}Gfx{reset} {info}{}{reset}  {dim}SM{reset} {info}{}{reset}  {dim}Vid{reset} {info}{}{reset}", max_clock(Clock::Graphics), max_clock(Clock::SM), max_clock(Clock::Video))?;
+            writeln!(out, "{tab} Cores {info}{}{reset}", or_unknown(gpu.num_cores().map(|cores| cores.to_string())))?;
+            writeln!(out, "{tab} PCI {info}{}{reset}", or_unknown(gpu.pci_info().map(|pci| pci.bus_id)))?;
+            writeln!(out, "{tab} UUID {info}{}{reset}", or_unknown(gpu.uuid()))?;
+            writeln!(out, "{tab} VBIOS {info}{}{reset}", or_unknown(gpu.vbios_version()))?;
+            writeln!(out, "{tab} Compute {info}{}{reset}", or_unknown(gpu.cuda_compute_capability().map(|cc| format!("{}.{}", cc.major, cc.minor))))?;
+            writeln!(out, "{tab} Persistence {info}{}{reset}", or_unknown(gpu.is_in_persistent_mode().map(|on| if on { "on" } else { "off" }.to_string())))?;
+            if let Ok(limit) = gpu.power_management_limit() {
+                let default = gpu.power_management_limit_default().map_or(String::new(), |default| format!(" {dim}default{reset} {info}{}W{reset}", default / 1000));
+                let range = gpu.power_management_limit_constraints().map_or(String::new(), |range| format!(" {dim}settable{reset} {info}{}-{}W{reset}", range.min_limit / 1000, range.max_limit / 1000));
+                writeln!(out, "{tab} Power limit {info}{}W{reset}{default}{range}", limit / 1000)?;
+            }
+            // which GPU each link goes to, anything else is an NVSwitch or the CPU
+            let mut links = vec![];
+            for link in 0..nvidia::MAX_NVLINKS {
+                let nvlink = gpu.link_wrapper_for(link);
+                let Ok(active) = nvlink.is_active() else {
+                    break;
+                };
+                let remote = nvlink.remote_pci_info().ok().filter(|_| active).map(|remote| {
+                    (0..num_gpus).find(|&other| nvml.device_by_index(other).and_then(|other| other.pci_info()).is_ok_and(|pci| pci.bus_id == remote.bus_id))
+                        .map_or(format!("{dim}{}{reset}", remote.bus_id), |other| format!("GPU {other}"))
+                });
+                let version = nvlink.version().map_or(String::new(), |version| format!(" {dim}v{version}{reset}"));
+                links.push(remote.map_or(format!("{dim}{link} off{reset}"), |remote| format!("{info}{link}{reset}{version} {dim}to{reset} {remote}")));
+            }
+            if !links.is_empty() {
+                let speed = gpu.field_values_for(&[FieldId(NVML_FI_DEV_NVLINK_SPEED_MBPS_COMMON)]).ok()
+                    .and_then(|samples| samples.into_iter().next()?.ok()?.value.ok())
+                    .map_or(String::new(), |speed| match speed {
+                        SampleValue::U32(mbps) => format!(" {info}{mbps}MB/s{reset}"),
+                        SampleValue::U64(mbps) => format!(" {info}{mbps}MB/s{reset}"),
+                        _ => String::new(),
+                    });
+                writeln!(out, "{tab} NVLink{speed} {}", links.join(", "))?;
+            }
+            // consumer cards have no ECC and answer NotSupported
+            if let Ok(mode) = gpu.is_ecc_enabled() {
+                let state = |enabled: bool| if enabled { "enabled" } else { "disabled" };
+                let pending = if mode.pending_enabled != mode.currently_enabled { format!(" {dim}({} after reboot){reset}", state(mode.pending_enabled)) } else { String::new() };
+                let lifetime = |error| gpu.total_ecc_errors(error, EccCounter::Aggregate).map_or("--".to_string(), |count| count.to_string());
+                writeln!(out, "{tab} ECC {info}{}{reset}{pending} {dim}lifetime corrected{reset} {info}{}{reset} {dim}uncorrected{reset} {info}{}{reset}",
+                    state(mode.currently_enabled), lifetime(MemoryError::Corrected), lifetime(MemoryError::Uncorrected))?;
+            }
+            // older cards have no NVENC and report NotSupported
+            let capacity = |codec| gpu.encoder_capacity(codec).map_or("--".to_string(), |capacity| format!("{capacity}%"));
+            if gpu.encoder_capacity(EncoderType::H264).is_ok() {
+                writeln!(out, "{tab} NVENC free {dim}H.264{reset} {info}{}{reset}  {dim}HEVC{reset} {info}{}{reset}", capacity(EncoderType::H264), capacity(EncoderType::HEVC))?;
+            }
+            writeln!(out, "{tab} Consumed {info}{}{reset}", or_unknown(gpu.total_energy_consumption().map(|mj| format!("{}MJ", (mj as f32 / 1e9 * 100.0).round() / 100.0))))?;
+            writeln!(out, "{tab} Driver {info}{}{reset}", or_unknown(nvml.sys_driver_version()))?;
+            writeln!(out, "{tab} Perf {info}{}{reset} {dim}(0-15, 0 = max){reset}", or_unknown(gpu.performance_state().map(|state| format!("{:?}", state.as_c()))))?;
+            writeln!(out, "{dim}{gpu_accent}{last_branch}{reset} CUDA {info}{}{reset}", or_unknown(nvml.sys_cuda_driver_version().map(|version| version.to_string())))?;
+        }
+        Ok(())
+    }
+
+    /// Heading over each machine's block with `--remote`, `note` says what's wrong with its connection
+    #[must_use]
+    pub fn host_header(&self, name: &str, note: Option<&str>, width: Option<usize>) -> String {
+        let (heading, dim, reset) = (self.theme.heading(), self.theme.dim(), self.theme.reset());
+        let note = note.map_or(String::new(), |note| format!("  {dim}{note}{reset}"));
+        truncate_visible(&format!("{heading}{name}{reset}{note}"), width.unwrap_or(usize::MAX)) + "\n"
+    }
+
+    /// `block` in the dim color only, for the last frame of a machine that's disconnected
+    #[must_use]
+    pub fn dimmed(&self, block: &str) -> String {
+        let (dim, reset) = (self.theme.dim(), self.theme.reset());
+        block.lines().map(|line| {
+            let mut plain = String::with_capacity(line.len());
+            let mut chars = line.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1b' {
+                    chars.by_ref().find(|&c| c == 'm');
+                } else {
+                    plain.push(c);
+                }
+            }
+            format!("{dim}{plain}{reset}\n")
+        }).collect()
+    }
+
+    /// Dim line at the bottom of the live view with the time, the refresh interval and how long collecting the snapshot took.
+    /// The spinner moves on with every snapshot, so an idle machine doesn't look like a frozen hwtop
+    #[must_use]
+    pub fn footer(&self, paused: bool, interval: Duration, collect_ms: Option<f32>, snapshots: usize, width: Option<usize>) -> String {
+        let (alert_col, dim, reset) = (self.theme.alert(), self.theme.dim(), self.theme.reset());
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |now| now.as_secs());
+        let state = match paused {
+            true => format!("{alert_col}PAUSED{reset}{dim}"),
+            false => format!("every {:.2}s", interval.as_secs_f32()),
+        };
+        let collect = collect_ms.map_or(String::new(), |ms| format!("  collect {ms:.0}ms"));
+        let spinner = glyphs::get().spinner;
+        let footer = format!("{dim}{}  {state}{collect}  {}{reset}", format_clock(now), spinner[snapshots % spinner.len()]);
+        truncate_visible(&footer, width.unwrap_or(usize::MAX)) + "\n"
+    }
+
+    /// Keys of the live view under the frame, empty when hidden with `h`
+    #[must_use]
+    pub fn key_hint(&self, show: bool, paused: bool, interval: Duration, width: Option<usize>) -> String {
+        if !show {
+            return String::new();
+        }
+        let (alert_col, dim, reset) = (self.theme.alert(), self.theme.dim(), self.theme.reset());
+        let pause = if paused { format!("{alert_col}p resume{reset}{dim}") } else { "p pause".to_string() };
+        let hint = format!("{dim}q quit  {pause}  e extra  +/- {:.2}s  g gpu  1-6 sections  ? help  h hide keys{reset}", interval.as_secs_f32());
+        truncate_visible(&hint, width.unwrap_or(usize::MAX)) + "\n"
+    }
+
+    /// Keys with the flags they stand for, then what hwtop found on this machine, `sys` is `None` for a replay
+    #[must_use]
+    pub fn help(&self, snapshot: &Snapshot, sys: Option<&System>, args: &Args, interval: Duration, width: Option<usize>) -> String {
+        let (heading, dim, reset) = (self.theme.heading(), self.theme.dim(), self.theme.reset());
+        let units = self.units;
+        let hidden: Vec<&str> = args.hide.iter().map(|section| section.name()).collect();
+        let keys = [
+            "q, Esc;quit;".to_string(),
+            "p;pause and resume;".to_string(),
+            "e;extra components;--extra".to_string(),
+            format!("+, -;halve or double the refresh interval, now {:.2}s;--interval", interval.as_secs_f32()),
+            "g;one GPU at a time, then all of them;".to_string(),
+            format!("1-6;toggle cpu, mem, clocks, fans, net, disks;--hide {}", hidden.join(",")),
+            "h;hide the key hint;".to_string(),
+            "?;this help;".to_string(),
+        ];
+        let cpu = sys.and_then(|sys| sys.cpus().first()).map_or("unknown".to_string(), |cpu| cpu.brand().trim().to_string());
+        let mut hardware = vec![
+            format!("CPU;{};{} threads", cell(&cpu), snapshot.cpu.cores.len()),
+            format!("RAM;{};", format_size(snapshot.memory.ram_total, units)),
+        ];
+        hardware.extend(snapshot.gpus.iter().map(|gpu| format!("GPU;{};{}", cell(&gpu.name), gpu.vram_total.map_or(String::new(), |total| format_size(total, units)))));
+        hardware.extend(snapshot.disks.iter().map(|disk| format!("DISK;{};{}", cell(&disk.name), format_size(disk.total, units))));
+        hardware.extend(snapshot.networks.iter().map(|net| format!("NETW;{};", cell(&net.name))));
+        let help = format!("{heading}KEYS{reset}\n{}{heading}HARDWARE{reset}\n{}{dim}press any key to go back{reset}\n", rows(&keys), rows(&hardware));
+        help.lines().map(|line| truncate_visible(line, width.unwrap_or(usize::MAX)) + "\n").collect()
+    }
+
+    /// Min, avg and max of the session for `--summary`, with what the energy cost at `kwh_price`
+    #[must_use]
+    pub fn summary(&self, summary: &Summary, kwh_price: Option<f64>) -> String {
+        let (dim, reset, units) = (self.theme.dim(), self.theme.reset(), self.units);
+        let deg = glyphs::get().degree;
+        let mut out = format!("{dim}session {}{reset}\n", format_duration(summary.started.elapsed().as_secs()));
+        let stat_row = |name: &str, stat: &Stat, show: &dyn Fn(f32) -> String| {
+            stat.get().map(|(min, avg, max)| format!("{name};{};{};{}", show(min), show(avg), show(max)))
+        };
+        let percent = |v: f32| format!("{}%", v.round() as u32);
+        let temp = |v: f32| format!("{}{deg}{}", self.temp(v.round() as u32), self.temp_unit());
+        let size = |v: f32| format_size(v as u64, units);
+        let energy = summary.cpu_energy.into_iter().chain(summary.gpu_energy).reduce(|a, b| a + b);
+        let or_dash = |wh: Option<f64>| wh.map_or("--".to_string(), format_energy);
+        let stats: Vec<String> = [
+            Some(";min;avg;max".to_string()),
+            stat_row("CPU usage", &summary.cpu_usage, &percent),
+            stat_row("CPU temp", &summary.cpu_temp, &temp),
+            stat_row("GPU usage", &summary.gpu_usage, &percent),
+            stat_row("GPU temp", &summary.gpu_temp, &temp),
+            stat_row("GPU power", &summary.gpu_power, &|v| format!("{}W", v.round() as u32)),
+            stat_row("RAM", &summary.ram, &size),
+            stat_row("VRAM", &summary.vram, &size),
+            Some(format!("NETW;rx {};tx {};", format_size(summary.net_rx, units), format_size(summary.net_tx, units))),
+            Some(format!("DISK;R {};W {};", format_size(summary.disk_read, units), format_size(summary.disk_written, units))),
+            energy.map(|total| format!("ENERGY;cpu {};gpu {};total {}", or_dash(summary.cpu_energy), or_dash(summary.gpu_energy), format_energy(total))),
+            energy.zip(kwh_price).map(|(total, price)| format!("COST;{:.2};at {price}/kWh;", total / 1000.0 * price)),
+            summary.throttle_events.map(|events| format!("THROTTLE;{events}{};;", if summary.throttle_guessed { "?" } else { "" })),
+        ].into_iter().flatten().collect();
+        for (i, line) in rows(&stats).lines().enumerate() {
+            if i == 0 { out += &format!("{dim}{line}{reset}\n") } else { out += &format!("{line}\n") }
+        }
+        out
+    }
+}
+
+/// The CPU and GPU blocks of a frame, built up apart and then put side by side or interleaved row by row
+#[derive(Default)]
+struct Blocks {
+    cpu_head: String,
+    gpu_head: String,
+    ram_rows: String,
+    vram_rows: String,
+    core_rows: String,
+    gpu_rows: String,
+    cpu: String,
+    gpu: String,
+}
+
+/// Draws snapshots as frames, keeping the histories of the sparklines and graphs between them
+pub struct View {
+    pub style: Style,
+    once: bool,
+    history_len: usize,
+    cpu_history: History<u32>,
+    gpu_histories: Vec<History<u32>>,
+    graph_histories: [History<u32>; 3],
+    rate_histories: HashMap<String, [History<u64>; 2]>,
+    disk_rates: HashMap<String, [RateHistory; 2]>,
+    /// uncorrected ECC errors of each GPU when the session started, by PCI bus id since the order can change when one drops out
+    ecc_baselines: HashMap<String, u64>,
+    /// peaks of the memory bars and core rows, `None` with `--no-peaks` or `--once`
+    peaks: Option<Peaks>,
+    /// `g` steps through the GPUs one at a time and back to all of them
+    gpu_shown: Option<usize>,
+    /// rows of the last frame, written over by the next one instead of allocated again
+    blocks: Blocks,
+    table: Table,
+    /// milliseconds the last frame took, for `--debug-timing`
+    draw_ms: f32,
+    /// when the last new snapshot was drawn, peaks of a snapshot drawn again are taken at the same time so they hold still
+    drawn_at: Instant,
+}
+
+impl View {
+    /// `interval` is the refresh interval histories are sized for, `once` leaves out the sparklines
+    pub fn new(style: Style, args: &Args, interval: Duration, once: bool) -> Self {
+        // --history is in seconds at the default 1s interval, faster refreshes keep more samples for the same wall time
+        let history_len = (args.history as f32 / interval.as_secs_f32()).ceil() as usize;
+        // two samples per braille cell across the terminal, a few minutes at the default interval
+        let graph_len = 2 * args.width.or_else(term::width).unwrap_or(80).saturating_sub(5);
+        Self {
+            style,
+            once,
+            history_len,
+            cpu_history: History::new(history_len),
+            gpu_histories: vec![],
+            graph_histories: std::array::from_fn(|_| History::new(graph_len)),
+            rate_histories: HashMap::new(),
+            disk_rates: HashMap::new(),
+            ecc_baselines: HashMap::new(),
+            peaks: (args.peaks && !once).then(|| Peaks::new(args.peak_hold, args.peak_decay)),
+            gpu_shown: None,
+            blocks: Blocks::default(),
+            table: Table::default(),
+            draw_ms: 0.0,
+            drawn_at: Instant::now(),
+        }
+    }
+
+    /// Narrows the view to the next GPU, after the last one all of them are shown again
+    pub fn next_gpu(&mut self) {
+        self.gpu_shown = Some(self.gpu_shown.map_or(0, |i| i + 1));
+        self.gpu_histories.clear();
+    }
+
+    /// The whole display for `snapshot` in place of what `out` held, rows shrink to fit `width` when it's known. A snapshot
+    /// that isn't `fresh`, drawn again while paused, leaves the histories and peaks as they are
+    pub fn frame(&mut self, out: &mut String, snapshot: &Snapshot, fresh: bool, args: &Args, alerts: &Alerts, width: Option<usize>) -> fmt::Result {
+        let View { style, once, history_len, cpu_history, gpu_histories, graph_histories, rate_histories, disk_rates, ecc_baselines, peaks, gpu_shown, blocks, table, draw_ms, drawn_at } = self;
+        let start = Instant::now();
+        if fresh {
+            *drawn_at = start;
+        }
+        let now = *drawn_at;
+        let (style, once, history_len) = (&*style, *once, *history_len);
+        let theme = &style.theme;
+        let (cpu_accent, gpu_accent, ram_accent) = (theme.cpu_accent(), theme.gpu_accent(), theme.ram_accent());
+        let (heading, info, rx_accent, tx_accent) = (theme.heading(), theme.info(), theme.rx(), theme.tx());
+        let (alert_col, dim, reset) = (theme.alert(), theme.dim(), theme.reset());
+        let glyphs = glyphs::get();
+        let (rx_mark, tx_mark, deg) = (glyphs.rx, glyphs.tx, glyphs.degree);
+        let (units, temp_unit) = (style.units, style.temp_unit());
+        let disp_temp = |celsius: u32| style.temp(celsius);
+        // memory junction and hotspot, each colored by its own thresholds
+        let gpu_temps = |gpu: &GpuSample| -> Vec<String> {
+            [("mem", gpu.mem_temp_c, TempKind::GpuMem), ("hot", gpu.hotspot_c, TempKind::GpuHotspot)].into_iter()
+                .filter_map(|(name, temp, kind)| temp.map(|t| format!("{dim}{name}{reset} {}{}{deg}{temp_unit}{reset}", style.temp_col(t, kind), disp_temp(t))))
+                .collect()
+        };
+
+        out.clear();
+        let multi_gpu = snapshot.gpus.len() > 1;
+        // `g` narrows the view to one GPU, alerts keep the index it has among all of them
+        *gpu_shown = gpu_shown.filter(|&i| i < snapshot.gpus.len());
+        let gpus: Vec<(usize, &_)> = snapshot.gpus.iter().enumerate().filter(|&(i, _)| gpu_shown.is_none_or(|shown| shown == i)).collect();
+        let bar_width = style.bar_width.unwrap_or(match width {
+            Some(..60) => 6,
+            Some(..80) => 10,
+            _ => 14,
+        });
+
+        // RESUMED
+        // nothing from before a suspend compares to what comes after, the histories start over and this frame has no rates
+        let resumed = snapshot.resumed_after_s.is_some();
+        if let Some(secs) = snapshot.resumed_after_s {
+            if fresh {
+                std::iter::once(&mut *cpu_history).chain(gpu_histories.iter_mut()).chain(graph_histories.iter_mut()).for_each(History::clear);
+                rate_histories.clear();
+                disk_rates.clear();
+                if let Some(peaks) = peaks {
+                    peaks.clear();
+                }
+            }
+            writeln!(out, "{dim}resumed after {}{reset}", format_duration(secs))?;
+        }
+
+        // ALERTS
+        let firing = alerts.firing();
+        if !firing.is_empty() {
+            let list: Vec<String> = firing.iter().map(|firing| {
+                let gpu = if multi_gpu && matches!(firing.alert.metric, alert::Metric::Gpu | alert::Metric::GpuTemp | alert::Metric::Vram) { format!(" #{}", firing.device) } else { String::new() };
+                let threshold = match firing.alert.threshold {
+                    alert::Threshold::Percent(percent) => format!("{percent}%"),
+                    alert::Threshold::Celsius(celsius) => format!("{}{deg}{temp_unit}", disp_temp(celsius)),
+                    alert::Threshold::Bytes(bytes) => format_size(bytes, units),
+                };
+                format!("{}{gpu}{dim}{}{threshold}{reset} {alert_col}{}{reset}", firing.alert.metric.name(), glyphs.at_least, format_duration(firing.active.as_secs()))
+            }).collect();
+            writeln!(out, "{alert_col}ALERT{reset} {}", list.join("  "))?;
+        }
+        // rows of firing alerts are shown in reverse video and blink, resets inside the row re-apply it
+        let highlight = |row: String, firing: bool| -> String {
+            if firing && style.use_ansi { format!("{}{}{reset}", theme.highlight(), row.replace(reset, &format!("{reset}{}", theme.highlight()))) } else { row }
+        };
+
+        // sections hidden with --hide, --only or the number keys
+        let shown = |section: Section| !args.hide.contains(&section);
+
+        // SYSTEM UTILIZATION
+        if shown(Section::Cpu) {
+            writeln!(out, "{}", style.load_line(snapshot.load, snapshot.uptime_s, snapshot.cpu.cores.len().max(1)))?;
+        }
+        let cpu_usage = snapshot.cpu.usage.round() as u32;
+        let cpu_temp = snapshot.cpu.temp_c.unwrap_or(0);
+        // package power is only colored when the TDP is known
+        let cpu_power_str = snapshot.cpu.power_w.map_or(String::new(), |power| {
+            let col = snapshot.cpu.power_limit_w.filter(|&limit| limit > 0.0).map_or("", |limit| theme.bar((power / limit * 100.0).round() as u32));
+            let energy = snapshot.cpu.energy_wh.map_or(String::new(), |wh| format!(" {dim}({}){reset}", format_energy(wh)));
+            format!(" {col}{:>3}W{reset}{energy}", power.round() as u32)
+        });
+        // the clock heuristic used without throttle counters is marked with a `?`
+        let cpu_throttle_str = snapshot.cpu.throttle.as_ref().map_or(String::new(), |throttle| {
+            let guess = if throttle.heuristic { "?" } else { "" };
+            let tag = if throttle.active { format!(" {alert_col}THROTTLE{guess}{reset}") } else { String::new() };
+            let events = if throttle.events > 0 { format!(" {dim}{}x throttled{guess}{reset}", throttle.events) } else { String::new() };
+            tag + &events
+        });
+        let cpu_usage_str = format!(" {cpu_accent}CPU{reset}{}{cpu_usage:>3}%{reset}{}{:>4}{deg}{temp_unit}{reset}{cpu_power_str}{cpu_throttle_str}",
+            theme.bar(cpu_usage), style.temp_col(cpu_temp, TempKind::Cpu), disp_temp(cpu_temp));
+        let mut cpu_usage_str = highlight(cpu_usage_str, alerts.is_firing(alert::Metric::Cpu, 0) || alerts.is_firing(alert::Metric::CpuTemp, 0));
+        // usage history drawn after a row, squeezed into the room the terminal has left
+        if fresh {
+            cpu_history.push(cpu_usage);
+        }
+        gpu_histories.resize_with(gpus.len(), || History::new(history_len));
+        let sparkline = |row: &mut String, history: &History<u32>| -> fmt::Result {
+            let room = width.map_or(args.history, |width| width.saturating_sub(visible_width(row) + 2)).min(args.history);
+            if once || room == 0 {
+                return Ok(());
+            }
+            write!(row, " {}", style.bars(&fit_bars(&history.to_vec(), room)))
+        };
+        sparkline(&mut cpu_usage_str, cpu_history)?;
+        // the CPU and GPU blocks go side by side on wide terminals, otherwise they're interleaved row by row
+        let Blocks { cpu_head, gpu_head, ram_rows, vram_rows, core_rows, gpu_rows, cpu: cpu_block, gpu: gpu_block } = blocks;
+        for block in [&mut *cpu_head, gpu_head, ram_rows, vram_rows, core_rows, gpu_rows, cpu_block, gpu_block] {
+            block.clear();
+        }
+        writeln!(cpu_head, "{cpu_usage_str}")?;
+        // busy machines with low CPU% usually show it here first
+        if args.extra && shown(Section::Extra) {
+            let activity = &snapshot.activity;
+            let rate = |rate: Option<u64>| rate.map_or("--".to_string(), |rate| format_count(rate) + "/s");
+            writeln!(cpu_head, " {dim}ctx {}  irq {}  forks {}{reset}", rate(activity.context_switches), rate(activity.interrupts), rate(activity.forks))?;
+        }
+        for (i, &(id, gpu)) in gpus.iter().enumerate() {
+            let gpu_power_usage_percent = gpu.power_w.zip(gpu.power_limit_w.filter(|&max| max > 0))
+                .map(|(power, max)| (power as f32 / max as f32 * 100.0).round() as u32);
+            let name = if multi_gpu { format!(" {dim}{}{reset}", gpu.name) } else { String::new() };
+            // usage is against the lowered limit, the card's own is shown so that's clear
+            let cap = gpu.power_default_limit_w.filter(|&default| gpu.power_limit_w.is_some_and(|limit| limit != default))
+                .map_or(String::new(), |default| format!(" {dim}(cap {default}W){reset}"));
+            let energy = gpu.energy_wh.map_or(String::new(), |wh| format!(" {dim}({}){reset}", format_energy(wh)));
+            let extra_temps = gpu_temps(gpu);
+            let extra_temps = if extra_temps.is_empty() { String::new() } else { format!(" {dim}({reset}{}{dim}){reset}", extra_temps.join(" ")) };
+            let gpu_usage_str = format!(" {gpu_accent}GPU{reset}{}{:>3}%{reset}{}{:>4}{deg}{temp_unit}{reset}{extra_temps} {}{:>3}W{reset}{dim}/{reset}{}{}W{reset}{cap}{energy}{name}",
+                style.opt_col(gpu.usage), or_dash(gpu.usage), gpu.temp_c.map_or(dim, |t| style.temp_col(t, TempKind::Gpu)), or_dash(gpu.temp_c.map(disp_temp)),
+                style.opt_col(gpu_power_usage_percent), or_dash(gpu.power_w), style.opt_col(gpu_power_usage_percent), or_dash(gpu.power_limit_w));
+            let mut gpu_usage_str = highlight(gpu_usage_str, alerts.is_firing(alert::Metric::Gpu, id) || alerts.is_firing(alert::Metric::GpuTemp, id));
+            let history = &mut gpu_histories[i];
+            if fresh {
+                history.push(gpu.usage.unwrap_or(0));
+            }
+            sparkline(&mut gpu_usage_str, history)?;
+            writeln!(gpu_head, "{gpu_usage_str}")?;
+        }
+
+        // MEMORY USAGES
+        let memory = &snapshot.memory;
+        let huge_unused = memory.hugepages.iter().map(|pool| pool.unused()).sum();
+        let ram = ram_bar(theme, memory.ram_used, huge_unused, memory.ram_cached.unwrap_or(0), memory.ram_total, bar_width, units);
+        let ram = peak_bar(theme, peaks, "ram", ram, (memory.ram_used, memory.ram_total), bar_width, now);
+        let swap = if memory.swap_total == 0 { format!("{dim}no swap{reset}") } else { mem_usage(theme, memory.swap_used, memory.swap_total, units) };
+        // churn is what hurts, not how much is swapped out
+        let swap_rates = match memory.swap_in_bps.zip(memory.swap_out_bps) {
+            Some((0, 0)) | None => String::new(),
+            Some((swap_in, swap_out)) => {
+                let col = if memory.swapping { alert_col } else { dim };
+                format!(" {col}in {}/s out {}/s{reset}", format_size(swap_in, units), format_size(swap_out, units))
+            }
+        };
+        // a big dirty set explains stalls before the next sync
+        let dirty = memory.ram_dirty.map_or(String::new(), |dirty| format!("  {dim}dirty {}{reset}", format_size(dirty, units)));
+        // hugepages count as used from boot on, mapped or not, so pools are shown as used/total pages
+        let huge = memory.hugepages.iter().map(|pool| format!(" {}/{} x{}", pool.total - pool.free, pool.total, format_size(pool.size, units))).collect::<String>();
+        let huge = if huge.is_empty() { huge } else { format!("  {dim}huge{huge}{reset}") };
+        writeln!(ram_rows, "{}", highlight(format!(" {ram_accent}RAM{reset} {ram}  {swap}{swap_rates}{dirty}{huge}"), alerts.is_firing(alert::Metric::Ram, 0)))?;
+        let compressed: Vec<String> = [("zram", &memory.zram), ("zswap", &memory.zswap)].into_iter().filter_map(|(name, compressed)| {
+            let compressed = compressed.as_ref()?;
+            Some(format!("{dim}{name}{reset} {}{}{} {info}({:.1}x){reset}", format_size(compressed.original, units), glyphs.to, format_size(compressed.compressed, units), compressed.ratio()))
+        }).collect();
+        if !compressed.is_empty() {
+            writeln!(ram_rows, "     {}", compressed.join("  "))?;
+        }
+        // one node filling up while the other sits empty is hidden by the total
+        for node in &memory.numa {
+            writeln!(ram_rows, "  {dim}N{:<2}{reset}{}", node.node, mem_bar(theme, node.used, node.cached, node.total, (bar_width / 2).max(1), units))?;
+        }
+
+        for &(id, gpu) in &gpus {
+            let vram = match gpu.vram_used.zip(gpu.vram_total) {
+                Some((used, total)) => peak_bar(theme, peaks, &format!("vram{id}"), mem_bar(theme, used, 0, total, bar_width, units), (used, total), bar_width, now),
+                None => format!("{dim}--{reset}"),
+            };
+            // only errors since hwtop started count, the driver's counts may be from long before
+            let ecc = match &gpu.ecc {
+                Some(ecc) if ecc.enabled => match ecc.uncorrected.map(|count| count.saturating_sub(*ecc_baselines.entry(gpu.bus_id.clone().unwrap_or_else(|| id.to_string())).or_insert(count))) {
+                    Some(0) | None => format!("  {dim}ECC ok{reset}"),
+                    Some(count) => format!("  {alert_col}ECC {count} uncorrected!{reset}"),
+                },
+                Some(_) => format!("  {dim}ECC off{reset}"),
+                None => String::new(),
+            };
+            let bar1 = gpu.bar1_used.zip(gpu.bar1_total.filter(|&total| total > 0)).map_or(String::new(), |(used, total)| {
+                let percent = (used as f64 / total as f64 * 100.0).round() as u32;
+                format!("  {dim}BAR1{reset} {}{percent}%{reset}", theme.bar(percent))
+            });
+            let vram_row = format!("{ram_accent}VRAM {reset}{vram}     {}{}%{reset}{bar1}{ecc}", style.opt_col(gpu.mem_usage), or_dash(gpu.mem_usage));
+            writeln!(vram_rows, "{}", highlight(vram_row, alerts.is_firing(alert::Metric::Vram, id)))?;
+        }
+
+        // CORE USAGES
+        let cpus = &snapshot.cpu.cores;
+        let cores: Vec<u32> = cpus.iter().map(|cpu| cpu.usage as u32).collect();
+        let max_core = cores.iter().copied().max().unwrap_or(0);
+
+        let max_core_freqs: Vec<u32> = cpus.iter().map(|cpu| cpu.max_freq_mhz.unwrap_or(cpu.freq_mhz as u32).max(1)).collect();
+        let core_freqs: Vec<u32> = cpus.iter().zip(max_core_freqs.iter()).map(|(cpu, max_freq)| (cpu.freq_mhz as f32 / *max_freq as f32 * 100.0).round().min(100.0) as u32).collect();
+
+        // CORE FREQS
+        let max_core_freq = cpus.iter().map(|cpu| cpu.freq_mhz).max().unwrap_or(0);
+        let max_core_freq_pct = core_freqs.iter().copied().max().unwrap_or(0);
+        let max_core_freq_str = format!("{max_core_freq}");
+        let mut rating = String::new();
+        if !args.waybar {
+            let min_core_freq_rating = max_core_freqs.iter().copied().min().unwrap_or(0);
+            let max_core_freq_rating = max_core_freqs.iter().copied().max().unwrap_or(0);
+            let single_core_freq_rating = min_core_freq_rating == max_core_freq_rating;
+            rating = format!("{min_core_freq_rating}{}MHz", if single_core_freq_rating { String::new() } else  { format!("-{max_core_freq_rating}") });
+            // a powersave governor or disabled turbo explains bars pinned low
+            let policy: Vec<String> = [snapshot.cpu.governor.clone(), snapshot.cpu.turbo.map(|turbo| format!("turbo {}", if turbo { "on" } else { "off" }))].into_iter().flatten().collect();
+            if !policy.is_empty() {
+                rating += &format!(" {}", policy.join(", "));
+            }
+        }
+
+        // CORE TEMPS
+        // per logical CPU when every core maps to a sensor, otherwise whatever core sensors there are
+        let core_temps: Vec<u32> = match cpus.iter().map(|cpu| cpu.temp_c).collect::<Option<Vec<u32>>>() {
+            Some(temps) if !temps.is_empty() => temps,
+            _ => align_temps(&snapshot.cpu.core_temps_c, cpus.len()),
+        };
+        let max_core_temp = core_temps.iter().copied().max().unwrap_or(0);
+
+        if args.cores {
+            // hybrid CPUs are told apart by their max clock, the fastest cores are P-cores
+            let hybrid = max_core_freqs.iter().any(|&max| max != max_core_freqs[0]);
+            let p_core_freq = max_core_freqs.iter().copied().max().unwrap_or(0);
+            let cells: Vec<String> = cpus.iter().enumerate().map(|(i, cpu)| {
+                let max_freq = cpu.max_freq_mhz.unwrap_or(0);
+                let kind = match hybrid {
+                    false => "",
+                    true if max_freq == p_core_freq => "P",
+                    true => "E",
+                };
+                let label_col = if kind == "E" { heading } else { info };
+                let usage = cpu.usage.round() as u32;
+                let max_freq_str = if max_freq > 0 { format!("{dim}/{max_freq}{reset}") } else { String::new() };
+                let temp = cpu.temp_c.map_or(String::new(), |t| format!(" {}{:>3}{deg}{temp_unit}{reset}", style.temp_col(t, TempKind::Cpu), disp_temp(t)));
+                format!("{label_col}C{i:02}{kind:<1}{reset} {} {}{usage:>3}%{reset} {:>4}{max_freq_str}MHz{temp}", fill_bar(theme, cpu.usage as f64 / 100.0, 10), theme.bar(usage), cpu.freq_mhz)
+            }).collect();
+            // as many columns as fit, cells are padded to the widest one
+            let cell_width = cells.iter().map(|cell| visible_width(cell)).max().unwrap_or(0);
+            let columns = (width.unwrap_or(80) / (cell_width + 2)).max(1);
+            for line in cells.chunks(columns) {
+                let line: Vec<String> = line.iter().map(|cell| format!("{cell}{}", " ".repeat(cell_width - visible_width(cell)))).collect();
+                writeln!(core_rows, "{}", line.join("  ").trim_end())?;
+            }
+        } else {
+            // the three bar rows are padded to the longest so their values line up,
+            // on narrow terminals neighbouring cores share a bar showing the busiest of them
+            let max_bars = width.map_or(usize::MAX, |width| width.saturating_sub(28).max(4));
+            let (core_peaks, freq_peaks) = (held(peaks, "cores", &cores, now), held(peaks, "freqs", &core_freqs, now));
+            let (core_peaks, freq_peaks) = (fit_bars(&core_peaks, max_bars), fit_bars(&freq_peaks, max_bars));
+            let (cores, core_freqs, core_temps) = (fit_bars(&cores, max_bars), fit_bars(&core_freqs, max_bars), fit_bars(&core_temps, max_bars));
+            let off = if style.use_ansi { 5 } else { 0 };
+            let columns = cores.len().max(core_temps.len());
+            if shown(Section::Cores) {
+                writeln!(core_rows, "{info}CORE{reset} {}{:>w$} {max_core}%{reset}", &style.peak_bars(&cores, &core_peaks), theme.bar(max_core), w = off + bar_pad(cores.len(), columns))?;
+            }
+            // Apple Silicon reports no clocks, the row would be all zeros
+            if shown(Section::Freq) && max_core_freq > 0 {
+                writeln!(core_rows, "{info}FREQ{reset} {}{:>w$} {max_core_freq_str:<4}MHz {reset}{dim}{rating}{reset}", style.peak_bars(&core_freqs, &freq_peaks), theme.bar(max_core_freq_pct), w = off + bar_pad(core_freqs.len(), columns))?;
+            }
+            // no sensors at all (Windows has none without admin rights) hides the row, only missing per core ones leave a placeholder
+            if shown(Section::CoreTemp) && core_temps.is_empty() && snapshot.cpu.temp_c.is_some() {
+                writeln!(core_rows, "{info}TEMP{reset} {dim}{} --{temp_unit}{reset}", "-".repeat(columns))?;
+            } else if shown(Section::CoreTemp) && !core_temps.is_empty() {
+                writeln!(core_rows, "{info}TEMP{reset} {}{:>w$} {}{temp_unit}{reset}", style.temp_bars(&core_temps), style.temp_col(max_core_temp, TempKind::Cpu), disp_temp(max_core_temp), w = off + bar_pad(core_temps.len(), columns))?;
+            }
+        }
+
+        for &(_, gpu) in &gpus {
+            // GPU CLOCK
+            let clocks = gpu.clocks.iter().map(|clock| {
+                let bar = match clock.mhz.zip(clock.max_mhz) {
+                    Some((clk, max_clk)) => style.mhz(clk, max_clk),
+                    None => format!(" {dim}-{reset}"),
+                };
+                format!("{dim}{}{reset}{bar}", clock.name)
+            }).collect::<Vec<_>>().join("  ");
+            // video engines go after the clocks, cards without them leave them out
+            let codecs = [("ENC", gpu.encoder), ("DEC", gpu.decoder)].iter().filter(|(_, usage)| usage.is_some())
+                .map(|&(name, usage)| format!("  {dim}{name}{reset} {}{}%{reset}", style.opt_col(usage), or_dash(usage))).collect::<String>();
+            if shown(Section::Clocks) {
+                writeln!(gpu_rows, "{info}CLCK{reset} {clocks}{codecs}")?;
+            }
+
+            // GPU FANS
+            let fan_str = match &gpu.fans {
+                Some(fans) => fans.iter().map(|fan| {
+                    format!("{}{}%{reset} {dim}{:>4}rpm{reset}", style.opt_col(fan.percent), or_dash(fan.percent), or_dash(fan.rpm))
+                }).collect::<Vec<_>>().join(", "),
+                None => format!("{dim}--{reset}"),
+            };
+            // with system fans around they share the one FANS row instead
+            if shown(Section::Fans) && snapshot.fans.is_empty() {
+                writeln!(gpu_rows, "{heading}FANS{reset} {fan_str}")?;
+            }
+
+            // PCIE
+            if let Some(pcie) = gpu.pcie.as_ref().filter(|_| shown(Section::Pcie)) {
+                let max_pcie_throughtput_str = format_size(pcie.max_bps, units);
+                // a link that dropped to a lower gen or fewer lanes fills up sooner, so usage is against the current one
+                // a link in a low power state can report no lanes at all, there's nothing to measure against then
+                let pcie_col = |bps: Option<u64>| style.opt_col(bps.filter(|_| pcie.link_bps > 0).map(|bps| (bps as f32 / pcie.link_bps as f32 * 100.0).round() as u32));
+                let pcie_str = |bps: Option<u64>| bps.map_or("--".to_string(), |bps| format_size(bps, units));
+                let (rx_col, tx_col) = (pcie_col(pcie.rx_bps), pcie_col(pcie.tx_bps));
+                let (rx_str, tx_str) = (pcie_str(pcie.rx_bps), pcie_str(pcie.tx_bps));
+                // idle cards drop their gen to save power, fewer lanes while busy is a card in the wrong slot or a bad riser
+                let link = match pcie.max_link.zip(pcie.link) {
+                    Some((max, now)) => {
+                        let col = if now == max { dim } else if now.width < max.width && gpu.usage.is_some_and(|usage| usage >= 50) { alert_col } else { info };
+                        format!(" {dim}{max}{reset} {col}(now {now}){reset}")
+                    }
+                    None => pcie.max_link.map_or(String::new(), |max| format!(" {dim}{max}{reset}")),
+                };
+                writeln!(gpu_rows, "{heading}PCIE{reset} {rx_accent}{rx_mark}{reset}{rx_col}{rx_str:>6}{reset}  {tx_accent}{tx_mark}{reset}{tx_col}{tx_str:>6}{reset}   {dim}{max_pcie_throughtput_str}/s{reset}{link}", )?;
+            }
+
+            // NVLINK
+            // inactive links are normal on cards with more links than bridges, they're only dimmed
+            if !gpu.nvlinks.is_empty() && shown(Section::Pcie) {
+                let links = gpu.nvlinks.iter().map(|link| {
+                    if !link.active {
+                        return format!("{dim}{} off{reset}", link.link);
+                    }
+                    let rate = |bps: Option<u64>| bps.map_or("--".to_string(), |bps| format_size(bps, units));
+                    format!("{info}{}{reset} {rx_accent}{rx_mark}{reset}{:>6} {tx_accent}{tx_mark}{reset}{:>6}", link.link, rate(link.rx_bps), rate(link.tx_bps))
+                }).collect::<Vec<_>>().join("  ");
+                writeln!(gpu_rows, "{heading}NVLK{reset} {links}")?;
+            }
+
+            // GPU PROCESSES
+            if let Some(processes) = &gpu.processes {
+                if processes.is_empty() {
+                    writeln!(gpu_rows, "{heading}GPRC{reset} {dim}idle{reset}")?;
+                }
+                for proc in processes {
+                    let kind = match proc.kind {
+                        GpuProcessKind::Compute => "C",
+                        GpuProcessKind::Graphics => "G",
+                    };
+                    let vram_col = style.opt_col(proc.vram_used.zip(gpu.vram_total).map(|(used, total)| (used as f64 / total as f64 * 100.0).round() as u32));
+                    let vram = proc.vram_used.map_or("--".to_string(), |used| format_size(used, units));
+                    let sm = proc.sm.map_or(String::new(), |sm| format!("{}{sm:>3}%{reset}", theme.bar(sm)));
+                    write!(table.cell(), "{heading}GPRC{reset}")?;
+                    write!(table.cell(), "{dim}{:>7}{reset}", proc.pid)?;
+                    table.cell().push_str(&proc.name);
+                    write!(table.cell(), "{dim}{kind}{reset}")?;
+                    write!(table.cell(), "{vram_col}{vram:>6}{reset}")?;
+                    table.cell().push_str(&sm);
+                    table.end_row();
+                }
+                table.write(gpu_rows, &[]);
+            }
+        }
+        if !shown(Section::Cpu) {
+            cpu_head.clear();
+        }
+        // the --cores grid has usage, clock and temperature in one cell, it goes with cores
+        if args.cores && !shown(Section::Cores) {
+            core_rows.clear();
+        }
+        if !shown(Section::Mem) {
+            ram_rows.clear();
+        }
+        if !shown(Section::Vram) {
+            vram_rows.clear();
+        }
+        for part in [&*cpu_head, ram_rows, core_rows] {
+            cpu_block.push_str(part);
+        }
+        for part in [&*gpu_head, vram_rows, gpu_rows] {
+            gpu_block.push_str(part);
+        }
+        let block_width = |block: &str| block.lines().map(visible_width).max().unwrap_or(0);
+        if !gpu_block.is_empty() && width.is_some_and(|width| block_width(cpu_block) + 3 + block_width(gpu_block) <= width) {
+            beside(out, cpu_block, gpu_block, 3);
+        } else {
+            for part in [&*cpu_head, gpu_head, ram_rows, vram_rows, core_rows, gpu_rows] {
+                out.push_str(part);
+            }
+        }
+
+        // USAGE GRAPH
+        if args.graph {
+            let gpu_usage = gpus.iter().filter_map(|(_, gpu)| gpu.usage).max();
+            let ram_usage = (memory.ram_used as f64 / memory.ram_total.max(1) as f64 * 100.0).round() as u32;
+            if fresh {
+                for (history, usage) in graph_histories.iter_mut().zip([Some(cpu_usage), gpu_usage, Some(ram_usage)]) {
+                    history.push(usage.unwrap_or(0));
+                }
+            }
+            let series = [("CPU", cpu_accent), ("GPU", gpu_accent), ("RAM", ram_accent)];
+            let shown = if gpus.is_empty() { vec![0, 2] } else { vec![0, 1, 2] };
+            let cells = width.unwrap_or(80).saturating_sub(5).max(1);
+            let height = args.graph_height;
+            let mut canvas = braille::Canvas::new(cells, height);
+            let dots = height * 4 - 1;
+            for &i in &shown {
+                let (_, col) = series[i];
+                let mut last_y = None;
+                for (x, usage) in fit_bars(&graph_histories[i].to_vec(), cells * 2).iter().copied().enumerate() {
+                    let y = ((100 - usage.min(100)) as usize * dots + 50) / 100;
+                    // vertical runs join neighbouring samples into a line
+                    let (from, to) = last_y.map_or((y, y), |last: usize| (last.min(y), last.max(y)));
+                    for y in from..=to {
+                        canvas.set(x, y, col);
+                    }
+                    last_y = Some(y);
+                }
+            }
+            let legend = shown.iter().map(|&i| format!("{}{}{reset}", series[i].1, series[i].0)).collect::<Vec<_>>().join(" ");
+            writeln!(out, "{dim}GRPH{reset} {legend}")?;
+            for (row, line) in canvas.lines(reset).iter().enumerate() {
+                let axis = match row {
+                    0 => "100%",
+                    _ if row == height - 1 => "  0%",
+                    _ => "",
+                };
+                writeln!(out, "{dim}{axis:>4}{reset} {line}")?;
+            }
+        }
+
+        // SYSTEM FANS
+        // CPU fans, then case fans, then the GPUs' so positions hold still, semi-passive fans sit at 0rpm and are dimmed instead of hidden
+        if !snapshot.fans.is_empty() && shown(Section::Fans) {
+            let gpu_fans = gpus.iter().flat_map(|&(id, gpu)| {
+                let fans = gpu.fans.as_deref().unwrap_or_default();
+                let gpu_label = if multi_gpu { format!("GPU{id}") } else { "GPU".to_string() };
+                fans.iter().enumerate().map(move |(i, fan)| (if fans.len() > 1 { format!("{gpu_label} fan{}", i + 1) } else { gpu_label.clone() }, fan.rpm, fan.percent, false))
+            });
+            // hwmon fans that missed the deadline are last tick's and dimmed, GPU fans are read separately
+            let fans: Vec<String> = snapshot.fans.iter().map(|fan| (fan.label.clone(), Some(fan.rpm), fan.percent, snapshot.sensors_stale)).chain(gpu_fans).map(|(label, rpm, percent, stale)| {
+                let col = match percent {
+                    _ if stale || rpm == Some(0) || rpm.is_none() && percent == Some(0) => dim,
+                    Some(percent) => theme.bar(percent),
+                    None => info,
+                };
+                let percent = percent.map_or(String::new(), |percent| format!(" ({percent}%)"));
+                let rpm = rpm.map_or(String::new(), |rpm| format!(" {rpm}rpm"));
+                format!("{dim}{label}{reset}{col}{rpm}{percent}{reset}")
+            }).collect();
+            // wraps under the first fan when the row gets wider than the terminal
+            let mut line = format!("{heading}FANS{reset} ");
+            for (i, fan) in fans.iter().enumerate() {
+                if i > 0 && width.is_some_and(|width| visible_width(&line) + 2 + visible_width(fan) > width) {
+                    writeln!(out, "{line},")?;
+                    line = " ".repeat(5);
+                } else if i > 0 {
+                    line += ", ";
+                }
+                line += fan;
+            }
+            writeln!(out, "{line}")?;
+        }
+
+        // NETWORK
+        let shown_nets = match &args.iface {
+            _ if !shown(Section::Net) => vec![],
+            Some(iface) => snapshot.networks.iter().filter(|net| &net.name == iface).collect(),
+            None if args.all_ifaces => snapshot.networks.iter().collect(),
+            None => snapshot.networks.iter().take(1).collect::<Vec<_>>(),
+        };
+        let net_rates = |net: &NetSample| {
+            let rate = |bps: u64| if resumed { "--".to_string() } else { format_size(bps, units) + "/s" };
+            let count = |pps: u64| if resumed { "--".to_string() } else { format_count(pps) };
+            let (rx, tx, prx, ptx) = (rate(net.rx_bps), rate(net.tx_bps), count(net.rx_pps), count(net.tx_pps));
+            let session = format!("{dim}{}/{}{reset}", format_size(net.session_rx, units), format_size(net.session_tx, units));
+            let errors = if net.errors > 0 { format!(" {alert_col}errs{reset}") } else { String::new() };
+            // rates are colored by how much of the link they take, like PCIE
+            let link_col = |bps: u64| net.link_mbps.map_or(info, |mbps| theme.bar((bps as f32 * 8.0 / (mbps as f32 * 1e6) * 100.0).round() as u32));
+            let (rx_col, tx_col) = (link_col(net.rx_bps), link_col(net.tx_bps));
+            format!("{heading}NETW{reset} {rx_accent}{rx_mark}{reset}{rx_col}{rx:>7}{reset}  {tx_accent}{tx_mark}{reset}{tx_col}{tx:>7}{reset} {rx_accent}{prx:>5}{reset}/{tx_accent}{ptx:<5}{reset} {session}{errors}")
+        };
+        // signal is colored like usage turned around, a strong one is the calm color
+        let wifi_link = |net: &NetSample| net.wifi.as_ref().map_or(String::new(), |wifi| {
+            let ssid = wifi.ssid.as_ref().map_or(String::new(), |ssid| format!(" {info}{ssid}{reset}"));
+            let signal = wifi.signal_percent().map_or(String::new(), |percent| {
+                let col = theme.bar(100 - percent);
+                format!(" {col}{}{percent}%{reset}", percent_bar(percent))
+            });
+            let bitrate = wifi.bitrate_mbps.map_or(String::new(), |mbps| format!(" {dim}{}Mb/s{reset}", mbps.round() as u32));
+            ssid + &signal + &bitrate
+        });
+        // a gigabit NIC stuck at 100M is red, that's usually the cable
+        let eth_link = |net: &NetSample| net.link_mbps.map_or(String::new(), |mbps| {
+            let duplex = net.duplex.as_ref().map_or(String::new(), |duplex| format!(" {duplex}"));
+            let col = if net.link_downgraded { alert_col } else { dim };
+            format!(" {col}{}{duplex}{reset}", format_link_speed(mbps))
+        });
+        if let [net] = shown_nets[..] {
+            writeln!(out, "{} {dim}{}{reset}{}{}", net_rates(net), net.name, wifi_link(net), eth_link(net))?;
+        } else {
+            for net in &shown_nets {
+                table.cell().push_str(&net_rates(net));
+                write!(table.cell(), "{dim}{}{reset}{}{}", net.name, wifi_link(net), eth_link(net))?;
+                table.end_row();
+            }
+            table.write(out, &[]);
+        }
+
+        // RATE GRAPHS
+        // each metric is scaled to its own peak in the window, so a quiet device still shows its shape
+        let mut rate_graph = |out: &mut String, key: String, label: String, markers: [&str; 2], rates: [u64; 2]| {
+            let histories = rate_histories.entry(key).or_insert_with(|| [History::new(history_len), History::new(history_len)]);
+            for ((history, rate), (marker, col)) in histories.iter_mut().zip(rates).zip(markers.into_iter().zip([rx_accent, tx_accent])) {
+                if fresh {
+                    history.push(rate);
+                }
+                let cells = width.map_or(args.history, |width| width.saturating_sub(visible_width(&label) + 16)).min(args.history);
+                let rates = history.to_vec();
+                let rates = fit_bars(&rates, cells);
+                let peak = rates.iter().copied().max().unwrap_or(0);
+                let percents: Vec<u32> = rates.iter().map(|&rate| (rate as f64 / peak.max(1) as f64 * 100.0).round() as u32).collect();
+                writeln!(out, "{label} {col}{marker}{reset} {} {dim}peak {}/s{reset}", style.bars(&percents), format_size(peak, units))?;
+            }
+            Ok::<_, fmt::Error>(())
+        };
+        if args.graphs {
+            for net in &shown_nets {
+                rate_graph(out, format!("net {}", net.name), format!("{dim}{:>4}{reset}", net.name), [rx_mark, tx_mark], [net.rx_bps, net.tx_bps])?;
+            }
+        }
+
+        // DISKS
+        // colored against the busiest this device has been this session
+        let disk_rate = |bps: u64| if resumed { "--".to_string() } else { format_size(bps, units) + "/s" };
+        let shown_disks: &[DiskSample] = if shown(Section::Disks) { &snapshot.disks } else { &[] };
+        for disk in shown_disks {
+            let [read, write] = disk_rates.entry(disk.name.clone()).or_default();
+            let (read_bps, write_bps) = if fresh { (read.push(disk.read_bps), write.push(disk.write_bps)) } else { (read.smoothed(), write.smoothed()) };
+            let peak = read.peak.max(write.peak).max(1);
+            let rate_col = |bps: u64| theme.bar((bps as f64 / peak as f64 * 100.0).round() as u32);
+            let temp_col = |t| if snapshot.sensors_stale { dim } else { style.temp_col(t, TempKind::Nvme) };
+            write!(table.cell(), "{heading}{}{reset}", disk.name)?;
+            table.cell().push_str(&mem_usage(theme, disk.used, disk.total, units));
+            write!(table.cell(), "{rx_accent}R{reset}{}{:>7}{reset} {tx_accent}W{reset}{}{:>7}{reset}", rate_col(read_bps), disk_rate(read_bps), rate_col(write_bps), disk_rate(write_bps))?;
+            match disk.temp_c {
+                Some(t) => write!(table.cell(), "{}{}{deg}{temp_unit}{reset}", temp_col(t), disp_temp(t))?,
+                None => write!(table.cell(), "{dim}--{reset}")?,
+            }
+            write!(table.cell(), "Tot {rx_accent}{}{reset}/{tx_accent}{}{reset}", format_size(disk.total_read, units), format_size(disk.total_written, units))?;
+            if args.disk_detail {
+                let iops = |iops: Option<u64>| iops.map_or("--".to_string(), format_count);
+                write!(table.cell(), "{rx_accent}{:>5}{reset}/{tx_accent}{:<5}{reset}{dim}iops{reset}", iops(disk.read_iops), iops(disk.write_iops))?;
+                match disk.util {
+                    Some(util) => write!(table.cell(), "{}{:>3}%{reset}", theme.bar(util.round() as u32), util.round() as u32)?,
+                    None => write!(table.cell(), "{dim}--{reset}")?,
+                }
+            }
+            table.end_row();
+        }
+        // the name on the left, usage, rates and temperature lined up on the right
+        table.write(out, &[Align::Left, Align::Right, Align::Right, Align::Right, Align::Right, Align::Right, Align::Right]);
+        if args.graphs {
+            for disk in shown_disks {
+                rate_graph(out, format!("disk {}", disk.name), format!("{dim}{:>4}{reset}", disk.name), ["R", "W"], [disk.read_bps, disk.write_bps])?;
+            }
+        }
+
+        // MOUNTS
+        if args.mounts && shown(Section::Disks) {
+            for mount in &snapshot.mounts {
+                write!(table.cell(), "{heading}{}{reset}", truncate_left(&mount.mount_point, 20))?;
+                write!(table.cell(), "{dim}{}{reset}", mount.fs)?;
+                table.cell().push_str(&mem_bar(theme, mount.used, 0, mount.total, 10, units));
+                table.end_row();
+            }
+            table.write(out, &[]);
+        }
+
+        // EXTRA COMPONENTS
+        if args.extra && shown(Section::Extra) {
+            for comp in &snapshot.components {
+                write!(table.cell(), "{info}{}{reset} ", comp.name)?;
+                let temps = table.cell();
+                for (i, &temp) in comp.temps_c.iter().enumerate() {
+                    if i > 0 {
+                        temps.push_str(", ");
+                    }
+                    write!(temps, "{}{}{deg}{temp_unit}{reset}", style.temp_col(temp, comp.kind), disp_temp(temp))?;
+                }
+                table.end_row();
+            }
+            for (gpu, temps) in snapshot.gpus.iter().map(|gpu| (gpu, gpu_temps(gpu))).filter(|(_, temps)| !temps.is_empty()) {
+                write!(table.cell(), "{info}{}{reset} ", gpu.name)?;
+                table.cell().push_str(&temps.join(", "));
+                table.end_row();
+            }
+            table.write(out, &[Align::Left, Align::Right]);
+
+            // PER PACKAGE POWER
+            for package in &snapshot.cpu.packages {
+                write!(table.cell(), "{info}{}{reset} ", package.name)?;
+                let power = table.cell();
+                match package.power_w {
+                    Some(watts) => write!(power, "{}W", watts.round() as u32)?,
+                    None => power.push_str("--W"),
+                }
+                if let Some(limit) = package.limit_w {
+                    write!(power, "{dim}/{reset}{}W", limit.round() as u32)?;
+                }
+                table.end_row();
+            }
+            table.write(out, &[Align::Left, Align::Right]);
+
+            // VOLTAGES AND POWER
+            let mut last_chip = None;
+            for rail in &snapshot.rails {
+                // the chip is only named on its first row
+                let chip = if last_chip == Some(&rail.chip) { "" } else { &rail.chip };
+                last_chip = Some(&rail.chip);
+                let col = if snapshot.sensors_stale { dim } else if rail.out_of_range() { alert_col } else { "" };
+                let decimals = if rail.unit == RailUnit::Watts { 1 } else { 2 };
+                write!(table.cell(), "{info}{chip}{reset}")?;
+                write!(table.cell(), "{dim}{}{reset} ", rail.label)?;
+                write!(table.cell(), "{col}{:.decimals$}{}{reset}", rail.value, rail.unit.symbol())?;
+                table.end_row();
+            }
+            table.write(out, &[Align::Left, Align::Left, Align::Right]);
+
+            // PRESSURE STALLS
+            if let Some(psi) = &snapshot.psi {
+                // `full` of the cpu file is always 0 system wide, only cgroups make use of it
+                let pressures: Vec<String> = [("cpu", &psi.cpu, false), ("mem", &psi.memory, true), ("io", &psi.io, true)].into_iter().filter_map(|(name, pressure, show_full)| {
+                    let pressure = pressure.as_ref()?;
+                    let full = pressure.full.filter(|_| show_full).map_or(String::new(), |full| format!(" {dim}({full:.1}%){reset}"));
+                    Some(format!("{name} {}{:.1}%{reset}{full}", theme.bar(pressure.some.round() as u32), pressure.some))
+                }).collect();
+                writeln!(out, "{info}PSI{reset}  {}", pressures.join("  "))?;
+            }
+
+            // SOCKETS
+            if let Some(conns) = &snapshot.connections {
+                writeln!(out, "{info}CONN{reset} {} established  {} listening  {dim}{} udp{reset}", conns.established, conns.listening, conns.udp)?;
+            }
+        }
+        // PROCESSES
+        if !snapshot.processes.is_empty() {
+            // the VRAM column only exists when some process is on a GPU that reports it
+            let on_gpu = snapshot.processes.iter().any(|proc| proc.vram.is_some());
+            for proc in &snapshot.processes {
+                // names are cut at 15 characters, where the kernel cuts `comm`
+                let name = proc.name.char_indices().nth(15).map_or(&proc.name[..], |(end, _)| &proc.name[..end]);
+                write!(table.cell(), "{heading}PROC{reset}")?;
+                write!(table.cell(), "{dim}{:>7}{reset}", proc.pid)?;
+                if proc.exited {
+                    write!(table.cell(), "{dim}{name}{reset}")?;
+                    write!(table.cell(), "{dim}   --{reset}")?;
+                    table.cell();
+                    table.cell();
+                } else {
+                    let col = theme.bar(proc.cpu.min(100.0).round() as u32);
+                    table.cell().push_str(name);
+                    write!(table.cell(), "{col}{:>5.1}%{reset}", proc.cpu)?;
+                    write!(table.cell(), "{info}{:>5}{reset}/{dim}{:<5}{reset}", format_size(proc.rss, units), format_size(proc.virt, units))?;
+                    write!(table.cell(), "{rx_accent}{:>5}{reset}/{tx_accent}{:<5}{reset}", format_size(proc.read_bps, units), format_size(proc.write_bps, units))?;
+                }
+                if on_gpu {
+                    write!(table.cell(), "{gpu_accent}{:>5}{reset}", proc.vram.map_or(String::new(), |vram| format_size(vram, units)))?;
+                }
+                table.end_row();
+            }
+            table.write(out, &[]);
+        }
+        // TIMING
+        if let Some(timing) = &snapshot.timing {
+            let ms = |ms: f32| format!("{info}{ms:.1}ms{reset}");
+            let late = |taken: Option<f32>| taken.map_or(format!("{alert_col}late{reset}"), ms);
+            let hwmon = late(timing.hwmon_ms);
+            writeln!(out, "{heading}TIME{reset} collect {}  {dim}gpus{reset} {}  {dim}conns{reset} {}  {dim}hwmon{reset} {hwmon}  {dim}draw{reset} {}", ms(timing.collect_ms), late(timing.gpus_ms), late(timing.connections_ms), ms(*draw_ms))?;
+        }
+        if let Some(width) = width {
+            // most lines fit, only the ones that don't are copied into a new string
+            if out.lines().any(|line| visible_width(line) > width) {
+                *out = out.lines().map(|line| truncate_visible(line, width) + "\n").collect();
+            }
+        }
+        *draw_ms = start.elapsed().as_secs_f32() * 1000.0;
+        Ok(())
+    }
+
+    /// `--compact`, two lines for a small tmux pane: CPU usage, temp and core clock bars with the RAM bar,
+    /// then the GPU `g` is on with its VRAM bar and the network rates. The bars take whatever room `width` leaves
+    #[must_use]
+    pub fn compact(&self, snapshot: &Snapshot, width: Option<usize>) -> String {
+        let style = &self.style;
+        let theme = &style.theme;
+        let (cpu_accent, gpu_accent, ram_accent, dim, reset) = (theme.cpu_accent(), theme.gpu_accent(), theme.ram_accent(), theme.dim(), theme.reset());
+        let (rx_accent, tx_accent, glyphs) = (theme.rx(), theme.tx(), glyphs::get());
+        let (units, width) = (style.units, width.unwrap_or(80));
+        let temp = |celsius: Option<u32>, kind: TempKind| celsius.map_or(format!("{dim}  --{reset}"), |t| format!("{}{:>4}{}{}{reset}", style.temp_col(t, kind), style.temp(t), glyphs.degree, style.temp_unit()));
+        let bar_width = style.bar_width.unwrap_or((width / 8).clamp(4, 20) as u32);
+
+        // CPU AND RAM
+        let cpu = &snapshot.cpu;
+        let usage = cpu.usage.round() as u32;
+        let head = format!("{cpu_accent}CPU{reset} {}{usage:>3}%{reset}{}", theme.bar(usage), temp(cpu.temp_c, TempKind::Cpu));
+        let memory = &snapshot.memory;
+        let ram = format!("{ram_accent}RAM{reset} {}", mem_bar(theme, memory.ram_used, 0, memory.ram_total, bar_width, units));
+        let freqs: Vec<u32> = cpu.cores.iter().filter_map(|core| core.max_freq_mhz.filter(|&max| max > 0).map(|max| (core.freq_mhz as f32 / max as f32 * 100.0).round().min(100.0) as u32)).collect();
+        // the clock bars get what's left between the two, a pane too narrow for even one leaves them out
+        let room = width.saturating_sub(visible_width(&head) + visible_width(&ram) + 3);
+        let freqs = if freqs.is_empty() || room == 0 { String::new() } else { format!(" {}", style.bars(&fit_bars(&freqs, room))) };
+        let line = format!("{head}{freqs}  {ram}");
+        let mut out = truncate_visible(&line, width) + "\n";
+
+        // GPU AND NETWORK
+        let rx = snapshot.networks.iter().map(|net| net.rx_bps).sum();
+        let tx = snapshot.networks.iter().map(|net| net.tx_bps).sum();
+        // no room for the frame's resumed line, the rates just aren't there
+        let rate = |bps: u64| if snapshot.resumed_after_s.is_some() { "--".to_string() } else { format_size(bps, units) };
+        let net = format!("{rx_accent}{}{reset}{} {tx_accent}{}{reset}{}", glyphs.rx, rate(rx), glyphs.tx, rate(tx));
+        let line = match snapshot.gpus.get(self.gpu_shown.unwrap_or(0)) {
+            Some(gpu) => {
+                let power = gpu.power_w.map_or(format!("{dim}  --{reset}"), |power| format!(" {}{power:>3}W{reset}", style.opt_col(gpu.power_limit_w.filter(|&max| max > 0).map(|max| (power as f32 / max as f32 * 100.0).round() as u32))));
+                let vram = match gpu.vram_used.zip(gpu.vram_total) {
+                    Some((used, total)) => mem_bar(theme, used, 0, total, bar_width, units),
+                    None => format!("{dim}--{reset}"),
+                };
+                format!("{gpu_accent}GPU{reset} {}{:>3}%{reset}{}{power}  {ram_accent}VRAM{reset} {vram}  {net}", style.opt_col(gpu.usage), or_dash(gpu.usage), temp(gpu.temp_c, TempKind::Gpu))
+            }
+            None => net,
+        };
+        out += &(truncate_visible(&line, width) + "\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collect::{Timing, cpu::{CoreSample, CpuSample}, gpu::{EccSample, FanSample, PcieSample}, host::HostSample, hwmon::{FanReading, RailReading}, memory::{Compressed, HugePages, MemorySample, NumaNode}, processes::ProcessSample, procstat::ActivitySample, throttle::ThrottleSample};
+
+    #[test]
+    fn format_count_compacts() {
+        assert_eq!(format_count(9999), "9999");
+        assert_eq!(format_count(12_345), "12.3k");
+        assert_eq!(format_count(123_456), "123k");
+        assert_eq!(format_count(1_234_567), "1.2M");
+        // rounding up to the next arm's width
+        assert_eq!(format_count(99_949), "99.9k");
+        assert_eq!(format_count(99_950), "100k");
+        assert_eq!(format_count(99_999), "100k");
+        assert_eq!(format_count(999_499), "999k");
+        assert_eq!(format_count(999_999), "1.0M");
+        assert_eq!(format_count(99_999_999), "100M");
+        assert!((0..999_999_999).step_by(997).all(|count| format_count(count).len() <= 5));
+    }
+
+    #[test]
+    fn format_link_speed_units() {
+        assert_eq!(format_link_speed(100), "100M");
+        assert_eq!(format_link_speed(1000), "1G");
+        assert_eq!(format_link_speed(2500), "2.5G");
+        assert_eq!(format_link_speed(10_000), "10G");
+    }
+
+    #[test]
+    fn format_uptime_units() {
+        assert_eq!(format_uptime(59), "0m");
+        assert_eq!(format_uptime(4 * 3600 + 12 * 60), "4h 12m");
+        assert_eq!(format_uptime(3 * 86400 + 4 * 3600 + 12 * 60 + 5), "3d 4h 12m");
+        assert_eq!(format_uptime(86400), "1d 0h 0m");
+    }
+
+    #[test]
+    fn rate_history_smooths() {
+        let mut history = RateHistory::default();
+        assert_eq!(history.push(0), 0);
+        assert_eq!(history.push(300), 150);
+        assert_eq!(history.push(0), 100);
+        assert_eq!(history.push(0), 100);
+        assert_eq!(history.push(0), 0);
+        assert_eq!(history.peak, 150);
+    }
+
+    #[test]
+    fn truncate_left_keeps_the_end() {
+        assert_eq!(truncate_left("/home", 6), "/home");
+        assert_eq!(truncate_left("/home/george/data", 15), "…me/george/data");
+    }
+
+    #[test]
+    fn temp_row_alignment() {
+        // (cores, temps, temps drawn, core row padding, temp row padding)
+        for (cores, temps, drawn, core_pad, temp_pad) in [(16, 8, 16, 0, 0), (8, 0, 0, 0, 8), (8, 12, 12, 4, 0)] {
+            let temps = align_temps(&vec![50; temps], cores);
+            let columns = cores.max(temps.len());
+            assert_eq!(temps.len(), drawn);
+            assert_eq!(bar_pad(cores, columns), core_pad);
+            assert_eq!(bar_pad(temps.len(), columns), temp_pad);
+        }
+        assert_eq!(align_temps(&[40, 60], 4), [40, 60, 40, 60]);
+    }
+
+    #[test]
+    fn truncate_visible_keeps_colors() {
+        assert_eq!(truncate_visible("NETW eth0", 20), "NETW eth0");
+        assert_eq!(truncate_visible("NETW eth0", 6), "NETW …");
+        assert_eq!(truncate_visible("\x1b[96mNETW\x1b[0m eth0", 3), "\x1b[96mNE…\x1b[0m");
+    }
+
+    #[test]
+    fn beside_pads_the_left_block() {
+        let mut out = "above\n".to_string();
+        beside(&mut out, " CPU 5%\n RAM [█  ]\n\n", " GPU 9%\n", 2);
+        assert_eq!(out, "above\n CPU 5%      GPU 9%\n RAM [█  ]\n\n");
+    }
+
+    #[test]
+    fn fit_bars_keeps_the_busiest() {
+        assert_eq!(*fit_bars(&[1, 2, 3], 4), [1, 2, 3]);
+        assert_eq!(*fit_bars(&[10, 90, 20, 30, 50, 40], 3), [90, 30, 50]);
+        assert_eq!(*fit_bars(&[1, 2, 3, 4, 5], 2), [3, 5]);
+    }
+
+    #[test]
+    fn mobo_name_without_dmi() {
+        assert_eq!(mobo_name(None), "unknown");
+    }
+
+    fn made_up_snapshot() -> Snapshot {
+        Snapshot {
+            host: HostSample::default(),
+            load: [1.0, 0.5, 0.25],
+            uptime_s: 3600,
+            activity: ActivitySample::default(),
+            cpu: CpuSample {
+                usage: 42.0,
+                temp_c: Some(61),
+                cores: (0..4).map(|_| CoreSample { usage: 42.0, freq_mhz: 3000, max_freq_mhz: Some(4000), temp_c: None }).collect(),
+                core_temps_c: vec![],
+                power_w: None,
+                power_limit_w: None,
+                energy_wh: None,
+                packages: vec![],
+                governor: None,
+                turbo: None,
+                throttle: None,
+            },
+            memory: MemorySample { ram_used: 8 << 30, ram_total: 16 << 30, ram_cached: None, ram_dirty: None, swap_used: 0, swap_total: 0, swap_in_bps: None, swap_out_bps: None, swapping: false, zram: None, zswap: None, numa: vec![], hugepages: vec![] },
+            psi: None,
+            gpus: vec![],
+            fans: vec![],
+            rails: vec![],
+            networks: vec![],
+            connections: None,
+            disks: vec![],
+            mounts: vec![],
+            processes: vec![],
+            components: vec![],
+            sensors_stale: false,
+            resumed_after_s: None,
+            timing: None,
+        }
+    }
+
+    #[test]
+    fn resumed_frames_say_so_instead_of_rates() {
+        let mut snapshot = made_up_snapshot();
+        snapshot.networks = vec![NetSample { name: "eth0".to_string(), rx_bps: 0, tx_bps: 0, rx_pps: 0, tx_pps: 0, total_rx: 0, total_tx: 0, session_rx: 0, session_tx: 0, errors: 0, wifi: None, link_mbps: None, duplex: None, link_downgraded: false }];
+        let frame = plain_frame(&snapshot, &Args::default());
+        assert!(frame.contains("0B/s"), "{frame}");
+        snapshot.resumed_after_s = Some(3 * 3600 + 12 * 60);
+        let frame = plain_frame(&snapshot, &Args::default());
+        assert_eq!(frame.lines().next(), Some("resumed after 3h 12m"));
+        let net = frame.lines().find(|line| line.starts_with("NETW")).unwrap();
+        assert!(!net.contains("B/s") && net.contains("--"), "{net}");
+    }
+
+    #[test]
+    fn compact_is_two_lines_that_fit() {
+        let style = Style { theme: Theme::plain(), units: Units::Binary, fahrenheit: false, use_ansi: false, thresholds: vec![], bar_width: None };
+        let view = View::new(style, &Args::default(), Duration::from_secs(1), false);
+        let compact = view.compact(&made_up_snapshot(), Some(80));
+        assert_eq!(compact.lines().collect::<Vec<_>>(), [format!("CPU  42%  61{}C {}  RAM [{}{}    ] 8G/16G", glyphs::get().degree, percent_bar(75).repeat(4), glyphs::get().full.repeat(5), glyphs::get().slider[0]), format!("{}0B {}0B", glyphs::get().rx, glyphs::get().tx)]);
+        for width in 20..=160 {
+            let compact = view.compact(&made_up_snapshot(), Some(width));
+            assert_eq!(compact.lines().count(), 2);
+            assert!(compact.lines().all(|line| visible_width(line) <= width), "{width}: {compact}");
+        }
+    }
+
+    #[test]
+    fn footer_says_when_it_is_paused() {
+        let style = Style { theme: Theme::plain(), units: Units::Binary, fahrenheit: false, use_ansi: false, thresholds: vec![], bar_width: None };
+        let spinner = glyphs::get().spinner[1];
+        let footer = style.footer(false, Duration::from_millis(500), Some(12.4), 9, None);
+        assert!(footer.ends_with(&format!("  every 0.50s  collect 12ms  {spinner}\n")), "{footer}");
+        let footer = style.footer(true, Duration::from_millis(500), None, 9, Some(80));
+        assert!(footer.ends_with(&format!("  PAUSED  {spinner}\n")), "{footer}");
+    }
+
+    fn plain_frame(snapshot: &Snapshot, args: &Args) -> String {
+        let style = Style { theme: Theme::plain(), units: Units::Binary, fahrenheit: false, use_ansi: false, thresholds: vec![], bar_width: None };
+        let mut frame = String::new();
+        View::new(style, args, Duration::from_secs(1), true).frame(&mut frame, snapshot, true, args, &Alerts::new(vec![]), Some(80)).unwrap();
+        frame
+    }
+
+    #[test]
+    fn config_thresholds_and_bar_width_win() {
+        let theme = Theme::new(crate::theme::Preset::Default, &[], false, false);
+        let style = Style { theme, units: Units::Binary, fahrenheit: false, use_ansi: true, thresholds: vec![(TempKind::Cpu, [70, 80, 90])], bar_width: Some(4) };
+        assert_eq!(style.temp_col(65, TempKind::Cpu), style.theme.heat(65, [70, 80, 90]));
+        assert_ne!(style.temp_col(65, TempKind::Cpu), style.theme.heat(65, TempKind::Cpu.thresholds()));
+        assert_eq!(style.temp_col(65, TempKind::Gpu), style.theme.heat(65, TempKind::Gpu.thresholds()));
+        let style = Style { theme: Theme::plain(), use_ansi: false, ..style };
+        let mut frame = String::new();
+        View::new(style, &Args::default(), Duration::from_secs(1), true).frame(&mut frame, &made_up_snapshot(), true, &Args::default(), &Alerts::new(vec![]), Some(120)).unwrap();
+        let ram = frame.lines().find(|line| line.starts_with(" RAM")).unwrap();
+        let bar = ram.split_once('[').and_then(|(_, rest)| rest.split_once(']')).unwrap().0;
+        assert_eq!(visible_width(bar), 4, "{ram}");
+    }
+
+    #[test]
+    fn redrawing_a_paused_snapshot_holds_still() {
+        let style = Style { theme: Theme::plain(), units: Units::Binary, fahrenheit: false, use_ansi: false, thresholds: vec![], bar_width: None };
+        let args = Args { graph: true, ..Args::default() };
+        let mut view = View::new(style, &args, Duration::from_secs(1), false);
+        let mut frame = |fresh| {
+            let mut frame = "left over".to_string();
+            view.frame(&mut frame, &made_up_snapshot(), fresh, &args, &Alerts::new(vec![]), Some(120)).unwrap();
+            frame
+        };
+        let first = frame(true);
+        assert_eq!(frame(false), first);
+        assert_eq!(frame(false), first);
+        assert_ne!(frame(true), first);
+    }
+
+    #[test]
+    fn frame_of_a_made_up_snapshot() {
+        let frame = plain_frame(&made_up_snapshot(), &Args::default());
+        let lines: Vec<&str> = frame.lines().collect();
+        assert_eq!(lines[0], "LOAD  1.0   0.5   0.2  up 1h 0m");
+        assert_eq!(lines[1], " CPU 42%  61°C");
+        assert!(lines[2].starts_with(" RAM ["), "{}", lines[2]);
+        assert!(lines[2].ends_with("] 8G/16G  no swap"), "{}", lines[2]);
+    }
+
+    #[test]
+    fn gpu_extra_temps_only_when_exposed() {
+        let mut snapshot = made_up_snapshot();
+        let gpu = |mem_temp_c, hotspot_c| GpuSample { name: "RTX 3090".to_string(), usage: Some(90), temp_c: Some(64), mem_temp_c, hotspot_c, power_w: Some(300), power_limit_w: Some(350), ..Default::default() };
+        snapshot.gpus = vec![gpu(None, None)];
+        assert!(plain_frame(&snapshot, &Args::default()).contains(" GPU 90%  64°C 300W/350W"));
+        snapshot.gpus = vec![gpu(Some(96), Some(75))];
+        let args = Args { extra: true, ..Args::default() };
+        let frame = plain_frame(&snapshot, &args);
+        assert!(frame.contains(" GPU 90%  64°C (mem 96°C hot 75°C) 300W/350W"), "{frame}");
+        assert!(frame.contains("RTX 3090  mem 96°C, hot 75°C"), "{frame}");
+    }
+
+    #[test]
+    fn lowered_gpu_power_limit_shows_the_cap() {
+        let mut snapshot = made_up_snapshot();
+        let gpu = |power_default_limit_w| GpuSample { name: "RTX 3090".to_string(), usage: Some(50), temp_c: Some(60), power_w: Some(112), power_limit_w: Some(220), power_default_limit_w, ..Default::default() };
+        snapshot.gpus = vec![gpu(Some(250))];
+        assert!(plain_frame(&snapshot, &Args::default()).contains(" 112W/220W (cap 250W)\n"));
+        snapshot.gpus = vec![gpu(Some(220))];
+        assert!(plain_frame(&snapshot, &Args::default()).contains(" 112W/220W\n"));
+    }
+
+    #[test]
+    fn ecc_baselines_follow_the_bus_id() {
+        let mut snapshot = made_up_snapshot();
+        let gpu = |bus_id: &str, uncorrected| GpuSample { name: "A100".to_string(), bus_id: Some(bus_id.to_string()), ecc: Some(EccSample { enabled: true, corrected: None, uncorrected: Some(uncorrected) }), ..Default::default() };
+        let style = Style { theme: Theme::plain(), units: Units::Binary, fahrenheit: false, use_ansi: false, thresholds: vec![], bar_width: None };
+        let args = Args::default();
+        let mut view = View::new(style, &args, Duration::from_secs(1), false);
+        let mut frame = String::new();
+        snapshot.gpus = vec![gpu("0000:01:00.0", 0), gpu("0000:02:00.0", 5)];
+        view.frame(&mut frame, &snapshot, true, &args, &Alerts::new(vec![]), Some(80)).unwrap();
+        assert_eq!(frame.matches("ECC ok").count(), 2, "{frame}");
+        // the first card fell off the bus, the second one's old errors aren't new to it
+        snapshot.gpus = vec![gpu("0000:02:00.0", 5)];
+        view.frame(&mut frame, &snapshot, true, &args, &Alerts::new(vec![]), Some(80)).unwrap();
+        assert!(frame.contains("ECC ok"), "{frame}");
+        snapshot.gpus = vec![gpu("0000:02:00.0", 7)];
+        view.frame(&mut frame, &snapshot, true, &args, &Alerts::new(vec![]), Some(80)).unwrap();
+        assert!(frame.contains("ECC 2 uncorrected!"), "{frame}");
+    }
+
+    #[test]
+    fn pcie_without_lanes_is_left_uncolored() {
+        let mut snapshot = made_up_snapshot();
+        let pcie = PcieSample { rx_bps: Some(1 << 30), tx_bps: Some(0), max_bps: 32 << 30, link_bps: 0, max_link: None, link: None };
+        snapshot.gpus = vec![GpuSample { name: "RTX 3090".to_string(), pcie: Some(pcie), ..Default::default() }];
+        let style = Style { theme: Theme::new(crate::theme::Preset::Default, &[], false, false), units: Units::Binary, fahrenheit: false, use_ansi: true, thresholds: vec![], bar_width: None };
+        let dim = style.theme.dim().to_string();
+        let args = Args::default();
+        let mut frame = String::new();
+        View::new(style, &args, Duration::from_secs(1), true).frame(&mut frame, &snapshot, true, &args, &Alerts::new(vec![]), Some(80)).unwrap();
+        let row = frame.lines().find(|line| line.contains("PCIE")).unwrap();
+        assert!(row.contains(&format!("{dim}{:>6}", "1G")), "{row:?}");
+    }
+
+    #[test]
+    fn swap_rates_only_while_swapping() {
+        let mut snapshot = made_up_snapshot();
+        snapshot.memory.swap_total = 4 << 30;
+        snapshot.memory.swap_in_bps = Some(0);
+        snapshot.memory.swap_out_bps = Some(0);
+        assert!(plain_frame(&snapshot, &Args::default()).lines().nth(2).unwrap().ends_with("] 8G/16G  0B/4G"));
+        snapshot.memory.swap_out_bps = Some(3 << 20);
+        assert!(plain_frame(&snapshot, &Args::default()).lines().nth(2).unwrap().ends_with("] 8G/16G  0B/4G in 0B/s out 3M/s"));
+    }
+
+    #[test]
+    fn zram_row_only_with_zram() {
+        let mut snapshot = made_up_snapshot();
+        assert!(!plain_frame(&snapshot, &Args::default()).contains("zram"));
+        snapshot.memory.zram = Some(Compressed { original: 3 << 30, compressed: 1 << 30 });
+        let to = glyphs::get().to;
+        assert_eq!(plain_frame(&snapshot, &Args::default()).lines().nth(3), Some(format!("     zram 3G{to}1G (3.0x)").as_str()));
+    }
+
+    #[test]
+    fn kernel_activity_is_dashed_until_there_is_a_rate() {
+        let mut snapshot = made_up_snapshot();
+        let args = Args { extra: true, ..Args::default() };
+        assert_eq!(plain_frame(&snapshot, &args).lines().nth(2), Some(" ctx --  irq --  forks --"));
+        snapshot.activity = ActivitySample { context_switches: Some(84_321), interrupts: Some(21_000), forks: Some(12) };
+        assert_eq!(plain_frame(&snapshot, &args).lines().nth(2), Some(" ctx 84.3k/s  irq 21.0k/s  forks 12/s"));
+    }
+
+    #[test]
+    fn rails_are_grouped_by_chip() {
+        let mut snapshot = made_up_snapshot();
+        let rail = |chip: &str, label: &str, unit, value| RailReading { chip: chip.to_string(), label: label.to_string(), unit, value };
+        snapshot.rails = vec![rail("corsairpsu", "+12V", RailUnit::Volts, 12.07), rail("corsairpsu", "power total", RailUnit::Watts, 312.44), rail("nct6798", "Vcore", RailUnit::Volts, 1.2)];
+        let frame = plain_frame(&snapshot, &Args { extra: true, ..Args::default() });
+        let lines: Vec<&str> = frame.lines().map(str::trim_end).filter(|line| line.ends_with('V') || line.ends_with('W')).collect();
+        assert_eq!(lines, vec!["corsairpsu +12V         12.07V", "           power total  312.4W", "nct6798    Vcore         1.20V"]);
+    }
+
+    #[test]
+    fn system_and_gpu_fans_share_a_wrapping_row() {
+        let mut snapshot = made_up_snapshot();
+        let fan = |label: &str, rpm, percent| FanReading { label: label.to_string(), rpm, max_rpm: None, percent };
+        snapshot.fans = vec![fan("CPU Fan", 1200, Some(40)), fan("Chassis Fan 1", 0, Some(0)), fan("Chassis Fan 2", 950, None), fan("Chassis Fan 3", 900, Some(35))];
+        snapshot.gpus = vec![GpuSample { name: "RTX 3090".to_string(), fans: Some(vec![FanSample { percent: Some(30), rpm: None }]), ..Default::default() }];
+        let frame = plain_frame(&snapshot, &Args::default());
+        let start = frame.find("FANS").unwrap();
+        let fans: Vec<&str> = frame[start..].lines().take(2).collect();
+        assert_eq!(fans, vec!["FANS CPU Fan 1200rpm (40%), Chassis Fan 1 0rpm (0%), Chassis Fan 2 950rpm,", "     Chassis Fan 3 900rpm (35%), GPU (30%)"]);
+        assert_eq!(frame.matches("FANS").count(), 1);
+    }
+
+    #[test]
+    fn timing_row_marks_late_hwmon() {
+        let mut snapshot = made_up_snapshot();
+        snapshot.timing = Some(Timing { collect_ms: 1.25, gpus_ms: Some(0.5), connections_ms: Some(0.0), hwmon_ms: None });
+        let frame = plain_frame(&snapshot, &Args::default());
+        assert_eq!(frame.lines().last(), Some("TIME collect 1.2ms  gpus 0.5ms  conns 0.0ms  hwmon late  draw 0.0ms"));
+    }
+
+    #[test]
+    fn numa_nodes_get_a_bar_each() {
+        let mut snapshot = made_up_snapshot();
+        let single = plain_frame(&snapshot, &Args::default());
+        assert!(!single.contains(" N0"));
+        snapshot.memory.numa = vec![NumaNode { node: 0, used: 1 << 30, cached: 0, total: 8 << 30 }, NumaNode { node: 1, used: 7 << 30, cached: 0, total: 8 << 30 }];
+        let frame = plain_frame(&snapshot, &Args::default());
+        let nodes: Vec<&str> = frame.lines().filter(|line| line.starts_with("  N")).collect();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes[0].starts_with("  N0 [") && nodes[0].ends_with("] 1G/8G"), "{}", nodes[0]);
+        assert!(nodes[1].starts_with("  N1 [") && nodes[1].ends_with("] 7G/8G"), "{}", nodes[1]);
+        assert_eq!(frame.lines().count(), single.lines().count() + 2);
+    }
+
+    #[test]
+    fn hugepages_annotate_the_ram_line() {
+        let mut snapshot = made_up_snapshot();
+        assert!(!plain_frame(&snapshot, &Args::default()).contains("huge"));
+        snapshot.memory.hugepages = vec![HugePages { size: 2 << 20, total: 16, free: 4 }, HugePages { size: 1 << 30, total: 2, free: 2 }];
+        let frame = plain_frame(&snapshot, &Args::default());
+        let ram = frame.lines().find(|line| line.starts_with(" RAM")).unwrap();
+        assert!(ram.ends_with("  huge 12/16 x2M 0/2 x1G"), "{ram}");
+        assert!(ram.contains(glyphs::get().reserved));
+    }
+
+    #[test]
+    fn process_table_gains_vram_from_the_gpus() {
+        let mut snapshot = made_up_snapshot();
+        let proc = |pid, name: &str, vram, exited| ProcessSample { pid, name: name.to_string(), cpu: 20.0, rss: 1 << 30, virt: 2 << 30, read_bps: 0, write_bps: 0, kernel: false, vram, exited };
+        snapshot.processes = vec![proc(100, "chrome", Some(1 << 30), false), proc(200, "make", None, false)];
+        let frame = plain_frame(&snapshot, &Args::default());
+        let lines: Vec<&str> = frame.lines().filter(|line| line.starts_with("PROC")).map(str::trim_end).collect();
+        assert!(lines[0].ends_with("0B/0B       1G"), "{}", lines[0]);
+        assert!(lines[1].ends_with("0B/0B"), "{}", lines[1]);
+        snapshot.processes.push(proc(300, "<exited>", Some(512 << 20), true));
+        let frame = plain_frame(&snapshot, &Args::default());
+        let exited = frame.lines().find(|line| line.contains("<exited>")).unwrap();
+        assert!(exited.contains("--") && exited.trim_end().ends_with("512M") && !exited.contains('%'), "{exited}");
+        snapshot.processes.truncate(2);
+        snapshot.processes.iter_mut().for_each(|proc| proc.vram = None);
+        assert!(plain_frame(&snapshot, &Args::default()).lines().filter(|line| line.starts_with("PROC")).all(|line| line.trim_end().ends_with("0B/0B")));
+    }
+
+    #[test]
+    fn throttling_tags_the_cpu_line() {
+        let mut snapshot = made_up_snapshot();
+        snapshot.cpu.throttle = Some(ThrottleSample { active: true, events: 3, heuristic: false });
+        assert_eq!(plain_frame(&snapshot, &Args::default()).lines().nth(1), Some(" CPU 42%  61°C THROTTLE 3x throttled"));
+        snapshot.cpu.throttle = Some(ThrottleSample { active: false, events: 1, heuristic: true });
+        assert_eq!(plain_frame(&snapshot, &Args::default()).lines().nth(1), Some(" CPU 42%  61°C 1x throttled?"));
+    }
+}
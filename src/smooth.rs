@@ -0,0 +1,92 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use crate::collect::Snapshot;
+
+/// Exponential moving average of the rates that jump around from frame to frame, for `--smooth`
+pub struct Smoother {
+    /// weight of the newest sample, 1 is no smoothing at all
+    alpha: f64,
+    /// interval and time of the last snapshot
+    last: Option<(Duration, Instant)>,
+    previous: HashMap<String, f64>,
+    averages: HashMap<String, f64>,
+}
+
+impl Smoother {
+    pub fn new(alpha: f64) -> Self {
+        Self { alpha, last: None, previous: HashMap::new(), averages: HashMap::new() }
+    }
+
+    /// Replaces per-core usage, GPU usage and network and disk rates of `snapshot`, taken at `now` with `interval` between
+    /// refreshes, by their averages. Only the display gets these, JSON, CSV and recordings are written before
+    pub fn apply(&mut self, snapshot: &mut Snapshot, interval: Duration, now: Instant) {
+        // the monotonic clock doesn't see a suspend, the snapshot does
+        if snapshot.resumed_after_s.is_some() {
+            self.last = None;
+        }
+        self.start(interval, now);
+        for (i, core) in snapshot.cpu.cores.iter_mut().enumerate() {
+            core.usage = self.average(format!("core{i}"), core.usage as f64) as f32;
+        }
+        for (i, gpu) in snapshot.gpus.iter_mut().enumerate() {
+            if let Some(usage) = &mut gpu.usage {
+                *usage = self.average(format!("gpu{i}"), *usage as f64).round() as u32;
+            }
+        }
+        for net in &mut snapshot.networks {
+            net.rx_bps = self.average(format!("rx {}", net.name), net.rx_bps as f64).round() as u64;
+            net.tx_bps = self.average(format!("tx {}", net.name), net.tx_bps as f64).round() as u64;
+            net.rx_pps = self.average(format!("rx packets {}", net.name), net.rx_pps as f64).round() as u64;
+            net.tx_pps = self.average(format!("tx packets {}", net.name), net.tx_pps as f64).round() as u64;
+        }
+        for disk in &mut snapshot.disks {
+            disk.read_bps = self.average(format!("read {}", disk.name), disk.read_bps as f64).round() as u64;
+            disk.write_bps = self.average(format!("write {}", disk.name), disk.write_bps as f64).round() as u64;
+        }
+    }
+
+    /// Starts a tick on the averages of the last one. A changed interval or a gap of a few of them, like a pause or a suspend,
+    /// starts over from the new values. Recordings are replayed at the gaps they were taken at, so a few percent is no change
+    fn start(&mut self, interval: Duration, now: Instant) {
+        let restart = self.last.is_none_or(|(last, at)| {
+            let changed = (interval.as_secs_f64() - last.as_secs_f64()).abs() > last.as_secs_f64() / 4.0;
+            changed || now.saturating_duration_since(at) > interval * 3
+        });
+        self.previous = std::mem::take(&mut self.averages);
+        if restart {
+            self.previous.clear();
+        }
+        self.last = Some((interval, now));
+    }
+
+    /// Moves the average of `key` towards `value`, a series that's new or gone for a tick starts at its value
+    fn average(&mut self, key: String, value: f64) -> f64 {
+        let average = self.previous.get(&key).map_or(value, |average| average + self.alpha * (value - average));
+        self.averages.insert(key, average);
+        average
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_restart_after_a_gap_or_a_new_interval() {
+        let (mut smoother, start, second) = (Smoother::new(0.5), Instant::now(), Duration::from_secs(1));
+        let tick = |smoother: &mut Smoother, interval: Duration, secs: u64, value: f64| {
+            smoother.start(interval, start + Duration::from_secs(secs));
+            smoother.average("core0".to_string(), value)
+        };
+        assert_eq!(tick(&mut smoother, second, 0, 100.0), 100.0);
+        assert_eq!(tick(&mut smoother, second, 1, 0.0), 50.0);
+        assert_eq!(tick(&mut smoother, second, 2, 0.0), 25.0);
+        // paused for a while
+        assert_eq!(tick(&mut smoother, second, 10, 80.0), 80.0);
+        assert_eq!(tick(&mut smoother, second, 11, 40.0), 60.0);
+        assert_eq!(tick(&mut smoother, second * 2, 13, 0.0), 0.0);
+        // a series that wasn't there last tick starts at its value
+        smoother.start(second * 2, start + Duration::from_secs(15));
+        assert_eq!(smoother.average("gpu0".to_string(), 30.0), 30.0);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{collect::Snapshot, glyphs, fmt::{Units, format_size}};
+
+/// `CPU 23% 61° | GPU 4% 42° | RAM 14.2G/31.3G | ↓1.2M ↑80K` for status bars, `temp` converts °C to the shown unit
+#[must_use]
+pub fn line(snapshot: &Snapshot, units: Units, temp: impl Fn(u32) -> u32) -> String {
+    let mut parts = vec![format!("CPU {}", usage(Some(snapshot.cpu.usage.round() as u32), snapshot.cpu.temp_c, &temp))];
+    parts.extend(snapshot.gpus.iter().map(|gpu| format!("GPU {}", usage(gpu.usage, gpu.temp_c, &temp))));
+    let memory = &snapshot.memory;
+    parts.push(format!("RAM {}/{}", format_size(memory.ram_used, units), format_size(memory.ram_total, units)));
+    let rx = snapshot.networks.iter().map(|net| net.rx_bps).sum();
+    let tx = snapshot.networks.iter().map(|net| net.tx_bps).sum();
+    let glyphs = glyphs::get();
+    parts.push(format!("{}{} {}{}", glyphs.down, format_size(rx, units), glyphs.up, format_size(tx, units)));
+    parts.join(" | ")
+}
+
+/// `hwtop: 23% 61° | gpu 4% 42°` for `--title`, kept short since tab lists cut titles off
+#[must_use]
+pub fn title(snapshot: &Snapshot, temp: impl Fn(u32) -> u32) -> String {
+    let mut title = format!("hwtop: {}", usage(Some(snapshot.cpu.usage.round() as u32), snapshot.cpu.temp_c, &temp));
+    for gpu in &snapshot.gpus {
+        title += &format!(" | gpu {}", usage(gpu.usage, gpu.temp_c, &temp));
+    }
+    title
+}
+
+/// `23% 61°`, the temperature is left out when there's none
+fn usage(usage: Option<u32>, temp_c: Option<u32>, temp: impl Fn(u32) -> u32) -> String {
+    let usage = usage.map_or("--".to_string(), |usage| format!("{usage}%"));
+    let temp = temp_c.map_or(String::new(), |celsius| format!(" {}{}", temp(celsius), glyphs::get().degree));
+    format!("{usage}{temp}")
+}
@@ -0,0 +1,138 @@
+use std::time::Instant;
+
+use crate::collect::Snapshot;
+
+/// Running min, max and average of one metric
+#[derive(Default)]
+pub struct Stat {
+    min: Option<f32>,
+    max: Option<f32>,
+    sum: f64,
+    count: u32,
+}
+
+impl Stat {
+    pub fn add(&mut self, value: f32) {
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        self.sum += value as f64;
+        self.count += 1;
+    }
+
+    /// `(min, avg, max)`, `None` if nothing was recorded
+    pub fn get(&self) -> Option<(f32, f32, f32)> {
+        Some((self.min?, (self.sum / self.count as f64) as f32, self.max?))
+    }
+}
+
+/// Totals of a whole session, printed on exit with `--summary`
+pub struct Summary {
+    pub started: Instant,
+    pub cpu_usage: Stat,
+    pub cpu_temp: Stat,
+    pub gpu_usage: Stat,
+    pub gpu_temp: Stat,
+    pub gpu_power: Stat,
+    /// bytes
+    pub ram: Stat,
+    pub vram: Stat,
+    pub net_rx: u64,
+    pub net_tx: u64,
+    pub disk_read: u64,
+    pub disk_written: u64,
+    /// CPU thermal throttle events, `None` when throttling can't be told
+    pub throttle_events: Option<u64>,
+    /// the events were guessed from clocks, see `ThrottleSample::heuristic`
+    pub throttle_guessed: bool,
+    /// watt-hours of the session, `None` without power readings
+    pub cpu_energy: Option<f64>,
+    /// of all GPUs together
+    pub gpu_energy: Option<f64>,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            cpu_usage: Stat::default(),
+            cpu_temp: Stat::default(),
+            gpu_usage: Stat::default(),
+            gpu_temp: Stat::default(),
+            gpu_power: Stat::default(),
+            ram: Stat::default(),
+            vram: Stat::default(),
+            net_rx: 0,
+            net_tx: 0,
+            disk_read: 0,
+            disk_written: 0,
+            throttle_events: None,
+            throttle_guessed: false,
+            cpu_energy: None,
+            gpu_energy: None,
+        }
+    }
+
+    /// Adds one tick, `delta` is the seconds it covered, with several GPUs the busiest/hottest one counts
+    pub fn record(&mut self, snapshot: &Snapshot, delta: f32) {
+        // a tick spanning a suspend has no rates and its readings are from the moment of waking
+        if snapshot.resumed_after_s.is_some() {
+            return;
+        }
+        self.cpu_usage.add(snapshot.cpu.usage);
+        if let Some(throttle) = &snapshot.cpu.throttle {
+            self.throttle_events = Some(throttle.events);
+            self.throttle_guessed = throttle.heuristic;
+        }
+        if let Some(temp) = snapshot.cpu.temp_c {
+            self.cpu_temp.add(temp as f32);
+        }
+        let gpus = &snapshot.gpus;
+        let gpu_max = |value: fn(&crate::collect::gpu::GpuSample) -> Option<u64>| gpus.iter().filter_map(value).max();
+        if let Some(usage) = gpu_max(|gpu| gpu.usage.map(u64::from)) {
+            self.gpu_usage.add(usage as f32);
+        }
+        if let Some(temp) = gpu_max(|gpu| gpu.temp_c.map(u64::from)) {
+            self.gpu_temp.add(temp as f32);
+        }
+        if let Some(power) = gpu_max(|gpu| gpu.power_w.map(u64::from)) {
+            self.gpu_power.add(power as f32);
+        }
+        // the counters are kept by the collectors, so the newest is the session's
+        self.cpu_energy = snapshot.cpu.energy_wh;
+        self.gpu_energy = gpus.iter().filter_map(|gpu| gpu.energy_wh).reduce(|a, b| a + b);
+        self.ram.add(snapshot.memory.ram_used as f32);
+        if let Some(vram) = gpu_max(|gpu| gpu.vram_used) {
+            self.vram.add(vram as f32);
+        }
+        let bytes = |bps: u64| (bps as f32 * delta) as u64;
+        for net in &snapshot.networks {
+            self.net_rx += bytes(net.rx_bps);
+            self.net_tx += bytes(net.tx_bps);
+        }
+        for disk in &snapshot.disks {
+            self.disk_read += bytes(disk.read_bps);
+            self.disk_written += bytes(disk.write_bps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stat_min_avg_max() {
+        let mut stat = Stat::default();
+        assert_eq!(stat.get(), None);
+        for value in [40.0, 10.0, 70.0] {
+            stat.add(value);
+        }
+        assert_eq!(stat.get(), Some((10.0, 40.0, 70.0)));
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{collect::{Snapshot, disks::DiskSample, gpu::GpuSample, net::NetSample}, fmt::{Units, format_size}};
+
+/// One reading for a `{key}`, rendered as `--` when the machine doesn't have it
+enum Value {
+    /// shown with this many decimals unless the key asks for others
+    Number(f64, usize),
+    /// bytes or bytes per second, shown like the rest of hwtop as `14.2G`
+    Size(u64),
+    /// °C, converted for `--fahrenheit`
+    Temp(u32),
+    Text(String),
+    Missing,
+}
+
+impl Value {
+    fn number(value: Option<impl Into<f64>>, decimals: usize) -> Self {
+        value.map_or(Self::Missing, |value| Self::Number(value.into(), decimals))
+    }
+
+    fn temp(temp_c: Option<u32>) -> Self {
+        temp_c.map_or(Self::Missing, Self::Temp)
+    }
+
+    fn size(bytes: Option<u64>) -> Self {
+        bytes.map_or(Self::Missing, Self::Size)
+    }
+}
+
+/// A `{key}` of the registry, `value` reads it from a snapshot, a GPU, a disk or an interface
+struct Key<T> {
+    name: &'static str,
+    about: &'static str,
+    value: fn(&T) -> Value,
+}
+
+/// `{cpu.usage}` and the rest of the keys of the whole machine
+const MACHINE: &[Key<Snapshot>] = &[
+    Key { name: "cpu.usage", about: "CPU usage in %", value: |s| Value::Number(s.cpu.usage.into(), 0) },
+    Key { name: "cpu.temp", about: "CPU package temperature", value: |s| Value::temp(s.cpu.temp_c) },
+    Key { name: "cpu.freq", about: "average core clock in MHz", value: |s| Value::Number(s.cpu.cores.iter().map(|core| core.freq_mhz as f64).sum::<f64>() / s.cpu.cores.len().max(1) as f64, 0) },
+    Key { name: "cpu.power_w", about: "CPU package power in W", value: |s| Value::number(s.cpu.power_w, 1) },
+    Key { name: "mem.used", about: "RAM in use", value: |s| Value::Size(s.memory.ram_used) },
+    Key { name: "mem.total", about: "installed RAM", value: |s| Value::Size(s.memory.ram_total) },
+    Key { name: "mem.percent", about: "RAM in use in %", value: |s| Value::Number(s.memory.ram_used as f64 * 100.0 / s.memory.ram_total.max(1) as f64, 0) },
+    Key { name: "swap.used", about: "swap in use", value: |s| Value::Size(s.memory.swap_used) },
+    Key { name: "swap.total", about: "swap space", value: |s| Value::Size(s.memory.swap_total) },
+    Key { name: "net.rx_rate", about: "received per second over every shown interface", value: |s| Value::Size(s.networks.iter().map(|net| net.rx_bps).sum()) },
+    Key { name: "net.tx_rate", about: "sent per second over every shown interface", value: |s| Value::Size(s.networks.iter().map(|net| net.tx_bps).sum()) },
+    Key { name: "load.1", about: "1 minute load average", value: |s| Value::Number(s.load[0], 2) },
+    Key { name: "load.5", about: "5 minute load average", value: |s| Value::Number(s.load[1], 2) },
+    Key { name: "load.15", about: "15 minute load average", value: |s| Value::Number(s.load[2], 2) },
+    Key { name: "uptime_s", about: "seconds since boot", value: |s| Value::Number(s.uptime_s as f64, 0) },
+];
+
+/// `{gpu0.usage}`, GPUs are numbered in the order hwtop shows them
+const GPU: &[Key<GpuSample>] = &[
+    Key { name: "name", about: "short model name", value: |gpu| Value::Text(gpu.name.clone()) },
+    Key { name: "usage", about: "GPU usage in %", value: |gpu| Value::number(gpu.usage, 0) },
+    Key { name: "temp", about: "GPU temperature", value: |gpu| Value::temp(gpu.temp_c) },
+    Key { name: "hotspot", about: "hotspot temperature", value: |gpu| Value::temp(gpu.hotspot_c) },
+    Key { name: "power_w", about: "board power in W", value: |gpu| Value::number(gpu.power_w, 0) },
+    Key { name: "power_limit_w", about: "power limit in W", value: |gpu| Value::number(gpu.power_limit_w, 0) },
+    Key { name: "vram_used", about: "VRAM in use", value: |gpu| Value::size(gpu.vram_used) },
+    Key { name: "vram_total", about: "VRAM", value: |gpu| Value::size(gpu.vram_total) },
+];
+
+/// `{disk.nvme0n1.read_rate}`, by the name hwtop shows the disk under
+const DISK: &[Key<DiskSample>] = &[
+    Key { name: "used", about: "space in use", value: |disk| Value::Size(disk.used) },
+    Key { name: "total", about: "size", value: |disk| Value::Size(disk.total) },
+    Key { name: "read_rate", about: "read per second", value: |disk| Value::Size(disk.read_bps) },
+    Key { name: "write_rate", about: "written per second", value: |disk| Value::Size(disk.write_bps) },
+    Key { name: "util", about: "time busy in %", value: |disk| Value::number(disk.util, 0) },
+    Key { name: "temp", about: "drive temperature", value: |disk| Value::temp(disk.temp_c) },
+];
+
+/// `{net.eth0.rx_rate}`, by interface name
+const NET: &[Key<NetSample>] = &[
+    Key { name: "rx_rate", about: "received per second", value: |net| Value::Size(net.rx_bps) },
+    Key { name: "tx_rate", about: "sent per second", value: |net| Value::Size(net.tx_bps) },
+    Key { name: "total_rx", about: "received since boot", value: |net| Value::Size(net.total_rx) },
+    Key { name: "total_tx", about: "sent since boot", value: |net| Value::Size(net.total_tx) },
+];
+
+/// Where a `{key}` reads from, as indexes into the registry
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Machine(usize),
+    Gpu(usize, usize),
+    Disk(String, usize),
+    Net(String, usize),
+}
+
+impl Source {
+    fn read(&self, snapshot: &Snapshot) -> Value {
+        match self {
+            Self::Machine(key) => (MACHINE[*key].value)(snapshot),
+            Self::Gpu(index, key) => snapshot.gpus.get(*index).map_or(Value::Missing, GPU[*key].value),
+            Self::Disk(name, key) => snapshot.disks.iter().find(|disk| disk.name == *name).map_or(Value::Missing, DISK[*key].value),
+            Self::Net(name, key) => snapshot.networks.iter().find(|net| net.name == *name).map_or(Value::Missing, NET[*key].value),
+        }
+    }
+}
+
+/// `{key:>5.1}` takes an alignment (`<`, `>` or `^`), a width and a number of decimals, like Rust's format strings
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Spec {
+    align: Option<char>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Key(Source, Spec),
+}
+
+/// A parsed `--format` line, `{{` and `}}` are literal braces
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    text: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Fails on unknown keys and broken braces, the key error lists every valid key
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err("unclosed '{' in --format, '{{' is a literal brace".to_string()),
+                        }
+                    }
+                    let (key, spec) = field.split_once(':').unwrap_or((&field, ""));
+                    let source = source(key).ok_or_else(|| format!("unknown key '{{{key}}}' in --format, the keys are:\n{}", list_keys()))?;
+                    let spec = parse_spec(spec).ok_or_else(|| format!("invalid format '{spec}' of '{{{key}}}', expected something like '>5.1'"))?;
+                    parts.push(Part::Text(std::mem::take(&mut literal)));
+                    parts.push(Part::Key(source, spec));
+                }
+                '}' => return Err("unmatched '}' in --format, '}}' is a literal brace".to_string()),
+                c => literal.push(c),
+            }
+        }
+        parts.push(Part::Text(literal));
+        parts.retain(|part| *part != Part::Text(String::new()));
+        Ok(Self { text: text.to_string(), parts })
+    }
+
+    /// The template as it was given, for the config
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Fills in every key from `snapshot`, `temp` converts °C to the shown unit
+    #[must_use]
+    pub fn render(&self, snapshot: &Snapshot, units: Units, temp: impl Fn(u32) -> u32) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            let (source, spec) = match part {
+                Part::Text(text) => {
+                    line += text;
+                    continue;
+                }
+                Part::Key(source, spec) => (source, spec),
+            };
+            let text = match source.read(snapshot) {
+                Value::Number(value, decimals) => format!("{value:.*}", spec.precision.unwrap_or(decimals)),
+                Value::Size(bytes) => format_size(bytes, units),
+                Value::Temp(celsius) => temp(celsius).to_string(),
+                Value::Text(text) => text,
+                Value::Missing => "--".to_string(),
+            };
+            line += &pad(&text, spec);
+        }
+        line
+    }
+}
+
+/// A key of `hwtop get`, read raw: sizes in bytes, rates per second, temps as numbers
+pub struct Field(Source);
+
+impl Field {
+    /// Fails with the closest keys when there's no such key
+    pub fn parse(key: &str) -> Result<Self, String> {
+        if let Some(source) = source(key) {
+            return Ok(Self(source));
+        }
+        let mut near: Vec<(usize, String)> = candidates(key).into_iter().map(|candidate| (distance(key, &candidate), candidate)).filter(|(distance, _)| *distance <= 3).collect();
+        near.sort();
+        let near: Vec<String> = near.into_iter().take(3).map(|(_, candidate)| candidate).collect();
+        if near.is_empty() {
+            return Err(format!("unknown key '{key}', hwtop --list-keys lists them"));
+        }
+        Err(format!("unknown key '{key}', did you mean {}?", near.join(", ")))
+    }
+
+    /// GPU keys need the driver loaded, the rest don't
+    #[must_use]
+    pub fn needs_gpu(&self) -> bool {
+        matches!(self.0, Source::Gpu(..))
+    }
+
+    /// `None` when the machine doesn't have the reading, `temp` converts °C to the shown unit
+    #[must_use]
+    pub fn raw(&self, snapshot: &Snapshot, temp: impl Fn(u32) -> u32) -> Option<String> {
+        match self.0.read(snapshot) {
+            Value::Number(value, decimals) => Some(format!("{value:.decimals$}")),
+            Value::Size(bytes) => Some(bytes.to_string()),
+            Value::Temp(celsius) => Some(temp(celsius).to_string()),
+            Value::Text(text) => Some(text),
+            Value::Missing => None,
+        }
+    }
+}
+
+/// Every key with the GPU number, disk or interface of `key` filled in, for suggestions
+fn candidates(key: &str) -> Vec<String> {
+    let gpu = key.strip_prefix("gpu").and_then(|rest| rest.split_once('.')).map_or("0", |(gpu, _)| gpu);
+    let disk = key.strip_prefix("disk.").and_then(|rest| rest.rsplit_once('.')).map_or("<name>", |(disk, _)| disk);
+    let iface = key.strip_prefix("net.").and_then(|rest| rest.rsplit_once('.')).map_or("<iface>", |(iface, _)| iface);
+    MACHINE.iter().map(|entry| entry.name.to_string())
+        .chain(GPU.iter().map(|entry| format!("gpu{gpu}.{}", entry.name)))
+        .chain(DISK.iter().map(|entry| format!("disk.{disk}.{}", entry.name)))
+        .chain(NET.iter().map(|entry| format!("net.{iface}.{}", entry.name)))
+        .collect()
+}
+
+/// Levenshtein distance, keys are short enough for the plain two row version
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            row.push((previous[j] + usize::from(a != b)).min(previous[j + 1] + 1).min(row[j] + 1));
+        }
+        previous = row;
+    }
+    previous[b.len()]
+}
+
+/// `cpu.usage`, `gpu0.usage`, `disk.nvme0n1.read_rate` or `net.eth0.rx_rate`, disk and interface names may have dots in them,
+/// GPU keys may be written with a dot for the underscore (`gpu0.vram.used`)
+fn source(key: &str) -> Option<Source> {
+    if let Some(i) = MACHINE.iter().position(|entry| entry.name == key) {
+        return Some(Source::Machine(i));
+    }
+    if let Some((gpu, name)) = key.strip_prefix("gpu").and_then(|rest| rest.split_once('.')) {
+        return Some(Source::Gpu(gpu.parse().ok()?, GPU.iter().position(|entry| entry.name == name || entry.name.replace('_', ".") == name)?));
+    }
+    if let Some((disk, name)) = key.strip_prefix("disk.").and_then(|rest| rest.rsplit_once('.')) {
+        return Some(Source::Disk(disk.to_string(), DISK.iter().position(|entry| entry.name == name)?));
+    }
+    let (iface, name) = key.strip_prefix("net.")?.rsplit_once('.')?;
+    Some(Source::Net(iface.to_string(), NET.iter().position(|entry| entry.name == name)?))
+}
+
+/// `>5.1`, `<8`, `.2` or nothing
+fn parse_spec(spec: &str) -> Option<Spec> {
+    let align = spec.chars().next().filter(|c| matches!(c, '<' | '>' | '^'));
+    let rest = &spec[align.map_or(0, char::len_utf8)..];
+    let (width, precision) = rest.split_once('.').map_or((rest, None), |(width, precision)| (width, Some(precision)));
+    Some(Spec {
+        align,
+        width: if width.is_empty() { 0 } else { width.parse().ok()? },
+        precision: precision.map(str::parse).transpose().ok()?,
+    })
+}
+
+/// Pads to the width, numbers and sizes go right by default like in Rust, text left
+fn pad(text: &str, spec: &Spec) -> String {
+    let fill = spec.width.saturating_sub(text.chars().count());
+    let numeric = text.starts_with(|c: char| c.is_ascii_digit() || c == '-');
+    let (left, right) = match spec.align.unwrap_or(if numeric { '>' } else { '<' }) {
+        '>' => (fill, 0),
+        '^' => (fill / 2, fill - fill / 2),
+        _ => (0, fill),
+    };
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(right))
+}
+
+/// Every key with what it is, for `--list-keys` and unknown key errors
+#[must_use]
+pub fn list_keys() -> String {
+    let rows = MACHINE.iter().map(|key| (key.name.to_string(), key.about))
+        .chain(GPU.iter().map(|key| (format!("gpu<n>.{}", key.name), key.about)))
+        .chain(DISK.iter().map(|key| (format!("disk.<name>.{}", key.name), key.about)))
+        .chain(NET.iter().map(|key| (format!("net.<iface>.{}", key.name), key.about)));
+    rows.map(|(name, about)| format!("  {name:<24} {about}")).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_key_parses() {
+        let listed = list_keys();
+        for line in listed.lines() {
+            let key = line.split_whitespace().next().unwrap().replace("<n>", "0").replace("<name>", "nvme0n1").replace("<iface>", "eth0");
+            assert!(Template::parse(&format!("{{{key}}}")).is_ok(), "{key}");
+        }
+        assert_eq!(listed.lines().count(), MACHINE.len() + GPU.len() + DISK.len() + NET.len());
+    }
+
+    #[test]
+    fn parse_keys_specs_and_braces() {
+        let template = Template::parse("{{cpu}} {cpu.usage:>3}% {disk.dm-0.read_rate:.1}").unwrap();
+        assert_eq!(template.parts, [
+            Part::Text("{cpu} ".to_string()),
+            Part::Key(Source::Machine(0), Spec { align: Some('>'), width: 3, precision: None }),
+            Part::Text("% ".to_string()),
+            Part::Key(Source::Disk("dm-0".to_string(), 2), Spec { align: None, width: 0, precision: Some(1) }),
+        ]);
+        assert!(Template::parse("{cpu.usgae}").unwrap_err().starts_with("unknown key '{cpu.usgae}' in --format, the keys are:\n  cpu.usage"));
+        assert!(Template::parse("{gpu.usage}").is_err());
+        assert!(Template::parse("{cpu.usage:x}").is_err());
+        assert!(Template::parse("cpu}").is_err());
+        assert!(Template::parse("{cpu.usage").is_err());
+    }
+
+    #[test]
+    fn unknown_keys_suggest_near_misses() {
+        assert!(Field::parse("gpu0.vram.used").is_ok());
+        assert_eq!(Field::parse("cpu.temps").err(), Some("unknown key 'cpu.temps', did you mean cpu.temp, gpu0.temp?".to_string()));
+        assert_eq!(Field::parse("gpu1.powr_w").err(), Some("unknown key 'gpu1.powr_w', did you mean gpu1.power_w, cpu.power_w?".to_string()));
+        assert_eq!(Field::parse("battery").err(), Some("unknown key 'battery', hwtop --list-keys lists them".to_string()));
+    }
+
+    #[test]
+    fn pad_aligns() {
+        let spec = |spec: &str| parse_spec(spec).unwrap();
+        assert_eq!(pad("7", &spec("3")), "  7");
+        assert_eq!(pad("eth0", &spec("6")), "eth0  ");
+        assert_eq!(pad("7", &spec("<3")), "7  ");
+        assert_eq!(pad("ab", &spec("^5")), " ab  ");
+        assert_eq!(pad("toolong", &spec(">3")), "toolong");
+    }
+}
@@ -0,0 +1,189 @@
+use std::{fmt::Write as _, io::Write, ops::Range, sync::atomic::{AtomicBool, Ordering}, time::{Duration, Instant}};
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+#[cfg(unix)]
+use unix as sys;
+#[cfg(windows)]
+use windows as sys;
+
+pub use sys::{enable_ansi, width};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static RESIZED: AtomicBool = AtomicBool::new(false);
+/// `--title` changed the title, so `restore` has to put it back
+static TITLED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Turns SIGINT/SIGTERM into a flag checked by the main loop, so it can exit through the normal cleanup path,
+/// and terminal resizes into one that triggers a redraw
+pub fn install_signal_handlers() {
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+    sys::watch_resize();
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Whether the terminal was resized since the last call
+pub fn take_resized() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+/// Sleeps for `duration`, waking up early if a signal arrives, the terminal is resized or a key is pressed in raw mode
+pub fn sleep(duration: Duration) {
+    let end = Instant::now() + duration;
+    let raw = sys::raw_mode();
+    while !interrupted() && !RESIZED.load(Ordering::SeqCst) {
+        let left = end.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        let step = left.min(Duration::from_millis(50));
+        if raw {
+            if sys::wait_for_key(step) {
+                break;
+            }
+        } else {
+            std::thread::sleep(step);
+        }
+    }
+}
+
+/// Key pressed in the live view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    Esc,
+}
+
+/// Keys typed since the last call, never blocks, escape sequences like arrow keys are skipped
+pub fn keys() -> Vec<Key> {
+    // outside raw mode stdin may block or belong to something else
+    if !sys::raw_mode() {
+        return vec![];
+    }
+    sys::keys()
+}
+
+/// Leaves the alternate screen, shows the cursor, puts the title back and leaves raw mode, once, if they were entered
+pub fn restore() {
+    sys::leave_raw_mode();
+    let mut stdout = std::io::stdout();
+    // terminals without a title stack are left with an empty title instead of a stale reading
+    if TITLED.swap(false, Ordering::SeqCst) {
+        let _ = stdout.write_all(b"\x1b]2;\x07\x1b[23;0t");
+    }
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = stdout.write_all(b"\x1b[?1049l\x1b[?25h");
+    }
+    let _ = stdout.flush();
+}
+
+/// Redraws only the lines that changed since the previous frame, in a single write so nothing flickers
+#[derive(Default)]
+pub struct Frames {
+    /// the frame on screen, `lines` are where each of its lines is in it
+    previous: String,
+    lines: Vec<Range<usize>>,
+    /// the escapes of the next write, kept so idle frames don't allocate
+    out: String,
+    title: String,
+}
+
+impl Frames {
+    pub fn draw(&mut self, frame: &str) -> std::io::Result<()> {
+        let out = self.diff(frame);
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()
+    }
+
+    /// What turns the frame on screen into `frame`, which is on screen from then on
+    pub fn diff(&mut self, frame: &str) -> &str {
+        self.out.clear();
+        if self.lines.is_empty() {
+            self.out += "\x1b[H\x1b[2J";
+        }
+        let mut count = 0;
+        for (i, line) in frame.lines().enumerate() {
+            if self.lines.get(i).map(|range| &self.previous[range.clone()]) != Some(line) {
+                let _ = write!(self.out, "\x1b[{};1H{line}\x1b[K", i + 1);
+            }
+            // `previous` becomes a copy of `frame`, so the line is at the same offset there
+            let start = line.as_ptr() as usize - frame.as_ptr() as usize;
+            match self.lines.get_mut(i) {
+                Some(range) => *range = start..start + line.len(),
+                None => self.lines.push(start..start + line.len()),
+            }
+            count = i + 1;
+        }
+        // a shorter frame leaves old lines below it
+        if count < self.lines.len() {
+            let _ = write!(self.out, "\x1b[{};1H\x1b[J", count + 1);
+            self.lines.truncate(count);
+        }
+        self.previous.clear();
+        self.previous += frame;
+        &self.out
+    }
+
+    /// Sets the terminal title with OSC 2 when it changed, the first call saves the old one for `restore`
+    pub fn set_title(&mut self, title: &str) -> std::io::Result<()> {
+        if title == self.title {
+            return Ok(());
+        }
+        self.title = title.to_string();
+        let mut out = String::new();
+        if !TITLED.swap(true, Ordering::SeqCst) {
+            out += "\x1b[22;0t";
+        }
+        // a BEL or ESC would end the sequence early
+        out += &format!("\x1b]2;{}\x07", title.replace(['\x07', '\x1b'], ""));
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()
+    }
+
+    /// Forgets the previous frame so the next one is drawn from scratch
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.lines.clear();
+    }
+}
+
+/// Alternate screen and raw mode guard, the previous screen and terminal settings are restored on drop and on panic
+pub struct Screen(());
+
+impl Screen {
+    pub fn enter() -> Self {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+        ACTIVE.store(true, Ordering::SeqCst);
+        sys::enter_raw_mode();
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(b"\x1b[?1049h\x1b[?25l");
+        let _ = stdout.flush();
+        Self(())
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore();
+    }
+}
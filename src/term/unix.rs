@@ -0,0 +1,81 @@
+use std::{sync::{Mutex, atomic::Ordering}, time::Duration};
+
+use super::{Key, RESIZED};
+
+/// Terminal settings from before raw mode, put back by `leave_raw_mode`
+static COOKED: Mutex<Option<libc::termios>> = Mutex::new(None);
+
+extern "C" fn on_resize(_: libc::c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+/// SIGWINCH sets the resize flag
+pub fn watch_resize() {
+    let handler = on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe { libc::signal(libc::SIGWINCH, handler) };
+}
+
+/// Terminals take escape sequences as they are
+pub fn enable_ansi() -> bool {
+    true
+}
+
+/// Whether a key press arrives within `timeout`
+pub fn wait_for_key(timeout: Duration) -> bool {
+    let mut stdin = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+    unsafe { libc::poll(&mut stdin, 1, timeout.as_millis() as libc::c_int) > 0 && stdin.revents & libc::POLLIN != 0 }
+}
+
+pub fn keys() -> Vec<Key> {
+    let mut buf = [0u8; 64];
+    let read = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+    let input = &buf[..read.max(0) as usize];
+    // a lone ESC is the key, ESC followed by more bytes starts a sequence that runs to its final letter
+    if input == [0x1b] {
+        return vec![Key::Esc];
+    }
+    let mut keys = vec![];
+    let text = String::from_utf8_lossy(input);
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().skip(1).find(|c| c.is_ascii_alphabetic() || *c == '~');
+        } else {
+            keys.push(Key::Char(c));
+        }
+    }
+    keys
+}
+
+pub fn raw_mode() -> bool {
+    COOKED.lock().is_ok_and(|cooked| cooked.is_some())
+}
+
+/// Turns off line buffering and echo on stdin so single key presses can be read with `keys`, Ctrl-C still signals
+pub fn enter_raw_mode() {
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+        return;
+    }
+    let cooked = termios;
+    termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+    // reads return right away with whatever is there
+    termios.c_cc[libc::VMIN] = 0;
+    termios.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) } == 0 && let Ok(mut saved) = COOKED.lock() {
+        *saved = Some(cooked);
+    }
+}
+
+pub fn leave_raw_mode() {
+    if let Some(cooked) = COOKED.lock().ok().and_then(|mut cooked| cooked.take()) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &cooked) };
+    }
+}
+
+/// Terminal columns, `None` when stdout isn't a terminal
+pub fn width() -> Option<usize> {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    (ok && size.ws_col > 0).then_some(size.ws_col as usize)
+}
@@ -0,0 +1,140 @@
+use std::{ffi::c_void, sync::{Mutex, atomic::{AtomicUsize, Ordering}}, time::Duration};
+
+use super::{Key, RESIZED};
+
+type Handle = *mut c_void;
+
+// from consoleapi.h and wincon.h
+const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+const ENABLE_LINE_INPUT: u32 = 0x2;
+const ENABLE_ECHO_INPUT: u32 = 0x4;
+const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x4;
+const KEY_EVENT: u16 = 0x1;
+const WAIT_OBJECT_0: u32 = 0;
+
+/// INPUT_RECORD with the KEY_EVENT_RECORD member of its union, 20 bytes like the real one
+#[repr(C)]
+#[derive(Default)]
+struct InputRecord {
+    event_type: u16,
+    key_down: i32,
+    repeat_count: u16,
+    virtual_key_code: u16,
+    virtual_scan_code: u16,
+    unicode_char: u16,
+    control_key_state: u32,
+}
+
+/// CONSOLE_SCREEN_BUFFER_INFO, coordinates are (x, y) and the window is (left, top, right, bottom)
+#[repr(C)]
+#[derive(Default)]
+struct ScreenBufferInfo {
+    size: [i16; 2],
+    cursor_position: [i16; 2],
+    attributes: u16,
+    window: [i16; 4],
+    maximum_window_size: [i16; 2],
+}
+
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetStdHandle(std_handle: u32) -> Handle;
+    fn GetConsoleMode(console: Handle, mode: *mut u32) -> i32;
+    fn SetConsoleMode(console: Handle, mode: u32) -> i32;
+    fn WaitForSingleObject(handle: Handle, millis: u32) -> u32;
+    fn GetNumberOfConsoleInputEvents(console: Handle, events: *mut u32) -> i32;
+    fn PeekConsoleInputW(console: Handle, buffer: *mut InputRecord, length: u32, read: *mut u32) -> i32;
+    fn ReadConsoleInputW(console: Handle, buffer: *mut InputRecord, length: u32, read: *mut u32) -> i32;
+    fn GetConsoleScreenBufferInfo(console: Handle, info: *mut ScreenBufferInfo) -> i32;
+}
+
+/// Console input mode from before raw mode, put back by `leave_raw_mode`
+static COOKED: Mutex<Option<u32>> = Mutex::new(None);
+/// Console width at the last check, consoles have no resize signal so it's compared while waiting for keys
+static LAST_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+pub fn watch_resize() {
+    LAST_WIDTH.store(width().unwrap_or(0), Ordering::SeqCst);
+}
+
+/// Switches the console to parse escape sequences (Windows 10 and newer), `false` when it's a console that can't,
+/// redirected output is left alone
+pub fn enable_ansi() -> bool {
+    let stdout = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    let mut mode = 0;
+    if unsafe { GetConsoleMode(stdout, &mut mode) } == 0 {
+        return true;
+    }
+    mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0 || unsafe { SetConsoleMode(stdout, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) } != 0
+}
+
+fn is_key_press(record: &InputRecord) -> bool {
+    record.event_type == KEY_EVENT && record.key_down != 0 && record.unicode_char != 0
+}
+
+/// Whether a key press arrives within `timeout`, a resize found meanwhile sets the resize flag
+pub fn wait_for_key(timeout: Duration) -> bool {
+    let width = width().unwrap_or(0);
+    if LAST_WIDTH.swap(width, Ordering::SeqCst) != width {
+        RESIZED.store(true, Ordering::SeqCst);
+    }
+    let stdin = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    if unsafe { WaitForSingleObject(stdin, timeout.as_millis() as u32) } != WAIT_OBJECT_0 {
+        return false;
+    }
+    // key releases, focus and mouse events signal stdin too, they're dropped so only key presses end the wait
+    let (mut record, mut read) = (InputRecord::default(), 0);
+    if unsafe { PeekConsoleInputW(stdin, &mut record, 1, &mut read) } != 0 && read == 1 && is_key_press(&record) {
+        return true;
+    }
+    unsafe { ReadConsoleInputW(stdin, &mut record, 1, &mut read) };
+    false
+}
+
+/// Key presses with a character, arrows and function keys have none and are skipped
+pub fn keys() -> Vec<Key> {
+    let stdin = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    let mut units = vec![];
+    let mut pending = 0;
+    while unsafe { GetNumberOfConsoleInputEvents(stdin, &mut pending) } != 0 && pending > 0 {
+        let (mut record, mut read) = (InputRecord::default(), 0);
+        if unsafe { ReadConsoleInputW(stdin, &mut record, 1, &mut read) } == 0 || read == 0 {
+            break;
+        }
+        if is_key_press(&record) {
+            units.extend(std::iter::repeat_n(record.unicode_char, record.repeat_count.max(1) as usize));
+        }
+    }
+    char::decode_utf16(units).filter_map(Result::ok).map(|c| if c == '\x1b' { Key::Esc } else { Key::Char(c) }).collect()
+}
+
+pub fn raw_mode() -> bool {
+    COOKED.lock().is_ok_and(|cooked| cooked.is_some())
+}
+
+/// Key presses are handed over one at a time without echo, Ctrl-C still signals since processed input stays on
+pub fn enter_raw_mode() {
+    let stdin = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    let mut mode = 0;
+    if unsafe { GetConsoleMode(stdin, &mut mode) } == 0 {
+        return;
+    }
+    if unsafe { SetConsoleMode(stdin, mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT)) } != 0 && let Ok(mut saved) = COOKED.lock() {
+        *saved = Some(mode);
+    }
+}
+
+pub fn leave_raw_mode() {
+    if let Some(mode) = COOKED.lock().ok().and_then(|mut cooked| cooked.take()) {
+        unsafe { SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), mode) };
+    }
+}
+
+/// Console window columns, `None` when stdout isn't a console
+pub fn width() -> Option<usize> {
+    let mut info = ScreenBufferInfo::default();
+    let ok = unsafe { GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) } != 0;
+    let columns = info.window[2] - info.window[0] + 1;
+    (ok && columns > 0).then_some(columns as usize)
+}
@@ -0,0 +1,388 @@
+use std::fmt;
+
+/// What a color is used for, every escape the display prints comes from one of these
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    AccentCpu,
+    AccentGpu,
+    AccentRam,
+    AccentNet,
+    Heading,
+    Info,
+    Rx,
+    Tx,
+    Alert,
+    Dim,
+    BarCold,
+    BarCool,
+    BarWarm,
+    BarHot,
+}
+
+/// Config key of every role under `[colors]`, in `Role` order
+pub const ROLES: [(&str, Role); 14] = [
+    ("accent_cpu", Role::AccentCpu),
+    ("accent_gpu", Role::AccentGpu),
+    ("accent_ram", Role::AccentRam),
+    ("accent_net", Role::AccentNet),
+    ("heading", Role::Heading),
+    ("info", Role::Info),
+    ("rx", Role::Rx),
+    ("tx", Role::Tx),
+    ("alert", Role::Alert),
+    ("dim", Role::Dim),
+    ("bar_cold", Role::BarCold),
+    ("bar_cool", Role::BarCool),
+    ("bar_warm", Role::BarWarm),
+    ("bar_hot", Role::BarHot),
+];
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        ROLES[self as usize].0
+    }
+}
+
+#[must_use]
+pub fn parse_role(name: &str) -> Option<Role> {
+    ROLES.iter().find(|&&(n, _)| n == name).map(|&(_, role)| role)
+}
+
+/// The terminal's own palette by SGR code, so presets follow the user's terminal colors
+const NAMED: [(&str, &str); 18] = [
+    ("bold", "1"),
+    ("dim", "2"),
+    ("black", "30"),
+    ("red", "31"),
+    ("green", "32"),
+    ("yellow", "33"),
+    ("blue", "34"),
+    ("magenta", "35"),
+    ("cyan", "36"),
+    ("white", "37"),
+    ("bright-black", "90"),
+    ("bright-red", "91"),
+    ("bright-green", "92"),
+    ("bright-yellow", "93"),
+    ("bright-blue", "94"),
+    ("bright-magenta", "95"),
+    ("bright-cyan", "96"),
+    ("bright-white", "97"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// the terminal's default foreground
+    None,
+    /// SGR code of one of `NAMED`
+    Named(&'static str),
+    /// 256 color palette index
+    Index(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn escape(self, truecolor: bool) -> String {
+        match self {
+            Color::None => String::new(),
+            Color::Named(code) => format!("\x1b[{code}m"),
+            Color::Index(index) => format!("\x1b[38;5;{index}m"),
+            Color::Rgb(r, g, b) if truecolor => format!("\x1b[38;2;{r};{g};{b}m"),
+            Color::Rgb(r, g, b) => Color::Index(to_256(r, g, b)).escape(false),
+        }
+    }
+}
+
+/// Same form `parse_color` reads
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::None => write!(f, "none"),
+            Color::Named(code) => write!(f, "{}", NAMED.iter().find(|&&(_, c)| c == code).map_or("none", |&(name, _)| name)),
+            Color::Index(index) => write!(f, "{index}"),
+            Color::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+/// `#7aa2f7`, a 256 color index like `196`, a name like `red` or `bright-cyan`, or `none`
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    let err = || format!("invalid color '{value}', expected #rrggbb, a 256 color index like 196, none or one of {}", NAMED.map(|(name, _)| name).join(", "));
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(err()),
+        };
+    }
+    if value == "none" {
+        return Ok(Color::None);
+    }
+    if let Some(&(_, code)) = NAMED.iter().find(|&&(name, _)| name == value) {
+        return Ok(Color::Named(code));
+    }
+    value.parse().map(Color::Index).map_err(|_| err())
+}
+
+/// Nearest color of the 6x6x6 cube or the gray ramp of the 256 color palette
+#[must_use]
+fn to_256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_level = |c: u8| match c {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (c - 35) / 40,
+    };
+    let cube_value = |level: u8| if level == 0 { 0 } else { 55 + level * 40 };
+    let (cr, cg, cb) = (cube_level(r), cube_level(g), cube_level(b));
+    let gray = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+    let gray_level = if gray < 8 { 0 } else { ((gray - 8) / 10).min(23) };
+    let distance = |(x, y, z): (u8, u8, u8)| [(x, r), (y, g), (z, b)].iter().map(|&(a, b)| (a as i32 - b as i32).pow(2)).sum::<i32>();
+    let gray_value = 8 + gray_level * 10;
+    if distance((gray_value, gray_value, gray_value)) < distance((cube_value(cr), cube_value(cg), cube_value(cb))) {
+        232 + gray_level
+    } else {
+        16 + 36 * cr + 6 * cg + cb
+    }
+}
+
+/// `--theme` presets, the config's `[colors]` overrides single roles on top
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Preset {
+    #[default]
+    Default,
+    Mono,
+    Solarized,
+    HighContrast,
+}
+
+pub const PRESETS: [(&str, Preset); 4] = [
+    ("default", Preset::Default),
+    ("mono", Preset::Mono),
+    ("solarized", Preset::Solarized),
+    ("high-contrast", Preset::HighContrast),
+];
+
+impl Preset {
+    pub fn name(self) -> &'static str {
+        PRESETS[self as usize].0
+    }
+
+    /// Colors in `Role` order
+    fn colors(self) -> [Color; 14] {
+        let named = |name: &str| NAMED.iter().find(|&&(n, _)| n == name).map_or(Color::None, |&(_, code)| Color::Named(code));
+        let hex = |value: &str| parse_color(value).unwrap_or(Color::None);
+        match self {
+            Preset::Default => ["green", "magenta", "red", "cyan", "bright-cyan", "bright-blue", "green", "magenta", "red", "dim", "bright-blue", "bright-cyan", "magenta", "red"].map(named),
+            Preset::Mono => ["bold", "bold", "bold", "bold", "bold", "none", "none", "none", "bold", "dim", "none", "none", "bold", "bold"].map(named),
+            Preset::Solarized => ["#859900", "#d33682", "#dc322f", "#2aa198", "#b58900", "#268bd2", "#859900", "#d33682", "#dc322f", "#586e75", "#268bd2", "#2aa198", "#cb4b16", "#dc322f"].map(hex),
+            Preset::HighContrast => ["bright-green", "bright-magenta", "bright-red", "bright-cyan", "bright-yellow", "bright-white", "bright-green", "bright-magenta", "bright-red", "white", "bright-green", "bright-cyan", "bright-yellow", "bright-red"].map(named),
+        }
+    }
+}
+
+pub fn parse_preset(value: &str) -> Result<Preset, String> {
+    PRESETS.iter().find(|&&(name, _)| name == value).map(|&(_, preset)| preset)
+        .ok_or_else(|| format!("invalid theme '{value}', expected {}", PRESETS.map(|(name, _)| name).join(", ")))
+}
+
+/// Knots of the gradient, evenly spaced from 0% to 100%
+const GRADIENT: [(u8, u8, u8); 4] = [(80, 120, 255), (0, 215, 255), (215, 0, 215), (255, 40, 40)];
+
+/// Color `percent` of the way from blue through cyan and magenta to red, out of range values are clamped
+#[must_use]
+pub fn gradient(percent: f32) -> (u8, u8, u8) {
+    let position = percent.clamp(0.0, 100.0) / 100.0 * (GRADIENT.len() - 1) as f32;
+    let i = (position as usize).min(GRADIENT.len() - 2);
+    let t = position - i as f32;
+    let ((r0, g0, b0), (r1, g1, b1)) = (GRADIENT[i], GRADIENT[i + 1]);
+    let lerp = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    (lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
+
+/// Escape sequence of every role, all empty for plain output
+pub struct Theme {
+    escapes: [String; 14],
+    /// escape of every whole percent with gradient bars, empty for the bar_* levels
+    gradient: Vec<String>,
+    reset: &'static str,
+    highlight: &'static str,
+}
+
+impl Theme {
+    /// `truecolor` keeps hex colors as they are, otherwise they're matched to the 256 color palette,
+    /// `gradient` colors bars by their exact percent in 24-bit color instead of the four bar_* levels
+    pub fn new(preset: Preset, overrides: &[(Role, Color)], truecolor: bool, gradient: bool) -> Self {
+        let mut colors = preset.colors();
+        for &(role, color) in overrides {
+            colors[role as usize] = color;
+        }
+        // reverse video and blink for rows with a firing --alert
+        let gradient = match gradient {
+            true => (0..=100).map(|percent| {
+                let (r, g, b) = self::gradient(percent as f32);
+                Color::Rgb(r, g, b).escape(true)
+            }).collect(),
+            false => vec![],
+        };
+        Self { escapes: colors.map(|color| color.escape(truecolor)), gradient, reset: "\x1b[0m", highlight: "\x1b[7;5m" }
+    }
+
+    pub fn plain() -> Self {
+        Self { escapes: Default::default(), gradient: vec![], reset: "", highlight: "" }
+    }
+
+    pub fn get(&self, role: Role) -> &str {
+        &self.escapes[role as usize]
+    }
+
+    pub fn cpu_accent(&self) -> &str {
+        self.get(Role::AccentCpu)
+    }
+
+    pub fn gpu_accent(&self) -> &str {
+        self.get(Role::AccentGpu)
+    }
+
+    pub fn ram_accent(&self) -> &str {
+        self.get(Role::AccentRam)
+    }
+
+    pub fn net_accent(&self) -> &str {
+        self.get(Role::AccentNet)
+    }
+
+    /// Row labels like LOAD, FANS or NETW
+    pub fn heading(&self) -> &str {
+        self.get(Role::Heading)
+    }
+
+    /// Values that aren't colored by their level
+    pub fn info(&self) -> &str {
+        self.get(Role::Info)
+    }
+
+    /// Download and disk reads
+    pub fn rx(&self) -> &str {
+        self.get(Role::Rx)
+    }
+
+    /// Upload and disk writes
+    pub fn tx(&self) -> &str {
+        self.get(Role::Tx)
+    }
+
+    pub fn alert(&self) -> &str {
+        self.get(Role::Alert)
+    }
+
+    pub fn dim(&self) -> &str {
+        self.get(Role::Dim)
+    }
+
+    pub fn reset(&self) -> &'static str {
+        self.reset
+    }
+
+    /// Style of a whole row while one of its alerts fires
+    pub fn highlight(&self) -> &'static str {
+        self.highlight
+    }
+
+    /// Color of level 0 (cold) to 3 (hot)
+    pub fn level(&self, level: usize) -> &str {
+        self.get([Role::BarCold, Role::BarCool, Role::BarWarm, Role::BarHot][level.min(3)])
+    }
+
+    /// Color of a bar or value filled to `percent`
+    pub fn bar(&self, percent: u32) -> &str {
+        if let Some(escape) = self.gradient.get(percent.min(100) as usize) {
+            return escape;
+        }
+        self.level(match percent {
+            0..=25 => 0,
+            26..=50 => 1,
+            51..=75 => 2,
+            _ => 3,
+        })
+    }
+
+    /// Color of a temperature, `thresholds` are where it turns warm, hot and critical
+    pub fn heat(&self, celsius: u32, thresholds: [u32; 3]) -> &str {
+        let [warm, hot, critical] = thresholds;
+        if self.gradient.is_empty() {
+            return self.level(match celsius {
+                c if c >= critical => 3,
+                c if c >= hot => 2,
+                c if c >= warm => 1,
+                _ => 0,
+            });
+        }
+        // the gradient passes its knots at the thresholds, where the levels would switch
+        let knots = [0, warm, hot, critical];
+        let percent = match knots.windows(2).position(|knot| celsius < knot[1]) {
+            Some(i) => (i as f32 + (celsius - knots[i]) as f32 / (knots[i + 1] - knots[i]) as f32) / 3.0 * 100.0,
+            None => 100.0,
+        };
+        self.bar(percent.round() as u32)
+    }
+}
+
+/// Whether the terminal advertises 24-bit color through `COLORTERM`
+#[must_use]
+pub fn truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#7aa2f7"), Ok(Color::Rgb(0x7a, 0xa2, 0xf7)));
+        assert_eq!(parse_color("196"), Ok(Color::Index(196)));
+        assert_eq!(parse_color("bright-cyan"), Ok(Color::Named("96")));
+        assert_eq!(parse_color("none"), Ok(Color::None));
+        for value in ["#7aa2f", "#7aa2fg", "256", "-1", "purple", ""] {
+            assert!(parse_color(value).is_err(), "{value} should not parse");
+        }
+        for value in ["#7aa2f7", "196", "bright-cyan", "none"] {
+            assert_eq!(parse_color(value).map(|color| color.to_string()), Ok(value.to_string()));
+        }
+    }
+
+    #[test]
+    fn gradient_knots_and_between() {
+        assert_eq!(gradient(0.0), GRADIENT[0]);
+        assert_eq!(gradient(100.0 / 3.0), GRADIENT[1]);
+        assert_eq!(gradient(50.0), (108, 108, 235));
+        assert_eq!(gradient(200.0 / 3.0), GRADIENT[2]);
+        assert_eq!(gradient(100.0), GRADIENT[3]);
+        assert_eq!(gradient(-5.0), GRADIENT[0]);
+        assert_eq!(gradient(150.0), GRADIENT[3]);
+        assert_eq!(gradient(f32::INFINITY), GRADIENT[3]);
+    }
+
+    #[test]
+    fn heat_follows_thresholds() {
+        let levels = Theme::new(Preset::Default, &[], false, false);
+        assert_eq!(levels.heat(69, [70, 80, 90]), levels.level(0));
+        assert_eq!(levels.heat(70, [70, 80, 90]), levels.level(1));
+        assert_eq!(levels.heat(95, [70, 80, 90]), levels.level(3));
+        let gradient = Theme::new(Preset::Default, &[], true, true);
+        assert_eq!(gradient.heat(0, [70, 80, 90]), gradient.bar(0));
+        assert_eq!(gradient.heat(80, [70, 80, 90]), gradient.bar(67));
+        assert_eq!(gradient.heat(85, [70, 80, 90]), gradient.bar(83));
+        assert_eq!(gradient.heat(120, [70, 80, 90]), gradient.bar(100));
+    }
+
+    #[test]
+    fn hex_downgrades_to_256() {
+        assert_eq!(Color::Rgb(255, 0, 0).escape(true), "\x1b[38;2;255;0;0m");
+        assert_eq!(Color::Rgb(255, 0, 0).escape(false), "\x1b[38;5;196m");
+        assert_eq!(to_256(0, 0, 0), 16);
+        assert_eq!(to_256(255, 255, 255), 231);
+        assert_eq!(to_256(128, 128, 128), 244);
+        assert_eq!(to_256(0x7a, 0xa2, 0xf7), 111);
+    }
+}
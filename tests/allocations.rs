@@ -0,0 +1,73 @@
+//! Its own test binary, so only this test runs through the counting allocator
+
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell, time::Duration};
+
+use hwtop::alert::Alerts;
+use hwtop::args::Args;
+use hwtop::collect::{Snapshot, cpu::{CoreSample, CpuSample}, memory::MemorySample};
+use hwtop::fmt::Units;
+use hwtop::render::{Style, View};
+use hwtop::term::Frames;
+use hwtop::theme::{Preset, Theme};
+
+/// Counts the allocations of the thread it runs on
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, size) }
+    }
+}
+
+#[global_allocator]
+static COUNTING: Counting = Counting;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn snapshot(usage: f32) -> Snapshot {
+    Snapshot {
+        load: [1.0, 0.5, 0.25],
+        uptime_s: 3600,
+        cpu: CpuSample { usage, temp_c: Some(61), cores: (0..4).map(|_| CoreSample { usage, freq_mhz: 3000, max_freq_mhz: Some(4000), temp_c: None }).collect(), ..Default::default() },
+        memory: MemorySample { ram_used: 8 << 30, ram_total: 16 << 30, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn steady_frames_allocate_little() {
+    let style = Style { theme: Theme::new(Preset::Default, &[], true, true), units: Units::Binary, fahrenheit: false, use_ansi: true, thresholds: vec![], bar_width: None };
+    let (args, alerts) = (Args::default(), Alerts::new(vec![]));
+    let snapshots = [snapshot(42.0), snapshot(58.0)];
+    let mut view = View::new(style, &args, Duration::from_secs(1), false);
+    let (mut frame, mut frames) = (String::new(), Frames::default());
+    // the first frames size the buffers and fill the histories
+    for snapshot in snapshots.iter().cycle().take(4) {
+        view.frame(&mut frame, snapshot, true, &args, &alerts, Some(120)).unwrap();
+        frames.diff(&frame);
+    }
+    let drawn = allocations(|| view.frame(&mut frame, &snapshots[0], true, &args, &alerts, Some(120)).unwrap());
+    // what's left are the sizes and vectors of values formatted anew every frame, nothing per row, cell or bar
+    assert!(drawn <= 37, "{drawn} allocations drawing the frame");
+    // the usage changed, so some lines are redrawn into the buffer of the last write
+    let diffed = allocations(|| assert!(!frames.diff(&frame).is_empty()));
+    assert_eq!(diffed, 0);
+}